@@ -0,0 +1,272 @@
+// Experimental backend for ACIR, the circuit representation Noir compiles to.
+// The opcodes are read from JSON mirroring the ACIR structures, together with
+// the witness map solved by the ACVM, and lowered onto FieldChip:
+// * `arithmetic` asserts sum(q * w_l * w_r) + sum(q * w) + q_c = 0,
+// * `range` is the bit decomposition range check,
+// * `xor` and `and` are lookups in the bitwise table, chunk by chunk, the
+//   operands being range checked to `num_bits` bits.
+// The opcodes are deserialized with serde, so the module is only built with
+// the `serde` feature.
+// Coefficients are given as signed 64 bits integers. For example, x * y = z
+// with z public and x on 8 bits:
+// {
+//   "current_witness_index": 3,
+//   "public_parameters": [3],
+//   "opcodes": [
+//     {"arithmetic": {"mul_terms": [[1, 1, 2]], "linear_combinations": [[-1, 3]], "q_c": 0}},
+//     {"range": {"input": 1, "num_bits": 8}}
+//   ]
+// }
+use super::{from_i64, FieldChip, FieldConfig, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use serde::Deserialize;
+
+// Bit length of the bitwise table, the chunks of `xor` and `and` operands,
+// so the circuit needs at least 5 * 2^8 rows, that is k = 11.
+const XOR_BITLENGTH: usize = 4;
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct Acir {
+    // witnesses are numbered from 1 to current_witness_index
+    current_witness_index: u32,
+    // witnesses read from the instance column, in that order
+    #[serde(default)]
+    public_parameters: Vec<u32>,
+    opcodes: Vec<Opcode>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Opcode {
+    Arithmetic {
+        mul_terms: Vec<(i64, u32, u32)>,
+        linear_combinations: Vec<(i64, u32)>,
+        q_c: i64,
+    },
+    Range {
+        input: u32,
+        num_bits: usize,
+    },
+    Xor {
+        lhs: u32,
+        rhs: u32,
+        output: u32,
+        num_bits: usize,
+    },
+    And {
+        lhs: u32,
+        rhs: u32,
+        output: u32,
+        num_bits: usize,
+    },
+}
+
+pub(crate) struct AcirCircuit<F: FieldExt> {
+    acir: Acir,
+    // values of the witnesses 1..=current_witness_index
+    witness: Vec<Option<F>>,
+}
+
+impl<F: FieldExt> AcirCircuit<F> {
+    pub(crate) fn new(acir: Acir, witness: Vec<Option<F>>) -> Result<Self, Error> {
+        if witness.len() != acir.current_witness_index as usize {
+            return Err(Error::SynthesisError);
+        }
+        Ok(Self { acir, witness })
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for AcirCircuit<F> {
+    type Config = FieldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            acir: self.acir.clone(),
+            witness: vec![None; self.witness.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FieldChip::configure_default(meta, XOR_BITLENGTH)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FieldChip::<F>::construct(config);
+        chip.load_bitwise_table(layouter.namespace(|| "bitwise table"))?;
+
+        let one = chip.load_constant(layouter.namespace(|| "one"), F::one())?;
+        // witnesses[i] holds witness i + 1
+        let mut witnesses = Vec::with_capacity(self.witness.len());
+        for (i, value) in self.witness.iter().enumerate() {
+            let index = i as u32 + 1;
+            let num = match self.acir.public_parameters.iter().position(|w| *w == index) {
+                Some(row) => chip.load_public(layouter.namespace(|| "public witness"), row)?,
+                None => chip.load_private(layouter.namespace(|| "witness"), *value)?,
+            };
+            witnesses.push(num);
+        }
+        let get = |w: u32| -> Result<Number<F>, Error> {
+            match w {
+                0 => Err(Error::SynthesisError),
+                w => witnesses
+                    .get(w as usize - 1)
+                    .cloned()
+                    .ok_or(Error::SynthesisError),
+            }
+        };
+
+        for opcode in self.acir.opcodes.iter() {
+            match opcode {
+                Opcode::Arithmetic {
+                    mul_terms,
+                    linear_combinations,
+                    q_c,
+                } => {
+                    let mut layouter = layouter.namespace(|| "arithmetic");
+                    let mut terms = vec![(from_i64(*q_c), one.clone())];
+                    for (q, l, r) in mul_terms.iter() {
+                        let lr =
+                            chip.mul(layouter.namespace(|| "w_l * w_r"), get(*l)?, get(*r)?)?;
+                        terms.push((from_i64(*q), lr));
+                    }
+                    for (q, w) in linear_combinations.iter() {
+                        terms.push((from_i64(*q), get(*w)?));
+                    }
+                    let acc = chip.linear_combination(layouter.namespace(|| "sum"), &terms)?;
+                    let zero = chip.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+                    chip.assert_equal(layouter.namespace(|| "assert zero"), acc, zero)?;
+                }
+                Opcode::Range { input, num_bits } => {
+                    chip.range_check(layouter.namespace(|| "range"), get(*input)?, *num_bits)?;
+                }
+                Opcode::Xor {
+                    lhs,
+                    rhs,
+                    output,
+                    num_bits,
+                }
+                | Opcode::And {
+                    lhs,
+                    rhs,
+                    output,
+                    num_bits,
+                } => {
+                    let out = get(*output)?;
+                    let (lhs, rhs) = (get(*lhs)?, get(*rhs)?);
+                    // the chunks only bound the operands to a multiple of the
+                    // table bit length
+                    if *num_bits % XOR_BITLENGTH != 0 {
+                        chip.range_check(layouter.namespace(|| "lhs"), lhs.clone(), *num_bits)?;
+                        chip.range_check(layouter.namespace(|| "rhs"), rhs.clone(), *num_bits)?;
+                    }
+                    let res = match opcode {
+                        Opcode::Xor { .. } => {
+                            chip.xor_wide(layouter.namespace(|| "xor"), lhs, rhs, *num_bits)?
+                        }
+                        _ => chip.and_wide(layouter.namespace(|| "and"), lhs, rhs, *num_bits)?,
+                    };
+                    chip.assert_equal(layouter.namespace(|| "bitwise output"), res, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2::{dev::MockProver, pasta::Fp};
+
+    // x * y - z + 1 = 0 with z public, x on 8 bits, w = x ^ y and v = x & y
+    const PROGRAM: &str = r#"{
+        "current_witness_index": 5,
+        "public_parameters": [3],
+        "opcodes": [
+            {"arithmetic": {"mul_terms": [[1, 1, 2]], "linear_combinations": [[-1, 3]], "q_c": 1}},
+            {"range": {"input": 1, "num_bits": 8}},
+            {"xor": {"lhs": 1, "rhs": 2, "output": 4, "num_bits": 4}},
+            {"and": {"lhs": 1, "rhs": 2, "output": 5, "num_bits": 4}}
+        ]
+    }"#;
+
+    fn witness(values: &[u64]) -> Vec<Option<Fp>> {
+        values.iter().map(|v| Some(Fp::from(*v))).collect()
+    }
+
+    #[test]
+    fn lowering() {
+        let acir: Acir = serde_json::from_str(PROGRAM).unwrap();
+        let k = 11;
+        let circuit = AcirCircuit::new(acir.clone(), witness(&[3, 5, 16, 6, 1])).unwrap();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(16)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(15)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // wrong xor output
+        let circuit = AcirCircuit::new(acir.clone(), witness(&[3, 5, 16, 7, 1])).unwrap();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(16)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // wrong and output
+        let circuit = AcirCircuit::new(acir, witness(&[3, 5, 16, 6, 0])).unwrap();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(16)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn range() {
+        let acir: Acir = serde_json::from_str(
+            r#"{
+                "current_witness_index": 1,
+                "opcodes": [{"range": {"input": 1, "num_bits": 8}}]
+            }"#,
+        )
+        .unwrap();
+        let circuit = AcirCircuit::new(acir.clone(), witness(&[255])).unwrap();
+        let prover = MockProver::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let circuit = AcirCircuit::new(acir, witness(&[256])).unwrap();
+        let prover = MockProver::run(11, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn wide() {
+        // w = x ^ y on 8 bits and v = x & y on 6 bits
+        let acir: Acir = serde_json::from_str(
+            r#"{
+                "current_witness_index": 4,
+                "opcodes": [
+                    {"xor": {"lhs": 1, "rhs": 2, "output": 3, "num_bits": 8}},
+                    {"and": {"lhs": 1, "rhs": 2, "output": 4, "num_bits": 6}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let circuit = AcirCircuit::new(acir.clone(), witness(&[45, 54, 27, 36])).unwrap();
+        let prover = MockProver::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // wrong xor output
+        let circuit = AcirCircuit::new(acir.clone(), witness(&[45, 54, 26, 36])).unwrap();
+        let prover = MockProver::run(11, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // x does not fit in 6 bits, though its chunks are in the table
+        let circuit = AcirCircuit::new(acir, witness(&[100, 54, 82, 36])).unwrap();
+        let prover = MockProver::run(11, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,599 @@
+// Unsigned integers of any size over FieldChip, such as RSA moduli. A BigInt
+// is a little-endian vector of limbs of `limb_bits` bits each, every limb
+// being range checked with the running sum, so the bitwise table must be
+// loaded. Additions and schoolbook multiplications propagate their carries
+// limb by limb, splitting each column with `div_pow2`, and modular reduction
+// witnesses the quotient q and the remainder r of a by m before constraining
+// a = q * m + r and r < m.
+// The witness is computed natively on vectors of 32-bit digits.
+use super::{integer::lower_64, FieldChip, FieldConfig, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::Error,
+};
+use std::cmp::Ordering;
+
+// A natural number with little-endian 32-bit digits.
+type Nat = Vec<u32>;
+
+fn nat_bit(a: &[u32], i: usize) -> bool {
+    a.get(i / 32).map_or(false, |d| (d >> (i % 32)) & 1 == 1)
+}
+
+fn nat_from_limbs(limbs: &[u64], limb_bits: usize) -> Nat {
+    let mut a = vec![0; (limbs.len() * limb_bits + 31) / 32];
+    for (j, limb) in limbs.iter().enumerate() {
+        for i in 0..limb_bits {
+            if (limb >> i) & 1 == 1 {
+                let bit = j * limb_bits + i;
+                a[bit / 32] |= 1 << (bit % 32);
+            }
+        }
+    }
+    a
+}
+
+// The `n` limbs of `a`, or None if it does not fit in them.
+fn nat_to_limbs(a: &[u32], limb_bits: usize, n: usize) -> Option<Vec<u64>> {
+    if (n * limb_bits..a.len() * 32).any(|i| nat_bit(a, i)) {
+        return None;
+    }
+    Some(
+        (0..n)
+            .map(|j| {
+                (0..limb_bits)
+                    .filter(|i| nat_bit(a, j * limb_bits + i))
+                    .fold(0, |limb, i| limb | 1 << i)
+            })
+            .collect(),
+    )
+}
+
+fn nat_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    let len = a.len().max(b.len());
+    (0..len)
+        .rev()
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            x.cmp(&y)
+        })
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+// a - b for a >= b.
+fn nat_sub(a: &[u32], b: &[u32]) -> Nat {
+    let mut borrow = 0;
+    a.iter()
+        .enumerate()
+        .map(|(i, x)| {
+            let d = *x as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+            borrow = (d < 0) as i64;
+            (d + (borrow << 32)) as u32
+        })
+        .collect()
+}
+
+fn nat_mul(a: &[u32], b: &[u32]) -> Nat {
+    let mut c = vec![0u64; a.len() + b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let t = c[i + j] + *x as u64 * *y as u64 + carry;
+            c[i + j] = t & 0xffff_ffff;
+            carry = t >> 32;
+        }
+        c[i + b.len()] += carry;
+    }
+    c.into_iter().map(|d| d as u32).collect()
+}
+
+// (a / m, a mod m) by long division, None if m is zero.
+fn nat_divrem(a: &[u32], m: &[u32]) -> Option<(Nat, Nat)> {
+    if m.iter().all(|d| *d == 0) {
+        return None;
+    }
+    let mut q = vec![0; a.len()];
+    let mut r: Nat = vec![0; m.len() + 1];
+    for i in (0..a.len() * 32).rev() {
+        // r <- 2r + bit
+        let mut carry = nat_bit(a, i) as u32;
+        for d in r.iter_mut() {
+            let next = *d >> 31;
+            *d = (*d << 1) | carry;
+            carry = next;
+        }
+        if nat_cmp(&r, m) != Ordering::Less {
+            r = nat_sub(&r, m);
+            q[i / 32] |= 1 << (i % 32);
+        }
+    }
+    Some((q, r))
+}
+
+// a^e mod m on little-endian limbs of `limb_bits` bits, computed natively,
+// None if m is zero.
+pub(crate) fn mod_exp_native(a: &[u64], e: u64, m: &[u64], limb_bits: usize) -> Option<Vec<u64>> {
+    let m_limbs = m.len();
+    let m = nat_from_limbs(m, limb_bits);
+    let (_, base) = nat_divrem(&nat_from_limbs(a, limb_bits), &m)?;
+    let mut acc = nat_divrem(&[1], &m)?.1;
+    for i in (0..64).rev() {
+        acc = nat_divrem(&nat_mul(&acc, &acc), &m)?.1;
+        if (e >> i) & 1 == 1 {
+            acc = nat_divrem(&nat_mul(&acc, &base), &m)?.1;
+        }
+    }
+    nat_to_limbs(&acc, limb_bits, m_limbs)
+}
+
+#[derive(Clone)]
+pub(crate) struct BigInt<F: FieldExt> {
+    limbs: Vec<Number<F>>,
+}
+
+impl<F: FieldExt> BigInt<F> {
+    pub(crate) fn limbs(&self) -> &[Number<F>] {
+        &self.limbs
+    }
+
+    // The value of the integer, None while the witness is unknown.
+    fn value(&self, limb_bits: usize) -> Option<Nat> {
+        self.limbs
+            .iter()
+            .map(|limb| limb.value.map(|v| lower_64(&v)))
+            .collect::<Option<Vec<u64>>>()
+            .map(|limbs| nat_from_limbs(&limbs, limb_bits))
+    }
+}
+
+pub(crate) trait BigIntInstructions<F: FieldExt> {
+    /// Loads a private integer with the little-endian limbs `limbs`.
+    fn load(&self, layouter: impl Layouter<F>, limbs: &[Option<u64>]) -> Result<BigInt<F>, Error>;
+
+    /// Returns `a + b`, with one more limb than the longest operand.
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error>;
+
+    /// Returns `a * b`, with as many limbs as both operands together.
+    fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error>;
+
+    /// Returns `a mod m`, with as many limbs as `m`. Fails to synthesize if
+    /// `m` is zero.
+    fn reduce(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        m: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error>;
+
+    /// Returns `a * b mod m`.
+    fn mod_mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+        m: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error> {
+        let ab = self.mul(layouter.namespace(|| "a * b"), a, b)?;
+        self.reduce(layouter.namespace(|| "mod m"), &ab, m)
+    }
+
+    /// Returns `a^e mod m` for the public exponent `e`, by square and
+    /// multiply.
+    fn mod_exp(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        e: u64,
+        m: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error>;
+
+    /// Constrains `a` and `b` to be equal, the missing limbs of the shortest
+    /// one being zero.
+    fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+    ) -> Result<(), Error>;
+}
+
+// Big integer arithmetic laid out by a FieldChip on limbs of `limb_bits`
+// bits, at most 64.
+pub(crate) struct BigIntChip<F: FieldExt> {
+    field: FieldChip<F>,
+    limb_bits: usize,
+}
+
+impl<F: FieldExt> BigIntChip<F> {
+    // Fails if `limb_bits` is zero or above 64.
+    pub(crate) fn construct(config: FieldConfig, limb_bits: usize) -> Result<Self, Error> {
+        if limb_bits == 0 || limb_bits > 64 {
+            return Err(Error::SynthesisError);
+        }
+        Ok(Self {
+            field: FieldChip::construct(config),
+            limb_bits,
+        })
+    }
+
+    // Loads the limbs of `value`, None while the witness is unknown, on `n`
+    // limbs. Fails to synthesize if it does not fit.
+    fn assign_nat(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<Nat>,
+        n: usize,
+    ) -> Result<BigInt<F>, Error> {
+        let limbs = match value {
+            Some(value) => nat_to_limbs(&value, self.limb_bits, n)
+                .ok_or(Error::SynthesisError)?
+                .into_iter()
+                .map(Some)
+                .collect(),
+            None => vec![None; n],
+        };
+        self.load(layouter.namespace(|| "limbs"), &limbs)
+    }
+
+    // Constrains a < m, both of the same limb width.
+    pub(crate) fn assert_lt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        m: &BigInt<F>,
+    ) -> Result<(), Error> {
+        // m - a - 1 is a natural number, so a + (m - a - 1) + 1 = m
+        let d = match (a.value(self.limb_bits), m.value(self.limb_bits)) {
+            (Some(a), Some(m)) => {
+                if nat_cmp(&a, &m) != Ordering::Less {
+                    return Err(Error::SynthesisError);
+                }
+                Some(nat_sub(&nat_sub(&m, &a), &[1]))
+            }
+            _ => None,
+        };
+        let d = self.assign_nat(layouter.namespace(|| "m - a - 1"), d, m.limbs.len())?;
+        let one = BigInt {
+            limbs: vec![self
+                .field
+                .load_constant(layouter.namespace(|| "one"), F::one())?],
+        };
+        let sum = self.add(layouter.namespace(|| "a + d"), a, &d)?;
+        let sum = self.add(layouter.namespace(|| "a + d + 1"), &sum, &one)?;
+        self.assert_equal(layouter.namespace(|| "m"), &sum, m)
+    }
+}
+
+impl<F: FieldExt> Chip<F> for BigIntChip<F> {
+    type Config = FieldConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self.field.config()
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> BigIntInstructions<F> for BigIntChip<F> {
+    fn load(
+        &self,
+        mut layouter: impl Layouter<F>,
+        limbs: &[Option<u64>],
+    ) -> Result<BigInt<F>, Error> {
+        let limbs = limbs
+            .iter()
+            .map(|limb| {
+                let limb = self
+                    .field
+                    .load_private(layouter.namespace(|| "limb"), limb.map(F::from))?;
+                self.field.range_check_running_sum(
+                    layouter.namespace(|| "range"),
+                    limb.clone(),
+                    self.limb_bits,
+                )?;
+                Ok(limb)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(BigInt { limbs })
+    }
+
+    fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error> {
+        let one = F::one();
+        let mut limbs = vec![];
+        let mut carry: Option<Number<F>> = None;
+        for i in 0..a.limbs.len().max(b.limbs.len()) {
+            let terms: Vec<(F, Number<F>)> = a
+                .limbs
+                .get(i)
+                .iter()
+                .chain(b.limbs.get(i).iter())
+                .chain(carry.as_ref().iter())
+                .map(|n| (one, (*n).clone()))
+                .collect();
+            let sum = self
+                .field
+                .linear_combination(layouter.namespace(|| "column"), &terms)?;
+            // the sum of two limbs and a carry bit fits in limb_bits + 1 bits
+            let (high, low) = self.field.div_pow2(
+                layouter.namespace(|| "carry"),
+                sum,
+                self.limb_bits,
+                self.limb_bits + 1,
+            )?;
+            limbs.push(low);
+            carry = Some(high);
+        }
+        limbs.extend(carry);
+        Ok(BigInt { limbs })
+    }
+
+    fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error> {
+        let (n, m) = (a.limbs.len(), b.limbs.len());
+        if n == 0 || m == 0 {
+            return Err(Error::SynthesisError);
+        }
+        // a column and the incoming carry are below 2 min(n, m) 2^(2 limb_bits)
+        let shortest = n.min(m);
+        let log = (0..).find(|l| 1 << l >= shortest).unwrap();
+        let column_bits = 2 * self.limb_bits + log + 1;
+        if column_bits > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+
+        let one = F::one();
+        let mut limbs = vec![];
+        let mut carry: Option<Number<F>> = None;
+        for k in 0..n + m - 1 {
+            let range = k.saturating_sub(m - 1)..=k.min(n - 1);
+            let xs: Vec<Number<F>> = range.clone().map(|i| a.limbs[i].clone()).collect();
+            let ys: Vec<Number<F>> = range.map(|i| b.limbs[k - i].clone()).collect();
+            let mut column = self
+                .field
+                .inner_product(layouter.namespace(|| "column"), &xs, &ys)?;
+            if let Some(carry) = carry {
+                column = self.field.linear_combination(
+                    layouter.namespace(|| "column + carry"),
+                    &[(one, column), (one, carry)],
+                )?;
+            }
+            let (high, low) = self.field.div_pow2(
+                layouter.namespace(|| "carry"),
+                column,
+                self.limb_bits,
+                column_bits,
+            )?;
+            limbs.push(low);
+            carry = Some(high);
+        }
+        // the product fits in n + m limbs, so the last carry is a limb
+        let carry = carry.unwrap();
+        self.field.range_check_running_sum(
+            layouter.namespace(|| "last limb"),
+            carry.clone(),
+            self.limb_bits,
+        )?;
+        limbs.push(carry);
+        Ok(BigInt { limbs })
+    }
+
+    fn reduce(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        m: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error> {
+        let qr = match (a.value(self.limb_bits), m.value(self.limb_bits)) {
+            (Some(a), Some(m)) => Some(nat_divrem(&a, &m).ok_or(Error::SynthesisError)?),
+            _ => None,
+        };
+        let q = self.assign_nat(
+            layouter.namespace(|| "q"),
+            qr.as_ref().map(|(q, _)| q.clone()),
+            a.limbs.len(),
+        )?;
+        let r = self.assign_nat(
+            layouter.namespace(|| "r"),
+            qr.map(|(_, r)| r),
+            m.limbs.len(),
+        )?;
+        self.assert_lt(layouter.namespace(|| "r < m"), &r, m)?;
+        let qm = self.mul(layouter.namespace(|| "q * m"), &q, m)?;
+        let sum = self.add(layouter.namespace(|| "q * m + r"), &qm, &r)?;
+        self.assert_equal(layouter.namespace(|| "a"), &sum, a)?;
+        Ok(r)
+    }
+
+    fn mod_exp(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        e: u64,
+        m: &BigInt<F>,
+    ) -> Result<BigInt<F>, Error> {
+        if e == 0 {
+            let one = BigInt {
+                limbs: vec![self
+                    .field
+                    .load_constant(layouter.namespace(|| "one"), F::one())?],
+            };
+            return self.reduce(layouter.namespace(|| "1 mod m"), &one, m);
+        }
+        let base = self.reduce(layouter.namespace(|| "a mod m"), a, m)?;
+        let mut acc = base.clone();
+        for i in (0..63 - e.leading_zeros() as usize).rev() {
+            acc = self.mod_mul(layouter.namespace(|| "square"), &acc, &acc, m)?;
+            if (e >> i) & 1 == 1 {
+                acc = self.mod_mul(layouter.namespace(|| "multiply"), &acc, &base, m)?;
+            }
+        }
+        Ok(acc)
+    }
+
+    fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigInt<F>,
+        b: &BigInt<F>,
+    ) -> Result<(), Error> {
+        let zero = self
+            .field
+            .load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        for i in 0..a.limbs.len().max(b.limbs.len()) {
+            let x = a.limbs.get(i).unwrap_or(&zero).clone();
+            let y = b.limbs.get(i).unwrap_or(&zero).clone();
+            self.field
+                .assert_equal(layouter.namespace(|| "limb"), x, y)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use halo2::pasta::Fp;
+
+    const LIMB_BITS: usize = 8;
+    const LIMBS: usize = 3;
+
+    fn nat_add(a: &[u32], b: &[u32]) -> Nat {
+        let mut carry = 0;
+        let mut c: Nat = (0..a.len().max(b.len()))
+            .map(|i| {
+                let t = a.get(i).copied().unwrap_or(0) as u64
+                    + b.get(i).copied().unwrap_or(0) as u64
+                    + carry;
+                carry = t >> 32;
+                t as u32
+            })
+            .collect();
+        c.push(carry as u32);
+        c
+    }
+
+    fn field(a: &[u32], n: usize) -> Vec<Fp> {
+        nat_to_limbs(a, LIMB_BITS, n)
+            .unwrap()
+            .into_iter()
+            .map(Fp::from)
+            .collect()
+    }
+
+    fn sample(a: u64, b: u64) -> Vec<Fp> {
+        let mut inputs = field(&[a as u32], LIMBS);
+        inputs.extend(field(&[b as u32], LIMBS));
+        inputs
+    }
+
+    // [a, b] -> [a + b, a * b, a^5 mod b] on 3 limbs of 8 bits
+    #[derive(Clone, Debug)]
+    struct Arith;
+
+    const E: u64 = 5;
+
+    impl TestableGadget<Fp> for Arith {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            vec![
+                sample(0x12_3456, 0xab_cdef),
+                sample(0xff_ffff, 0xff_ffff),
+                sample(7, 1),
+                sample(0, 0x10_0001),
+            ]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            // a zero modulus, then a limb out of range
+            let mut wide = sample(1, 3);
+            wide[0] = Fp::from(256);
+            vec![sample(5, 0), wide]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let a_limbs: Vec<u64> = inputs[..LIMBS].iter().map(lower_64).collect();
+            let b_limbs: Vec<u64> = inputs[LIMBS..].iter().map(lower_64).collect();
+            let a = nat_from_limbs(&a_limbs, LIMB_BITS);
+            let b = nat_from_limbs(&b_limbs, LIMB_BITS);
+            let mut outputs = field(&nat_add(&a, &b), LIMBS + 1);
+            outputs.extend(field(&nat_mul(&a, &b), 2 * LIMBS));
+            let exp = mod_exp_native(&a_limbs, E, &b_limbs, LIMB_BITS)
+                .map(|exp| nat_from_limbs(&exp, LIMB_BITS))
+                .unwrap_or_default();
+            outputs.extend(field(&exp, LIMBS));
+            outputs
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let chip = BigIntChip::construct(chip.config().clone(), LIMB_BITS)?;
+            let values: Vec<Option<u64>> = inputs
+                .iter()
+                .map(|a| a.value.map(|v| lower_64(&v)))
+                .collect();
+            let a = chip.load(layouter.namespace(|| "a"), &values[..LIMBS])?;
+            let b = chip.load(layouter.namespace(|| "b"), &values[LIMBS..])?;
+            for (limb, input) in a.limbs.iter().chain(b.limbs.iter()).zip(inputs.iter()) {
+                chip.field.assert_equal(
+                    layouter.namespace(|| "input"),
+                    limb.clone(),
+                    input.clone(),
+                )?;
+            }
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let product = chip.mul(layouter.namespace(|| "a * b"), &a, &b)?;
+            let exp = chip.mod_exp(layouter.namespace(|| "a^e mod b"), &a, E, &b)?;
+            Ok(sum
+                .limbs
+                .into_iter()
+                .chain(product.limbs)
+                .chain(exp.limbs)
+                .collect())
+        }
+    }
+
+    #[test]
+    fn divrem() {
+        let a = vec![0xffff_ffff, 0x1234];
+        let m = vec![0x10, 0x1];
+        let (q, r) = nat_divrem(&a, &m).unwrap();
+        assert_eq!(nat_cmp(&r, &m), Ordering::Less);
+        assert_eq!(nat_cmp(&nat_add(&nat_mul(&q, &m), &r), &a), Ordering::Equal);
+        assert!(nat_divrem(&a, &[0]).is_none());
+    }
+
+    #[test]
+    fn arith() {
+        // a reduction and three modular multiplications, about 2^12 rows
+        check(&Arith, 13);
+    }
+}
@@ -0,0 +1,135 @@
+// Bytes over FieldChip, for hashing and serialization gadgets. A Byte is a
+// Number looked up in the byte table, so it is only obtained by loading or
+// checking a value against the table, or by splitting a Number with
+// `to_bytes`. The byte table must be loaded once with `load_byte_table`
+// before any byte is looked up.
+use super::{pow2, FieldChip, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::Error,
+};
+
+#[derive(Clone)]
+pub(crate) struct Byte<F: FieldExt>(Number<F>);
+
+impl<F: FieldExt> Byte<F> {
+    pub(crate) fn num(&self) -> Number<F> {
+        self.0.clone()
+    }
+}
+
+pub(crate) trait ByteInstructions<F: FieldExt> {
+    /// Loads the values 0 to 255 in the byte table.
+    fn load_byte_table(&self, layouter: impl Layouter<F>) -> Result<(), Error>;
+
+    /// Loads a private byte.
+    fn load_byte(&self, layouter: impl Layouter<F>, value: Option<u8>) -> Result<Byte<F>, Error>;
+
+    /// Constrains `a` to be a byte and returns it as such.
+    fn byte(&self, layouter: impl Layouter<F>, a: Number<F>) -> Result<Byte<F>, Error>;
+
+    /// Returns the `n` little-endian bytes of `a`, which must fit in `n`
+    /// bytes. `n` must be less than 32 so that the bytes determine `a`.
+    fn to_bytes(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        n: usize,
+    ) -> Result<Vec<Byte<F>>, Error>;
+
+    /// Returns the number with the little-endian bytes `bytes`.
+    fn from_bytes(&self, layouter: impl Layouter<F>, bytes: &[Byte<F>])
+        -> Result<Number<F>, Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Assigns `value` in advices[0], looking it up in the byte table.
+    fn assign_byte(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<Byte<F>, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "byte",
+            |mut region| {
+                config.sbyte.enable(&mut region, 0)?;
+                let cell = region.assign_advice(
+                    || "byte",
+                    config.advices[0],
+                    0,
+                    || value.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Byte(Number { cell, value }));
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+}
+
+impl<F: FieldExt> ByteInstructions<F> for FieldChip<F> {
+    fn load_byte_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let config = self.config();
+        layouter.assign_table(
+            || "byte table",
+            |mut table| {
+                for byte in 0..256 {
+                    table.assign_cell(
+                        || "byte",
+                        config.byte_table,
+                        byte,
+                        || Ok(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn load_byte(&self, layouter: impl Layouter<F>, value: Option<u8>) -> Result<Byte<F>, Error> {
+        self.assign_byte(layouter, value.map(|v| F::from(v as u64)))
+    }
+
+    fn byte(&self, mut layouter: impl Layouter<F>, a: Number<F>) -> Result<Byte<F>, Error> {
+        let byte = self.assign_byte(layouter.namespace(|| "byte"), a.value)?;
+        self.assert_equal(layouter.namespace(|| "a"), a, byte.num())?;
+        Ok(byte)
+    }
+
+    fn to_bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        n: usize,
+    ) -> Result<Vec<Byte<F>>, Error> {
+        if n >= 32 {
+            return Err(Error::SynthesisError);
+        }
+        let repr = a.value.map(|a| a.to_bytes());
+        let bytes = (0..n)
+            .map(|i| {
+                let value = repr.map(|repr| F::from(repr[i] as u64));
+                self.assign_byte(layouter.namespace(|| "byte"), value)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let sum = self.from_bytes(layouter.namespace(|| "recompose"), &bytes)?;
+        self.assert_equal(layouter.namespace(|| "a"), a, sum)?;
+        Ok(bytes)
+    }
+
+    fn from_bytes(
+        &self,
+        layouter: impl Layouter<F>,
+        bytes: &[Byte<F>],
+    ) -> Result<Number<F>, Error> {
+        let terms: Vec<(F, Number<F>)> = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| (pow2(8 * i), byte.num()))
+            .collect();
+        self.linear_combination(layouter, &terms)
+    }
+}
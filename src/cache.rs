@@ -0,0 +1,108 @@
+// On-disk cache of the proving parameters, which are slow to generate for
+// large k. The parameters for k are stored in `params-<k>.bin` in the cache
+// directory, prefixed with the BLAKE2b digest of their encoding: a file whose
+// digest does not match, being truncated or corrupted, is regenerated.
+// Files are written to a temporary path first and renamed, so that a reader
+// never sees a partial file.
+// Proving keys are not cached: the halo2 version used cannot serialize them.
+use super::proof::setup;
+use halo2::{pasta::EqAffine, poly::commitment::Params};
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+const DIGEST_LEN: usize = 32;
+
+fn digest(bytes: &[u8]) -> [u8; DIGEST_LEN] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(DIGEST_LEN)
+        .personal(b"halo2tuto_params")
+        .hash(bytes);
+    let mut out = [0u8; DIGEST_LEN];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+pub(crate) struct ParamsCache {
+    dir: PathBuf,
+}
+
+impl ParamsCache {
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, k: u32) -> PathBuf {
+        self.dir.join(format!("params-{}.bin", k))
+    }
+
+    // The cached parameters for k, None if they are missing or fail the
+    // integrity check.
+    fn load(&self, k: u32) -> Option<Params<EqAffine>> {
+        let mut bytes = vec![];
+        fs::File::open(self.path(k))
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .ok()?;
+        if bytes.len() < DIGEST_LEN || bytes[..DIGEST_LEN] != digest(&bytes[DIGEST_LEN..]) {
+            return None;
+        }
+        Params::read(&bytes[DIGEST_LEN..]).ok()
+    }
+
+    fn store(&self, k: u32, params: &Params<EqAffine>) -> io::Result<()> {
+        let mut encoding = vec![];
+        params.write(&mut encoding)?;
+        let mut bytes = digest(&encoding).to_vec();
+        bytes.extend(encoding);
+        fs::create_dir_all(&self.dir)?;
+        let tmp = self.dir.join(format!("params-{}.bin.tmp", k));
+        fs::write(&tmp, bytes)?;
+        fs::rename(tmp, self.path(k))
+    }
+
+    // The parameters for circuits of at most 2^k rows, read from the cache or
+    // generated and cached.
+    pub(crate) fn params(&self, k: u32) -> io::Result<Params<EqAffine>> {
+        if let Some(params) = self.load(k) {
+            return Ok(params);
+        }
+        let params = setup(k);
+        self.store(k, &params)?;
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(params: &Params<EqAffine>) -> Vec<u8> {
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn params() {
+        let dir = std::env::temp_dir().join(format!("halo2-tuto-cache-{}", std::process::id()));
+        let cache = ParamsCache::new(&dir);
+        let k = 4;
+        let fresh = encode(&cache.params(k).unwrap());
+        assert_eq!(encode(&cache.load(k).unwrap()), fresh);
+        assert_eq!(encode(&cache.params(k).unwrap()), fresh);
+
+        // a corrupted file is regenerated
+        let path = cache.path(k);
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(cache.load(k).is_none());
+        assert_eq!(encode(&cache.params(k).unwrap()), fresh);
+        assert!(cache.load(k).is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+// Cost model of circuits over FieldChip, to guide optimizations: the rows
+// used by each kind of region, the columns, selectors and lookups of the
+// constraint system, and the copy constraints of the permutation argument.
+// The circuit is synthesized on the coverage recorder, regions being grouped
+// by name in order of first assignment.
+use super::{
+    coverage::{estimate_k, gates, record_circuit},
+    FieldConfig,
+};
+use halo2::{
+    arithmetic::FieldExt,
+    plonk::{Circuit, Error},
+};
+use std::fmt;
+
+#[derive(Debug)]
+pub(crate) struct CostReport {
+    // region name, times it was assigned and total rows
+    regions: Vec<(String, usize, usize)>,
+    k: u32,
+    advice_columns: usize,
+    fixed_columns: usize,
+    instance_columns: usize,
+    selectors: usize,
+    lookups: usize,
+    permutation_pairs: usize,
+}
+
+impl CostReport {
+    // Synthesizes `circuit` with the given instance columns and measures it.
+    pub(crate) fn measure<F: FieldExt, C: Circuit<F, Config = FieldConfig>>(
+        circuit: &C,
+        instances: &[Vec<F>],
+    ) -> Result<Self, Error> {
+        let (config, recorder) = record_circuit(circuit, instances)?;
+        let mut regions: Vec<(String, usize, usize)> = vec![];
+        for (name, span) in recorder.regions.iter() {
+            let rows = span.map_or(0, |(first, last)| last - first + 1);
+            match regions.iter_mut().find(|(n, _, _)| n == name) {
+                Some((_, count, total)) => {
+                    *count += 1;
+                    *total += rows;
+                }
+                None => regions.push((name.clone(), 1, rows)),
+            }
+        }
+
+        Ok(Self {
+            regions,
+            k: estimate_k(circuit, instances)?,
+            advice_columns: config.advice_columns().len(),
+            fixed_columns: config.fixed_columns().len() + config.table_columns().len(),
+            // FieldConfig has the single instance column
+            instance_columns: 1,
+            selectors: gates(&config).len(),
+            lookups: config.lookups,
+            permutation_pairs: recorder.copies,
+        })
+    }
+}
+
+impl fmt::Display for CostReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .regions
+            .iter()
+            .map(|(name, _, _)| name.len())
+            .chain(Some("region".len()))
+            .max()
+            .unwrap();
+        writeln!(
+            f,
+            "{:<w$} {:>8} {:>8}",
+            "region",
+            "count",
+            "rows",
+            w = width
+        )?;
+        for (name, count, rows) in self.regions.iter() {
+            writeln!(f, "{:<w$} {:>8} {:>8}", name, count, rows, w = width)?;
+        }
+        writeln!(f, "k: {}", self.k)?;
+        writeln!(
+            f,
+            "columns: {} advice, {} fixed (tables included), {} instance",
+            self.advice_columns, self.fixed_columns, self.instance_columns
+        )?;
+        writeln!(f, "selectors: {}", self.selectors)?;
+        writeln!(f, "lookups: {}", self.lookups)?;
+        write!(f, "permutation pairs: {}", self.permutation_pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GadgetCircuit;
+    use crate::vectors::Gadget;
+    use halo2::pasta::Fp;
+
+    #[test]
+    fn measure() {
+        let circuit = GadgetCircuit::new(Gadget::Mul, vec![Some(Fp::from(6)), Some(Fp::from(7))]);
+        let report = CostReport::measure(&circuit, &[vec![Fp::from(42)]]).unwrap();
+        assert_eq!(report.k, 11);
+        // the columns of FieldConfig, the table columns being fixed
+        assert_eq!(report.advice_columns, 3);
+        assert_eq!(report.fixed_columns, 15);
+        assert_eq!(report.instance_columns, 1);
+        assert_eq!(report.selectors, 34);
+        // bitwise, running sum, byte and set lookups
+        assert_eq!(report.lookups, 4);
+        let mul = report
+            .regions
+            .iter()
+            .find(|(name, _, _)| name == "mul")
+            .unwrap();
+        assert_eq!((mul.1, mul.2), (1, 2));
+        // the inputs copied in the mul region and the product exposed
+        assert_eq!(report.permutation_pairs, 3);
+    }
+}
@@ -0,0 +1,484 @@
+// Constraint coverage of FieldChip over a set of circuits, such as a test
+// suite: how many rows each gate was enabled on, the gates that never were,
+// and which entries of each lookup table were looked up.
+// MockProver does not expose its assignment, so the circuits are synthesized
+// on a Recorder keeping only the enabled selectors and the cell values.
+use super::{pow2, BitwiseOp, FieldConfig};
+use halo2::{
+    arithmetic::FieldExt,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// The gates of the chip, by the selector enabling them. The generic gate is
+// enabled by its coefficients rather than a selector, so coverage counts it
+// apart, on the rows with a nonzero coefficient.
+pub(crate) fn gates(config: &FieldConfig) -> Vec<(&'static str, Selector)> {
+    vec![
+        ("mul", config.smul),
+        ("add", config.sadd),
+        ("running product", config.sprod),
+        ("barycentric sum", config.sbary),
+        ("butterfly", config.sbfly),
+        ("bit decomposition", config.sbits),
+        ("checked add", config.scadd),
+        ("checked mul", config.scmul),
+        ("div by 2^k", config.sdivp2),
+        ("inner product", config.sdot),
+        ("less than", config.slt),
+        ("lexicographic order", config.slex),
+        ("invert", config.sinv),
+        ("mul add", config.smuladd),
+        ("neg", config.sneg),
+        ("square", config.ssquare),
+        ("linear combination", config.slc),
+        ("add constant", config.saddc),
+        ("mul constant", config.smulc),
+        ("is zero", config.siszero),
+        ("invert or zero", config.sinvz),
+        ("accumulate", config.saccum),
+        ("not", config.snot),
+        ("mimc round", config.smimc),
+        ("feistel round", config.sfeistel),
+        ("xor lookup", config.stable),
+        ("and lookup", config.sand),
+        ("or lookup", config.sor),
+        ("nand lookup", config.snand),
+        ("xnor lookup", config.sxnor),
+        ("running sum lookup", config.srunsum),
+        ("byte lookup", config.sbyte),
+        ("set membership lookup", config.smember),
+        ("set gap lookup", config.sgap),
+    ]
+}
+
+// Keeps the parts of the assignment relevant to coverage and cost.
+pub(crate) struct Recorder<'a, F: FieldExt> {
+    instances: &'a [Vec<F>],
+    selectors: Vec<(Selector, usize)>,
+    advice: Vec<(Column<Advice>, usize, F)>,
+    fixed: Vec<(Column<Fixed>, usize, F)>,
+    // rows used by the fixed columns, the tables included
+    fixed_rows: usize,
+    // regions in assignment order, with their first and last rows if any
+    pub(crate) regions: Vec<(String, Option<(usize, usize)>)>,
+    in_region: bool,
+    // number of copy constraints
+    pub(crate) copies: usize,
+}
+
+impl<'a, F: FieldExt> Recorder<'a, F> {
+    // Records the use of `row` by the current region.
+    fn touch(&mut self, row: usize) {
+        if !self.in_region {
+            return;
+        }
+        if let Some((_, span)) = self.regions.last_mut() {
+            *span = Some(span.map_or((row, row), |(first, last)| (first.min(row), last.max(row))));
+        }
+    }
+}
+
+impl<'a, F: FieldExt> Assignment<F> for Recorder<'a, F> {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.regions.push((name().into(), None));
+        self.in_region = true;
+    }
+
+    fn exit_region(&mut self) {
+        self.in_region = false;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.selectors.push((*selector, row));
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Option<F>, Error> {
+        self.instances
+            .get(column.index())
+            .and_then(|values| values.get(row))
+            .map(|v| Some(*v))
+            .ok_or(Error::SynthesisError)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = to()?.into().evaluate();
+        self.advice.push((column, row, value));
+        self.touch(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = to()?.into().evaluate();
+        self.fixed.push((column, row, value));
+        self.fixed_rows = self.fixed_rows.max(row + 1);
+        self.touch(row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        self.copies += 1;
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Option<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+// Synthesizes `circuit` on a Recorder, returning it along with the config.
+pub(crate) fn record_circuit<'a, F: FieldExt, C: Circuit<F, Config = FieldConfig>>(
+    circuit: &C,
+    instances: &'a [Vec<F>],
+) -> Result<(FieldConfig, Recorder<'a, F>), Error> {
+    let mut meta = ConstraintSystem::default();
+    let config = C::configure(&mut meta);
+    let mut recorder = Recorder {
+        instances,
+        selectors: vec![],
+        advice: vec![],
+        fixed: vec![],
+        fixed_rows: 0,
+        regions: vec![],
+        in_region: false,
+        copies: 0,
+    };
+    C::FloorPlanner::synthesize(
+        &mut recorder,
+        circuit,
+        config.clone(),
+        vec![config.constant],
+    )?;
+    Ok((config, recorder))
+}
+
+// Synthesizes `circuit` and returns the number of rows used by its advice
+// cells.
+pub(crate) fn rows_used<F: FieldExt, C: Circuit<F, Config = FieldConfig>>(
+    circuit: &C,
+    instances: &[Vec<F>],
+) -> Result<usize, Error> {
+    let (_, recorder) = record_circuit(circuit, instances)?;
+    Ok(recorder
+        .advice
+        .iter()
+        .map(|(_, row, _)| row + 1)
+        .max()
+        .unwrap_or(0))
+}
+
+// The smallest k such that `circuit` fits in 2^k rows: its advice and fixed
+// cells, the tables included, its enabled selectors and its instances must
+// all stay clear of the last rows, which halo2 reserves for blinding.
+pub(crate) fn estimate_k<F: FieldExt, C: Circuit<F, Config = FieldConfig>>(
+    circuit: &C,
+    instances: &[Vec<F>],
+) -> Result<u32, Error> {
+    let (_, recorder) = record_circuit(circuit, instances)?;
+    let rows = recorder
+        .advice
+        .iter()
+        .map(|(_, row, _)| row + 1)
+        .chain(recorder.selectors.iter().map(|(_, row)| row + 1))
+        .chain(instances.iter().map(|values| values.len()))
+        .fold(recorder.fixed_rows, usize::max);
+    let mut meta = ConstraintSystem::<F>::default();
+    C::configure(&mut meta);
+    let needed = rows + meta.blinding_factors() + 1;
+    Ok((0..).find(|k| 1usize << k >= needed).unwrap())
+}
+
+#[derive(Default)]
+pub(crate) struct Coverage {
+    // gate name and number of rows it was enabled on
+    gates: Vec<(&'static str, usize)>,
+    // lookup name, entries looked up and number of entries of the table when
+    // it does not depend on the circuit, the set table being loaded by it
+    lookups: Vec<(&'static str, HashSet<Vec<u64>>, Option<usize>)>,
+}
+
+impl Coverage {
+    // Synthesizes `circuit` with the given instance columns and adds its
+    // enabled gates and lookups to the coverage.
+    pub(crate) fn record<F: FieldExt, C: Circuit<F, Config = FieldConfig>>(
+        &mut self,
+        circuit: &C,
+        instances: &[Vec<F>],
+    ) -> Result<(), Error> {
+        let (config, recorder) = record_circuit(circuit, instances)?;
+
+        for (name, selector) in gates(&config) {
+            let rows = recorder
+                .selectors
+                .iter()
+                .filter(|(s, _)| *s == selector)
+                .count();
+            self.add_gate(name, rows);
+        }
+        let generic_rows: HashSet<usize> = recorder
+            .fixed
+            .iter()
+            .filter(|(c, _, v)| config.generic.contains(c) && *v != F::zero())
+            .map(|(_, row, _)| *row)
+            .collect();
+        self.add_gate("generic", generic_rows.len());
+
+        let cells: HashMap<(usize, usize), F> = recorder
+            .advice
+            .iter()
+            .map(|(c, r, v)| ((c.index(), *r), *v))
+            .collect();
+        let advice = |column: Column<Advice>, row: usize| {
+            cells
+                .get(&(column.index(), row))
+                .copied()
+                .ok_or(Error::SynthesisError)
+        };
+        let int = |v: F| v.get_lower_32() as u64;
+        let rows = |selector: Selector| {
+            recorder
+                .selectors
+                .iter()
+                .filter(move |(s, _)| *s == selector)
+                .map(|(_, row)| *row)
+        };
+        let [a, b] = config.advices;
+        let k = config.xor_bitlength;
+
+        // (tag, a, b) in the bitwise table
+        let mut entries = vec![];
+        for (selector, op) in config.bitwise_selectors().iter() {
+            for row in rows(*selector) {
+                entries.push(vec![op.tag(), int(advice(a, row)?), int(advice(b, row)?)]);
+            }
+        }
+        self.add_lookups(
+            "bitwise table",
+            entries,
+            Some(BitwiseOp::ALL.len() << (2 * k)),
+        );
+
+        // windows z_cur - 2^K * z_next among the operands of the bitwise table
+        let entries = rows(config.srunsum)
+            .map(|row| {
+                let window = advice(a, row)? - advice(a, row + 1)? * pow2::<F>(k);
+                Ok(vec![int(window)])
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.add_lookups("running sum windows", entries, Some(1 << k));
+
+        let entries = rows(config.sbyte)
+            .map(|row| Ok(vec![int(advice(a, row)?)]))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.add_lookups("byte table", entries, Some(256));
+
+        // (1, a, 0) for the members and (2, lo, hi) for the gaps
+        let mut entries = vec![];
+        for row in rows(config.smember) {
+            entries.push(vec![1, int(advice(a, row)?), 0]);
+        }
+        for row in rows(config.sgap) {
+            entries.push(vec![2, int(advice(a, row)?), int(advice(b, row)?)]);
+        }
+        self.add_lookups("set table", entries, None);
+        Ok(())
+    }
+
+    fn add_gate(&mut self, name: &'static str, rows: usize) {
+        match self.gates.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, total)) => *total += rows,
+            None => self.gates.push((name, rows)),
+        }
+    }
+
+    fn add_lookups(&mut self, name: &'static str, entries: Vec<Vec<u64>>, size: Option<usize>) {
+        match self.lookups.iter_mut().find(|(n, _, _)| *n == name) {
+            Some((_, looked_up, _)) => looked_up.extend(entries),
+            None => self
+                .lookups
+                .push((name, entries.into_iter().collect(), size)),
+        }
+    }
+
+    // Gates that were never enabled.
+    pub(crate) fn dead_gates(&self) -> Vec<&'static str> {
+        self.gates
+            .iter()
+            .filter(|(_, rows)| *rows == 0)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, rows) in self.gates.iter() {
+            writeln!(f, "{}: {} rows", name, rows)?;
+        }
+        for (name, entries, size) in self.lookups.iter() {
+            match size {
+                Some(size) => {
+                    writeln!(f, "{}: {}/{} entries looked up", name, entries.len(), size)?
+                }
+                None => writeln!(f, "{}: {} entries looked up", name, entries.len())?,
+            }
+        }
+        write!(f, "never enabled: {:?}", self.dead_gates())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GadgetCircuit;
+    use crate::vectors::{generate, Gadget, TestVector};
+    use halo2::{dev::MockProver, pasta::Fp};
+
+    fn record<'a>(coverage: &mut Coverage, vectors: impl IntoIterator<Item = &'a TestVector<Fp>>) {
+        for vector in vectors {
+            let inputs = vector.inputs.iter().map(|v| Some(*v)).collect();
+            let circuit = GadgetCircuit::new(vector.gadget.clone(), inputs);
+            coverage
+                .record(&circuit, &[vector.outputs.clone()])
+                .unwrap();
+        }
+    }
+
+    // The number of entries looked up in the table `name`.
+    fn looked_up(coverage: &Coverage, name: &str) -> usize {
+        coverage
+            .lookups
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .map_or(0, |(_, entries, _)| entries.len())
+    }
+
+    #[test]
+    fn coverage() {
+        let vectors = generate::<Fp>();
+        let of = |gadget: fn(&Gadget) -> bool| vectors.iter().filter(move |v| gadget(&v.gadget));
+
+        // only the add instruction
+        let mut coverage = Coverage::default();
+        record(&mut coverage, &vectors[..1]);
+        assert_eq!(
+            coverage.gates.iter().find(|(n, _)| *n == "add").unwrap().1,
+            1
+        );
+        assert_eq!(coverage.dead_gates().len(), coverage.gates.len() - 1);
+        assert!(coverage
+            .lookups
+            .iter()
+            .all(|(_, entries, _)| entries.is_empty()));
+
+        // a single xor, of 5 and 9, looks up one row of the bitwise table
+        let mut coverage = Coverage::default();
+        record(&mut coverage, of(|g| matches!(g, Gadget::Xor)));
+        assert_eq!(looked_up(&coverage, "bitwise table"), 1);
+        assert_eq!(looked_up(&coverage, "byte table"), 0);
+
+        // the bytes 0x56, 0x34, 0x12, 0, 0 and 255, five distinct ones
+        let mut coverage = Coverage::default();
+        record(&mut coverage, of(|g| matches!(g, Gadget::FromBytes)));
+        assert_eq!(looked_up(&coverage, "byte table"), 5);
+
+        // the members 3, 6 and 200, looked up once each
+        let mut coverage = Coverage::default();
+        record(
+            &mut coverage,
+            of(|g| matches!(g, Gadget::AssertMember { .. })),
+        );
+        assert_eq!(looked_up(&coverage, "set table"), 3);
+
+        // the test vectors exercise every gate, the generic one included, and
+        // every lookup
+        let mut coverage = Coverage::default();
+        record(&mut coverage, &vectors);
+        assert!(coverage.dead_gates().is_empty());
+        assert!(coverage.gates.iter().any(|(n, _)| *n == "generic"));
+        for (name, entries, size) in coverage.lookups.iter() {
+            assert!(!entries.is_empty(), "{} never looked up", name);
+            assert!(size.map_or(true, |size| entries.len() <= size));
+        }
+        // the 5 operations on pairs of 4-bit operands, and the 4-bit windows
+        let size = |name: &str| {
+            coverage
+                .lookups
+                .iter()
+                .find(|(n, _, _)| *n == name)
+                .and_then(|(_, _, size)| *size)
+        };
+        assert_eq!(size("bitwise table"), Some(5 << 8));
+        assert_eq!(size("running sum windows"), Some(1 << 4));
+    }
+
+    #[test]
+    fn estimate_k() {
+        let vector = &generate::<Fp>()[0];
+        let inputs: Vec<_> = vector.inputs.iter().map(|v| Some(*v)).collect();
+        let circuit = GadgetCircuit::new(vector.gadget.clone(), inputs);
+        let instances = vec![vector.outputs.clone()];
+        // 1280 rows of bitwise table
+        let k = super::estimate_k(&circuit, &instances).unwrap();
+        assert_eq!(k, 11);
+        let fits = |k| {
+            MockProver::run(k, &circuit, instances.clone())
+                .map(|prover| prover.verify().is_ok())
+                .unwrap_or(false)
+        };
+        assert!(fits(k));
+        assert!(!fits(k - 1));
+    }
+}
@@ -0,0 +1,100 @@
+// Lookups against a table witnessed in advice cells, for ROM/RAM gadgets and
+// key-value membership where the table is part of the witness.
+// The lookup argument of halo2 only looks up fixed table columns, so the
+// membership of a triple is instead proved by selecting its row with a
+// one-hot vector and constraining the inner product of that vector with each
+// column of the table to equal the triple. A lookup thus costs rows linear in
+// the size of the table.
+use super::{FieldChip, Number, NumericInstructions};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+// The rows of a table of triples, each cell assigned in the circuit.
+#[derive(Clone)]
+pub(crate) struct DynamicTable<F: FieldExt> {
+    rows: Vec<[Number<F>; 3]>,
+}
+
+impl<F: FieldExt> DynamicTable<F> {
+    // The cells of the `j`-th column.
+    fn column(&self, j: usize) -> Vec<Number<F>> {
+        self.rows.iter().map(|row| row[j].clone()).collect()
+    }
+}
+
+pub(crate) trait DynamicLookupInstructions<F: FieldExt> {
+    /// Loads the private triples `rows` as a table.
+    fn load_dynamic_table(
+        &self,
+        layouter: impl Layouter<F>,
+        rows: &[[Option<F>; 3]],
+    ) -> Result<DynamicTable<F>, Error>;
+
+    /// Constrains `query` to be one of the rows of `table`. Fails to
+    /// synthesize if it is not.
+    fn lookup_dynamic(
+        &self,
+        layouter: impl Layouter<F>,
+        table: &DynamicTable<F>,
+        query: [Number<F>; 3],
+    ) -> Result<(), Error>;
+}
+
+impl<F: FieldExt> DynamicLookupInstructions<F> for FieldChip<F> {
+    fn load_dynamic_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        rows: &[[Option<F>; 3]],
+    ) -> Result<DynamicTable<F>, Error> {
+        let rows = rows
+            .iter()
+            .map(|row| {
+                Ok([
+                    self.load_private(layouter.namespace(|| "a"), row[0])?,
+                    self.load_private(layouter.namespace(|| "b"), row[1])?,
+                    self.load_private(layouter.namespace(|| "c"), row[2])?,
+                ])
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(DynamicTable { rows })
+    }
+
+    fn lookup_dynamic(
+        &self,
+        mut layouter: impl Layouter<F>,
+        table: &DynamicTable<F>,
+        query: [Number<F>; 3],
+    ) -> Result<(), Error> {
+        // the first row matching the query, None while the witness is unknown
+        let index = match table
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(query.iter())
+                    .map(|(cell, q)| cell.value.and_then(|c| q.value.map(|q| c == q)))
+                    .collect::<Option<Vec<bool>>>()
+                    .map(|eq| eq.into_iter().all(|eq| eq))
+            })
+            .collect::<Option<Vec<bool>>>()
+        {
+            Some(matches) => Some(
+                matches
+                    .iter()
+                    .position(|m| *m)
+                    .ok_or(Error::SynthesisError)?,
+            ),
+            None => None,
+        };
+        let index = self.load_private(
+            layouter.namespace(|| "index"),
+            index.map(|i| F::from(i as u64)),
+        )?;
+        let selector = self.one_hot(layouter.namespace(|| "row"), index, table.rows.len())?;
+        for (j, q) in query.iter().enumerate() {
+            let selected =
+                self.inner_product(layouter.namespace(|| "select"), &selector, &table.column(j))?;
+            self.assert_equal(layouter.namespace(|| "query"), selected, q.clone())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,685 @@
+// Points of the curve y^2 = x^3 + 5 over the circuit field, which is Pallas
+// over Fp and Vesta over Fq, so the points are native to the circuit.
+// Points are affine, the identity being (0, 0): 5 is not a square in either
+// field so no point has x = 0. Complete addition handles every case with
+// comparisons and selections, while incomplete addition is the fast path for
+// points known to have distinct x coordinates, such as an accumulator and a
+// fixed base.
+use super::{modulus_bytes, Bit, FieldChip, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::Layouter,
+    pasta::{Fp, Fq},
+    plonk::Error,
+};
+
+// The constant of the curve equation.
+pub(crate) const B: u64 = 5;
+
+// The number of bits of the scalars consumed at once by `msm`.
+const MSM_WINDOW: usize = 2;
+
+// Little-endian encoding of the order of the curve over F. The Pasta curves
+// form a cycle: the order of each one is the modulus of the other field.
+pub(crate) fn order_bytes<F: FieldExt>() -> [u8; 32] {
+    if modulus_bytes::<F>() == modulus_bytes::<Fp>() {
+        modulus_bytes::<Fq>()
+    } else {
+        modulus_bytes::<Fp>()
+    }
+}
+
+// An affine point computed natively.
+pub(crate) type Affine<F> = (F, F);
+
+#[derive(Clone)]
+pub(crate) struct Point<F: FieldExt> {
+    pub(crate) x: Number<F>,
+    pub(crate) y: Number<F>,
+}
+
+// p + q for points with distinct x coordinates, computed natively.
+pub(crate) fn add_native<F: FieldExt>(p: Affine<F>, q: Affine<F>) -> Affine<F> {
+    let lambda = (q.1 - p.1) * (q.0 - p.0).invert().unwrap();
+    let x = lambda.square() - p.0 - q.0;
+    (x, lambda * (p.0 - x) - p.1)
+}
+
+// 2p for a point other than the identity, computed natively.
+pub(crate) fn double_native<F: FieldExt>(p: Affine<F>) -> Affine<F> {
+    let lambda = p.0.square() * F::from(3) * (p.1 + p.1).invert().unwrap();
+    let x = lambda.square() - p.0 - p.0;
+    (x, lambda * (p.0 - x) - p.1)
+}
+
+// p + q for any points on the curve or the identity, computed natively.
+pub(crate) fn add_complete_native<F: FieldExt>(p: Affine<F>, q: Affine<F>) -> Affine<F> {
+    let o = (F::zero(), F::zero());
+    if p == o {
+        q
+    } else if q == o {
+        p
+    } else if p.0 != q.0 {
+        add_native(p, q)
+    } else if p.1 == q.1 {
+        double_native(p)
+    } else {
+        o
+    }
+}
+
+// k * p for any point on the curve or the identity, k having the
+// little-endian bits `bits`, computed natively.
+pub(crate) fn scalar_mul_native<F: FieldExt>(p: Affine<F>, bits: &[bool]) -> Affine<F> {
+    bits.iter().rev().fold((F::zero(), F::zero()), |acc, bit| {
+        let acc = add_complete_native(acc, acc);
+        if *bit {
+            add_complete_native(acc, p)
+        } else {
+            acc
+        }
+    })
+}
+
+// The point with the smallest x coordinate at least `start`, along with that
+// coordinate as an integer.
+pub(crate) fn find_point<F: FieldExt>(start: u64) -> (u64, Affine<F>) {
+    let mut x = start;
+    loop {
+        let xf = F::from(x);
+        if let Some(y) = Option::<F>::from((xf.square() * xf + F::from(B)).sqrt()) {
+            return (x, (xf, y));
+        }
+        x += 1;
+    }
+}
+
+pub(crate) trait EccInstructions<F: FieldExt> {
+    /// Loads the constant point `p`.
+    fn constant_point(&self, layouter: impl Layouter<F>, p: Affine<F>) -> Result<Point<F>, Error>;
+
+    /// Loads a private point, constrained to be on the curve.
+    fn witness_point(
+        &self,
+        layouter: impl Layouter<F>,
+        p: Option<Affine<F>>,
+    ) -> Result<Point<F>, Error>;
+
+    /// Constrains `p` to be on the curve, which excludes the identity.
+    fn assert_on_curve(&self, layouter: impl Layouter<F>, p: &Point<F>) -> Result<(), Error>;
+
+    /// Constrains `p` to be in the prime-order group. Both Pasta curves have
+    /// prime order, so this is the on-curve check.
+    fn assert_in_subgroup(&self, layouter: impl Layouter<F>, p: &Point<F>) -> Result<(), Error> {
+        self.assert_on_curve(layouter, p)
+    }
+
+    /// Returns p + q for points with distinct x coordinates. Fails to
+    /// synthesize otherwise.
+    fn add_incomplete(
+        &self,
+        layouter: impl Layouter<F>,
+        p: &Point<F>,
+        q: &Point<F>,
+    ) -> Result<Point<F>, Error>;
+
+    /// Returns p + q for any points on the curve or the identity.
+    fn add_complete(
+        &self,
+        layouter: impl Layouter<F>,
+        p: &Point<F>,
+        q: &Point<F>,
+    ) -> Result<Point<F>, Error>;
+
+    /// Returns 2p for any point on the curve or the identity.
+    fn double(&self, layouter: impl Layouter<F>, p: &Point<F>) -> Result<Point<F>, Error>;
+
+    /// Returns k * p for any point on the curve or the identity, k having
+    /// the little-endian bits `bits`.
+    fn scalar_mul(
+        &self,
+        layouter: impl Layouter<F>,
+        p: &Point<F>,
+        bits: &[Bit<F>],
+    ) -> Result<Point<F>, Error>;
+
+    /// Returns the sum of k * p over the `terms` (k, p), k having the
+    /// little-endian bits of its term. The doublings are shared between the
+    /// terms.
+    fn msm(
+        &self,
+        layouter: impl Layouter<F>,
+        terms: &[(Vec<Bit<F>>, Point<F>)],
+    ) -> Result<Point<F>, Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Returns (x_r, y_r) = (lambda^2 - x_p - x_q, lambda * (x_p - x_r) - y_p),
+    // the sum of p and q for the slope lambda of the line through them.
+    fn assign_add_with_slope(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &Point<F>,
+        q: &Point<F>,
+        lambda: Number<F>,
+    ) -> Result<Point<F>, Error> {
+        let one = F::one();
+        let lambda2 = self.square(layouter.namespace(|| "lambda^2"), lambda.clone())?;
+        let x = self.linear_combination(
+            layouter.namespace(|| "x"),
+            &[(one, lambda2), (-one, p.x.clone()), (-one, q.x.clone())],
+        )?;
+        let dx = self.linear_combination(
+            layouter.namespace(|| "x_p - x"),
+            &[(one, p.x.clone()), (-one, x.clone())],
+        )?;
+        let prod = self.mul(layouter.namespace(|| "lambda * (x_p - x)"), lambda, dx)?;
+        let y = self.linear_combination(
+            layouter.namespace(|| "y"),
+            &[(one, prod), (-one, p.y.clone())],
+        )?;
+        Ok(Point { x, y })
+    }
+
+    // Returns the tangent slope 3 x_p^2 / (2 y_p) at p, or 0 at the identity.
+    fn assign_tangent(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &Point<F>,
+    ) -> Result<Number<F>, Error> {
+        let x2 = self.square(layouter.namespace(|| "x_p^2"), p.x.clone())?;
+        let num = self.mul_const(layouter.namespace(|| "3 x_p^2"), x2, F::from(3))?;
+        let den = self.mul_const(layouter.namespace(|| "2 y_p"), p.y.clone(), F::from(2))?;
+        let inv = self.invert_or_zero(layouter.namespace(|| "1 / (2 y_p)"), den)?;
+        self.mul(layouter.namespace(|| "lambda"), num, inv)
+    }
+
+    // Returns a if `bit` is set and b otherwise, coordinate-wise.
+    fn select_point(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bit: &Bit<F>,
+        a: &Point<F>,
+        b: &Point<F>,
+    ) -> Result<Point<F>, Error> {
+        Ok(Point {
+            x: self.select(
+                layouter.namespace(|| "x"),
+                bit.clone(),
+                a.x.clone(),
+                b.x.clone(),
+            )?,
+            y: self.select(
+                layouter.namespace(|| "y"),
+                bit.clone(),
+                a.y.clone(),
+                b.y.clone(),
+            )?,
+        })
+    }
+
+    // Returns the table of j * p for j < 2^MSM_WINDOW.
+    fn assign_window_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &Point<F>,
+    ) -> Result<Vec<Point<F>>, Error> {
+        let identity =
+            self.constant_point(layouter.namespace(|| "identity"), (F::zero(), F::zero()))?;
+        let mut table = vec![identity, p.clone()];
+        for _ in 2..1 << MSM_WINDOW {
+            let last = table.last().unwrap();
+            let next = self.add_complete(layouter.namespace(|| "(j + 1) p"), last, p)?;
+            table.push(next);
+        }
+        Ok(table)
+    }
+
+    // Returns table[j] for j with the little-endian bits `bits`, missing
+    // bits being zero, by halving the table on each bit.
+    fn select_window(
+        &self,
+        mut layouter: impl Layouter<F>,
+        table: &[Point<F>],
+        bits: &[Bit<F>],
+    ) -> Result<Point<F>, Error> {
+        let mut table = table.to_vec();
+        for i in 0..MSM_WINDOW {
+            table = match bits.get(i) {
+                Some(bit) => table
+                    .chunks(2)
+                    .map(|pair| {
+                        self.select_point(layouter.namespace(|| "bit"), bit, &pair[1], &pair[0])
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?,
+                None => table.into_iter().step_by(2).collect(),
+            };
+        }
+        Ok(table.remove(0))
+    }
+}
+
+impl<F: FieldExt> EccInstructions<F> for FieldChip<F> {
+    fn constant_point(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: Affine<F>,
+    ) -> Result<Point<F>, Error> {
+        Ok(Point {
+            x: self.load_constant(layouter.namespace(|| "x"), p.0)?,
+            y: self.load_constant(layouter.namespace(|| "y"), p.1)?,
+        })
+    }
+
+    fn witness_point(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: Option<Affine<F>>,
+    ) -> Result<Point<F>, Error> {
+        let x = self.load_private(layouter.namespace(|| "x"), p.map(|p| p.0))?;
+        let y = self.load_private(layouter.namespace(|| "y"), p.map(|p| p.1))?;
+        let p = Point { x, y };
+        self.assert_on_curve(layouter.namespace(|| "on curve"), &p)?;
+        Ok(p)
+    }
+
+    fn assert_on_curve(&self, mut layouter: impl Layouter<F>, p: &Point<F>) -> Result<(), Error> {
+        // y^2 - x^3 = 5
+        let y2 = self.square(layouter.namespace(|| "y^2"), p.y.clone())?;
+        let x2 = self.square(layouter.namespace(|| "x^2"), p.x.clone())?;
+        let x3 = self.mul(layouter.namespace(|| "x^3"), x2, p.x.clone())?;
+        let one = F::one();
+        let lhs =
+            self.linear_combination(layouter.namespace(|| "y^2 - x^3"), &[(one, y2), (-one, x3)])?;
+        let b = self.load_constant(layouter.namespace(|| "b"), F::from(B))?;
+        self.assert_equal(layouter.namespace(|| "b"), lhs, b)
+    }
+
+    fn add_incomplete(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &Point<F>,
+        q: &Point<F>,
+    ) -> Result<Point<F>, Error> {
+        let one = F::one();
+        let dx = self.linear_combination(
+            layouter.namespace(|| "x_q - x_p"),
+            &[(one, q.x.clone()), (-one, p.x.clone())],
+        )?;
+        let dy = self.linear_combination(
+            layouter.namespace(|| "y_q - y_p"),
+            &[(one, q.y.clone()), (-one, p.y.clone())],
+        )?;
+        let lambda = self.div(layouter.namespace(|| "lambda"), dy, dx)?;
+        self.assign_add_with_slope(layouter.namespace(|| "sum"), p, q, lambda)
+    }
+
+    fn add_complete(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &Point<F>,
+        q: &Point<F>,
+    ) -> Result<Point<F>, Error> {
+        let one = F::one();
+        let dx = self.linear_combination(
+            layouter.namespace(|| "x_q - x_p"),
+            &[(one, q.x.clone()), (-one, p.x.clone())],
+        )?;
+        let dy = self.linear_combination(
+            layouter.namespace(|| "y_q - y_p"),
+            &[(one, q.y.clone()), (-one, p.y.clone())],
+        )?;
+        let same_x = self.is_zero(layouter.namespace(|| "x_p == x_q"), dx.clone())?;
+
+        // the chord slope, 0 when x_p = x_q, or the tangent slope
+        let inv = self.invert_or_zero(layouter.namespace(|| "1 / (x_q - x_p)"), dx)?;
+        let chord = self.mul(layouter.namespace(|| "chord"), dy, inv)?;
+        let tangent = self.assign_tangent(layouter.namespace(|| "tangent"), p)?;
+        let lambda = self.select(
+            layouter.namespace(|| "lambda"),
+            same_x.clone(),
+            tangent,
+            chord,
+        )?;
+        let sum = self.assign_add_with_slope(layouter.namespace(|| "sum"), p, q, lambda)?;
+
+        // q = -p when x_p = x_q but y_p != y_q
+        let same_y = self.is_equal(
+            layouter.namespace(|| "y_p == y_q"),
+            p.y.clone(),
+            q.y.clone(),
+        )?;
+        let both = self.mul(
+            layouter.namespace(|| "same x and y"),
+            same_x.num(),
+            same_y.num(),
+        )?;
+        let opposite = Bit(self.linear_combination(
+            layouter.namespace(|| "q == -p"),
+            &[(one, same_x.num()), (-one, both)],
+        )?);
+        let zero = self.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        let identity = Point {
+            x: zero.clone(),
+            y: zero,
+        };
+        let sum = self.select_point(layouter.namespace(|| "p + -p"), &opposite, &identity, &sum)?;
+
+        let p_is_identity = self.is_zero(layouter.namespace(|| "p == O"), p.x.clone())?;
+        let q_is_identity = self.is_zero(layouter.namespace(|| "q == O"), q.x.clone())?;
+        let sum = self.select_point(layouter.namespace(|| "p + O"), &q_is_identity, p, &sum)?;
+        self.select_point(layouter.namespace(|| "O + q"), &p_is_identity, q, &sum)
+    }
+
+    // The tangent slope is 0 at the identity, so the identity doubles to
+    // itself without a special case.
+    fn double(&self, mut layouter: impl Layouter<F>, p: &Point<F>) -> Result<Point<F>, Error> {
+        let lambda = self.assign_tangent(layouter.namespace(|| "tangent"), p)?;
+        self.assign_add_with_slope(layouter.namespace(|| "sum"), p, p, lambda)
+    }
+
+    // Double-and-add from the most significant bit, with complete additions
+    // since the accumulator starts at the identity and may meet p.
+    fn scalar_mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &Point<F>,
+        bits: &[Bit<F>],
+    ) -> Result<Point<F>, Error> {
+        let zero = self.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        let mut acc = Point {
+            x: zero.clone(),
+            y: zero,
+        };
+        for bit in bits.iter().rev() {
+            acc = self.double(layouter.namespace(|| "2 acc"), &acc)?;
+            let sum = self.add_complete(layouter.namespace(|| "acc + p"), &acc, p)?;
+            acc = self.select_point(layouter.namespace(|| "bit"), bit, &sum, &acc)?;
+        }
+        Ok(acc)
+    }
+
+    // Interleaved windows: the accumulator is doubled MSM_WINDOW times per
+    // window, then every term adds the multiple of its point selected by its
+    // window of bits. Once the table of each point is built, a term costs one
+    // complete addition per window where scalar_mul costs one per bit.
+    // Buckets do not pay off here: the window value is only known to the
+    // prover, so every point would be added to every bucket.
+    fn msm(
+        &self,
+        mut layouter: impl Layouter<F>,
+        terms: &[(Vec<Bit<F>>, Point<F>)],
+    ) -> Result<Point<F>, Error> {
+        let tables = terms
+            .iter()
+            .map(|(_, p)| self.assign_window_table(layouter.namespace(|| "table"), p))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let n = terms.iter().map(|(bits, _)| bits.len()).max().unwrap_or(0);
+        let mut acc =
+            self.constant_point(layouter.namespace(|| "identity"), (F::zero(), F::zero()))?;
+        for window in (0..(n + MSM_WINDOW - 1) / MSM_WINDOW).rev() {
+            for _ in 0..MSM_WINDOW {
+                acc = self.double(layouter.namespace(|| "2 acc"), &acc)?;
+            }
+            for ((bits, _), table) in terms.iter().zip(tables.iter()) {
+                let start = (window * MSM_WINDOW).min(bits.len());
+                let end = (start + MSM_WINDOW).min(bits.len());
+                let p =
+                    self.select_window(layouter.namespace(|| "window"), table, &bits[start..end])?;
+                acc = self.add_complete(layouter.namespace(|| "acc + j p"), &acc, &p)?;
+            }
+        }
+        Ok(acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use crate::to_le_bits;
+    use halo2::{
+        arithmetic::Field,
+        pasta::{Fp, Fq},
+    };
+
+    fn flatten(points: &[Affine<Fp>]) -> Vec<Fp> {
+        points.iter().flat_map(|p| vec![p.0, p.1]).collect()
+    }
+
+    // [x_p, y_p, x_q, y_q] -> [p + q, 2p], the identity being (0, 0)
+    #[derive(Clone, Debug)]
+    struct Add;
+
+    impl TestableGadget<Fp> for Add {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            let (_, g) = find_point(1);
+            let g2 = double_native(g);
+            let o = (Fp::zero(), Fp::zero());
+            vec![
+                flatten(&[g, g2]),
+                flatten(&[g, g]),
+                flatten(&[g, (g.0, -g.1)]),
+                flatten(&[o, g2]),
+                flatten(&[g2, o]),
+                flatten(&[o, o]),
+            ]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let p = (inputs[0], inputs[1]);
+            let q = (inputs[2], inputs[3]);
+            flatten(&[add_complete_native(p, q), add_complete_native(p, p)])
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let p = Point {
+                x: inputs[0].clone(),
+                y: inputs[1].clone(),
+            };
+            let q = Point {
+                x: inputs[2].clone(),
+                y: inputs[3].clone(),
+            };
+            let sum = chip.add_complete(layouter.namespace(|| "p + q"), &p, &q)?;
+            let double = chip.double(layouter.namespace(|| "2p"), &p)?;
+            Ok(vec![sum.x, sum.y, double.x, double.y])
+        }
+    }
+
+    // [x, y] -> [], (x, y) being on the curve, hence in the group
+    #[derive(Clone, Debug)]
+    struct Witness;
+
+    impl TestableGadget<Fp> for Witness {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            let (_, g) = find_point(1);
+            vec![flatten(&[g]), flatten(&[double_native(g)])]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            let (_, g) = find_point(1);
+            vec![
+                flatten(&[(g.0, g.1 + Fp::one())]),
+                vec![Fp::zero(), Fp::zero()],
+            ]
+        }
+
+        fn reference(&self, _: &[Fp]) -> Vec<Fp> {
+            vec![]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let value = inputs[0]
+                .value
+                .and_then(|x| inputs[1].value.map(|y| (x, y)));
+            let p = chip.witness_point(layouter.namespace(|| "p"), value)?;
+            chip.assert_equal(layouter.namespace(|| "x"), p.x, inputs[0].clone())?;
+            chip.assert_equal(layouter.namespace(|| "y"), p.y, inputs[1].clone())?;
+            let input = Point {
+                x: inputs[0].clone(),
+                y: inputs[1].clone(),
+            };
+            chip.assert_in_subgroup(layouter.namespace(|| "subgroup"), &input)?;
+            Ok(vec![])
+        }
+    }
+
+    // [x, y, 8 bits of k, little-endian] -> [k * (x, y)]
+    #[derive(Clone, Debug)]
+    struct ScalarMul;
+
+    fn scalar_mul_sample(p: Affine<Fp>, k: u64) -> Vec<Fp> {
+        let mut inputs = flatten(&[p]);
+        inputs.extend((0..8).map(|i| Fp::from((k >> i) & 1)));
+        inputs
+    }
+
+    impl TestableGadget<Fp> for ScalarMul {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            let (_, g) = find_point(1);
+            let o = (Fp::zero(), Fp::zero());
+            vec![
+                scalar_mul_sample(g, 0),
+                scalar_mul_sample(g, 1),
+                scalar_mul_sample(g, 6),
+                scalar_mul_sample(g, 255),
+                scalar_mul_sample(o, 3),
+            ]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let bits: Vec<bool> = inputs[2..].iter().map(|b| *b == Fp::one()).collect();
+            flatten(&[scalar_mul_native((inputs[0], inputs[1]), &bits)])
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let p = Point {
+                x: inputs[0].clone(),
+                y: inputs[1].clone(),
+            };
+            let bits = inputs[2..]
+                .iter()
+                .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let kp = chip.scalar_mul(layouter.namespace(|| "k * p"), &p, &bits)?;
+            Ok(vec![kp.x, kp.y])
+        }
+    }
+
+    // [x_i, y_i, 7 bits of k_i, little-endian]* -> [sum of k_i * (x_i, y_i)]
+    #[derive(Clone, Debug)]
+    struct Msm;
+
+    const MSM_BITS: usize = 7;
+
+    fn msm_sample(terms: &[(Affine<Fp>, u64)]) -> Vec<Fp> {
+        terms
+            .iter()
+            .flat_map(|(p, k)| {
+                let mut inputs = flatten(&[*p]);
+                inputs.extend((0..MSM_BITS).map(|i| Fp::from((k >> i) & 1)));
+                inputs
+            })
+            .collect()
+    }
+
+    impl TestableGadget<Fp> for Msm {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            let (_, g) = find_point(1);
+            let g2 = double_native(g);
+            let o = (Fp::zero(), Fp::zero());
+            vec![
+                msm_sample(&[(g, 3), (g2, 5), (o, 7)]),
+                msm_sample(&[(g, 0), (g2, 0), (g, 0)]),
+                // 127 G - 127 G + G
+                msm_sample(&[(g, 127), ((g.0, -g.1), 127), (g, 1)]),
+            ]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let sum = inputs
+                .chunks(2 + MSM_BITS)
+                .fold((Fp::zero(), Fp::zero()), |acc, term| {
+                    let bits: Vec<bool> = term[2..].iter().map(|b| *b == Fp::one()).collect();
+                    add_complete_native(acc, scalar_mul_native((term[0], term[1]), &bits))
+                });
+            flatten(&[sum])
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let terms = inputs
+                .chunks(2 + MSM_BITS)
+                .map(|term| {
+                    let bits = term[2..]
+                        .iter()
+                        .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let p = Point {
+                        x: term[0].clone(),
+                        y: term[1].clone(),
+                    };
+                    Ok((bits, p))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let sum = chip.msm(layouter.namespace(|| "msm"), &terms)?;
+            Ok(vec![sum.x, sum.y])
+        }
+    }
+
+    #[test]
+    fn add() {
+        check(&Add, 11);
+    }
+
+    #[test]
+    fn witness() {
+        check(&Witness, 11);
+    }
+
+    #[test]
+    fn order() {
+        // Pallas has prime order q, the modulus of Fq, so (q - 1) * G = -G
+        let (_, g) = find_point::<Fp>(1);
+        let q_minus_one = to_le_bits(&-Fq::one(), 255);
+        assert_eq!(scalar_mul_native(g, &q_minus_one), (g.0, -g.1));
+        assert_eq!(order_bytes::<Fp>(), modulus_bytes::<Fq>());
+        assert_eq!(order_bytes::<Fq>(), modulus_bytes::<Fp>());
+    }
+
+    #[test]
+    fn scalar_mul() {
+        let (_, g) = find_point::<Fp>(1);
+        let three = [true, true];
+        assert_eq!(
+            scalar_mul_native(g, &three),
+            add_native(g, double_native(g))
+        );
+        check(&ScalarMul, 11);
+    }
+
+    #[test]
+    fn msm() {
+        check(&Msm, 12);
+    }
+}
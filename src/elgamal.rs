@@ -0,0 +1,185 @@
+// Verifiable ElGamal encryption on the curve of ecc.rs. The plaintext m is
+// encrypted "in the exponent" under the public key pk with the randomness r
+// as (c1, c2) = (r * G, m * G + r * pk), G being the base point of the
+// Pedersen commitments, and the statement proven is that (c1, c2) encrypts
+// the value committed to in a Pedersen commitment. Decryption only recovers
+// m * G, so m must be small for the receiver to find it.
+// The ciphertext is returned to be exposed or compared by the caller.
+// Scalars are given as Numbers and decomposed on F::CAPACITY bits, like the
+// Pedersen scalars, so that the same value can be committed and encrypted.
+use super::{
+    ecc::{add_complete_native, find_point, scalar_mul_native, Affine, EccInstructions, Point},
+    pedersen::CommitmentInstructions,
+    to_le_bits, BitOrder, FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+#[derive(Clone)]
+pub(crate) struct Ciphertext<F: FieldExt> {
+    pub(crate) c1: Point<F>,
+    pub(crate) c2: Point<F>,
+}
+
+// (r * G, m * G + r * pk), computed natively.
+pub(crate) fn encrypt_native<F: FieldExt>(pk: Affine<F>, m: F, r: F) -> (Affine<F>, Affine<F>) {
+    let (_, g) = find_point(1);
+    let bits = |k: &F| to_le_bits(k, F::CAPACITY as usize);
+    let c1 = scalar_mul_native(g, &bits(&r));
+    let c2 = add_complete_native(
+        scalar_mul_native(g, &bits(&m)),
+        scalar_mul_native(pk, &bits(&r)),
+    );
+    (c1, c2)
+}
+
+pub(crate) trait ElGamalInstructions<F: FieldExt> {
+    /// Returns the encryption of `m` under `pk` with the randomness `r`. `pk`
+    /// must be on the curve.
+    fn encrypt(
+        &self,
+        layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        m: Number<F>,
+        r: Number<F>,
+    ) -> Result<Ciphertext<F>, Error>;
+
+    /// Returns the encryption under `pk` with the randomness `r` of the value
+    /// `m`, constrained to be committed to in `commitment` with `blinding`.
+    fn encrypt_committed(
+        &self,
+        layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        commitment: &Point<F>,
+        m: Number<F>,
+        r: Number<F>,
+        blinding: Number<F>,
+    ) -> Result<Ciphertext<F>, Error>;
+}
+
+impl<F: FieldExt> ElGamalInstructions<F> for FieldChip<F> {
+    fn encrypt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        m: Number<F>,
+        r: Number<F>,
+    ) -> Result<Ciphertext<F>, Error> {
+        let (_, g) = find_point(1);
+        let g = self.constant_point(layouter.namespace(|| "G"), g)?;
+        let m = self.unpack(
+            layouter.namespace(|| "bits of m"),
+            m,
+            F::CAPACITY as usize,
+            BitOrder::LittleEndian,
+        )?;
+        let r = self.unpack(
+            layouter.namespace(|| "bits of r"),
+            r,
+            F::CAPACITY as usize,
+            BitOrder::LittleEndian,
+        )?;
+        let c1 = self.scalar_mul(layouter.namespace(|| "r * G"), &g, &r)?;
+        let mg = self.scalar_mul(layouter.namespace(|| "m * G"), &g, &m)?;
+        let rpk = self.scalar_mul(layouter.namespace(|| "r * pk"), pk, &r)?;
+        let c2 = self.add_complete(layouter.namespace(|| "m * G + r * pk"), &mg, &rpk)?;
+        Ok(Ciphertext { c1, c2 })
+    }
+
+    fn encrypt_committed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        commitment: &Point<F>,
+        m: Number<F>,
+        r: Number<F>,
+        blinding: Number<F>,
+    ) -> Result<Ciphertext<F>, Error> {
+        self.open(
+            layouter.namespace(|| "commitment"),
+            commitment,
+            m.clone(),
+            blinding,
+        )?;
+        self.encrypt(layouter.namespace(|| "encrypt"), pk, m, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use crate::pedersen::commit_native;
+    use halo2::pasta::Fp;
+
+    // [pk, commitment, m, r, blinding] for the secret key `x`.
+    fn sample(x: u64, m: u64, r: u64, blinding: u64) -> Vec<Fp> {
+        let (_, g) = find_point::<Fp>(1);
+        let pk = scalar_mul_native(g, &to_le_bits(&Fp::from(x), 64));
+        let (m, r, blinding) = (Fp::from(m), Fp::from(r), Fp::from(blinding));
+        let commitment = commit_native(m, blinding);
+        vec![pk.0, pk.1, commitment.0, commitment.1, m, r, blinding]
+    }
+
+    // [pk, commitment, m, r, blinding] -> [c1, c2]
+    #[derive(Clone, Debug)]
+    struct EncryptCommitted;
+
+    impl TestableGadget<Fp> for EncryptCommitted {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            vec![sample(7, 42, 0x1234_5678, 99)]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            // a commitment to another value
+            let mut other = sample(7, 42, 0x1234_5678, 99);
+            let forged = sample(7, 43, 0x1234_5678, 99);
+            other[2] = forged[2];
+            other[3] = forged[3];
+            vec![other]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let (c1, c2) = encrypt_native((inputs[0], inputs[1]), inputs[4], inputs[5]);
+            vec![c1.0, c1.1, c2.0, c2.1]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let point = |i: usize| Point {
+                x: inputs[i].clone(),
+                y: inputs[i + 1].clone(),
+            };
+            let ct = chip.encrypt_committed(
+                layouter.namespace(|| "encrypt"),
+                &point(0),
+                &point(2),
+                inputs[4].clone(),
+                inputs[5].clone(),
+                inputs[6].clone(),
+            )?;
+            Ok(vec![ct.c1.x, ct.c1.y, ct.c2.x, ct.c2.y])
+        }
+    }
+
+    #[test]
+    fn native() {
+        // c2 - x * c1 is m * G
+        let (_, g) = find_point::<Fp>(1);
+        let x = to_le_bits(&Fp::from(7), 64);
+        let pk = scalar_mul_native(g, &x);
+        let (c1, c2) = encrypt_native(pk, Fp::from(3), Fp::from(11));
+        let xc1: Affine<Fp> = scalar_mul_native(c1, &x);
+        let m = add_complete_native(c2, (xc1.0, -xc1.1));
+        assert_eq!(m, scalar_mul_native(g, &to_le_bits(&Fp::from(3), 64)));
+    }
+
+    #[test]
+    fn encrypt_committed() {
+        // three scalar multiplications and a commitment, about 2^16 rows
+        check(&EncryptCommitted, 17);
+    }
+}
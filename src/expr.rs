@@ -0,0 +1,428 @@
+// Compiles arithmetic statements such as "a^2 + b^2 + 3 == c^2" into an IR
+// program, which IrCircuit then synthesizes over FieldChip.
+// The source is a list of statements separated by ';':
+// * `pub c, d` declares the public variables, loaded from the instance
+//   column in that order,
+// * `lhs == rhs` constrains two expressions to be equal.
+// Expressions are made of variables, integer constants, `+`, `*`, `^` with a
+// constant exponent and parentheses. Variables that are not declared public
+// are private and their values are given in order of first appearance.
+use super::ir::{Input, Op, Program};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParseError {
+    // byte offset in the source where parsing failed
+    pos: usize,
+    msg: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.msg, self.pos)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Plus,
+    Star,
+    Caret,
+    LParen,
+    RParen,
+    EqEq,
+    Comma,
+    Semi,
+}
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        let start = i;
+        let token = match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                i += 1;
+                continue;
+            }
+            '+' => Token::Plus,
+            '*' => Token::Star,
+            '^' => Token::Caret,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            ';' => Token::Semi,
+            '=' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 1;
+                Token::EqEq
+            }
+            '0'..='9' => {
+                while i + 1 < bytes.len() && bytes[i + 1].is_ascii_digit() {
+                    i += 1;
+                }
+                let n = src[start..=i].parse().map_err(|_| ParseError {
+                    pos: start,
+                    msg: "constant too large",
+                })?;
+                Token::Number(n)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while i + 1 < bytes.len()
+                    && (bytes[i + 1].is_ascii_alphanumeric() || bytes[i + 1] == b'_')
+                {
+                    i += 1;
+                }
+                Token::Ident(src[start..=i].to_string())
+            }
+            _ => {
+                return Err(ParseError {
+                    pos: start,
+                    msg: "unexpected character",
+                })
+            }
+        };
+        tokens.push((start, token));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Expr {
+    Const(u64),
+    Var(String),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, u64),
+}
+
+enum Statement {
+    Public(Vec<String>),
+    Equal(Expr, Expr),
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // offset of the end of the source, for errors on missing tokens
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(o, _)| *o)
+            .unwrap_or(self.end)
+    }
+
+    fn error(&self, msg: &'static str) -> ParseError {
+        ParseError {
+            pos: self.offset(),
+            msg,
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, msg: &'static str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(msg))
+        }
+    }
+
+    // program := statement (';' statement)* ';'?
+    fn program(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        while self.peek().is_some() {
+            statements.push(self.statement()?);
+            if !self.eat(&Token::Semi) && self.peek().is_some() {
+                return Err(self.error("expected ';'"));
+            }
+        }
+        Ok(statements)
+    }
+
+    // statement := 'pub' ident (',' ident)* | expr '==' expr
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        if self.peek() == Some(&Token::Ident("pub".to_string())) {
+            self.pos += 1;
+            let mut names = vec![self.ident()?];
+            while self.eat(&Token::Comma) {
+                names.push(self.ident()?);
+            }
+            return Ok(Statement::Public(names));
+        }
+        let lhs = self.expr()?;
+        self.expect(&Token::EqEq, "expected '=='")?;
+        let rhs = self.expr()?;
+        Ok(Statement::Equal(lhs, rhs))
+    }
+
+    fn ident(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected a variable")),
+        }
+    }
+
+    // expr := term ('+' term)*
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.term()?;
+        while self.eat(&Token::Plus) {
+            lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    // term := power ('*' power)*
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.power()?;
+        while self.eat(&Token::Star) {
+            lhs = Expr::Mul(Box::new(lhs), Box::new(self.power()?));
+        }
+        Ok(lhs)
+    }
+
+    // power := atom ('^' number)?
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.atom()?;
+        if !self.eat(&Token::Caret) {
+            return Ok(base);
+        }
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                self.pos += 1;
+                Ok(Expr::Pow(Box::new(base), n))
+            }
+            _ => Err(self.error("expected a constant exponent")),
+        }
+    }
+
+    // atom := number | ident | '(' expr ')'
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        match self.peek().cloned() {
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Expr::Const(n))
+            }
+            Some(Token::Ident(name)) if name != "pub" => {
+                self.pos += 1;
+                Ok(Expr::Var(name))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let e = self.expr()?;
+                self.expect(&Token::RParen, "expected ')'")?;
+                Ok(e)
+            }
+            _ => Err(self.error("expected an expression")),
+        }
+    }
+}
+
+// Lowers expressions to IR operations, naming intermediate wires "$i" so they
+// cannot clash with variables.
+#[derive(Default)]
+struct Compiler {
+    ops: Vec<Op>,
+    temps: usize,
+    // variables in order of first appearance
+    vars: Vec<String>,
+}
+
+impl Compiler {
+    fn fresh(&mut self) -> String {
+        self.temps += 1;
+        format!("${}", self.temps - 1)
+    }
+
+    fn binary(&mut self, a: String, b: String, mul: bool) -> String {
+        let out = self.fresh();
+        self.ops.push(if mul {
+            Op::Mul {
+                out: out.clone(),
+                a,
+                b,
+            }
+        } else {
+            Op::Add {
+                out: out.clone(),
+                a,
+                b,
+            }
+        });
+        out
+    }
+
+    fn constant(&mut self, value: u64) -> String {
+        let out = self.fresh();
+        self.ops.push(Op::Const {
+            out: out.clone(),
+            value,
+        });
+        out
+    }
+
+    // Returns the wire holding the value of `e`.
+    fn emit(&mut self, e: &Expr) -> String {
+        match e {
+            Expr::Const(n) => self.constant(*n),
+            Expr::Var(name) => {
+                if !self.vars.contains(name) {
+                    self.vars.push(name.clone());
+                }
+                name.clone()
+            }
+            Expr::Add(a, b) => {
+                let (a, b) = (self.emit(a), self.emit(b));
+                self.binary(a, b, false)
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (self.emit(a), self.emit(b));
+                self.binary(a, b, true)
+            }
+            Expr::Pow(_, 0) => self.constant(1),
+            Expr::Pow(base, n) => {
+                // square and multiply from the most significant bit
+                let base = self.emit(base);
+                let mut acc = base.clone();
+                for i in (0..63 - n.leading_zeros()).rev() {
+                    acc = self.binary(acc.clone(), acc, true);
+                    if (n >> i) & 1 == 1 {
+                        acc = self.binary(acc, base.clone(), true);
+                    }
+                }
+                acc
+            }
+        }
+    }
+}
+
+// Compiles `src` into an IR program. Public variables come first in the
+// program inputs, in declaration order, followed by the private ones.
+pub(crate) fn compile(src: &str) -> Result<Program, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        end: src.len(),
+    };
+    let statements = parser.program()?;
+
+    let mut compiler = Compiler::default();
+    let mut public = Vec::new();
+    for statement in statements.iter() {
+        match statement {
+            Statement::Public(names) => public.extend(names.iter().cloned()),
+            Statement::Equal(lhs, rhs) => {
+                let a = compiler.emit(lhs);
+                let b = compiler.emit(rhs);
+                compiler.ops.push(Op::AssertEqual { a, b });
+            }
+        }
+    }
+
+    let mut inputs: Vec<Input> = public
+        .iter()
+        .map(|name| Input {
+            name: name.clone(),
+            public: true,
+        })
+        .collect();
+    inputs.extend(
+        compiler
+            .vars
+            .iter()
+            .filter(|name| !public.contains(name))
+            .map(|name| Input {
+                name: name.clone(),
+                public: false,
+            }),
+    );
+    Ok(Program {
+        inputs,
+        ops: compiler.ops,
+        public: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::IrCircuit;
+    use halo2::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn pythagore() {
+        let program = compile("pub c; a^2 + b^2 + 3 == c^2").unwrap();
+        let k = 11;
+        let circuit = IrCircuit::new(program, vec![Some(Fp::from(2)), Some(Fp::from(3))]);
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(4)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(5)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn precedence() {
+        // 3^5 = 2 * 105 + 3 * 2^3 + 9
+        let program = compile("pub y; (x + 1)^5 == y * 2 + 3 * x^3 * 1 + 9").unwrap();
+        let circuit = IrCircuit::new(program, vec![Some(Fp::from(2))]);
+        let prover = MockProver::run(11, &circuit, vec![vec![Fp::from(105)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            compile("a + == b").err(),
+            Some(ParseError {
+                pos: 4,
+                msg: "expected an expression"
+            })
+        );
+        assert_eq!(
+            compile("a + b").err(),
+            Some(ParseError {
+                pos: 5,
+                msg: "expected '=='"
+            })
+        );
+        assert_eq!(
+            compile("a - b == c").err(),
+            Some(ParseError {
+                pos: 2,
+                msg: "unexpected character"
+            })
+        );
+        assert_eq!(
+            compile("a^b == c").err(),
+            Some(ParseError {
+                pos: 2,
+                msg: "expected a constant exponent"
+            })
+        );
+    }
+}
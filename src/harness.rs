@@ -0,0 +1,156 @@
+// Uniform testing of the chip instructions. A gadget implementing
+// TestableGadget describes its sample inputs, computes its outputs natively
+// and synthesizes itself over FieldChip; `check` then verifies every sample
+// against the native outputs, makes sure tampered outputs and invalid inputs
+// are rejected, and reports the number of rows used.
+use super::{
+    bytes::ByteInstructions, coverage, FieldChip, FieldConfig, Number, NumericInstructions,
+};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use std::fmt::Debug;
+
+// Bit length of the bitwise table, so its operands must fit in 4 bits and the
+// circuit needs at least 5 * 2^8 rows, that is k = 11.
+const XOR_BITLENGTH: usize = 4;
+
+pub(crate) trait TestableGadget<F: FieldExt>: Clone + Debug {
+    /// Inputs the gadget must accept.
+    fn samples(&self) -> Vec<Vec<F>>;
+
+    /// Inputs outside of the domain of the gadget, which must be rejected
+    /// even with the outputs `reference` computes for them.
+    fn invalid_samples(&self) -> Vec<Vec<F>> {
+        vec![]
+    }
+
+    /// Computes the outputs natively.
+    fn reference(&self, inputs: &[F]) -> Vec<F>;
+
+    /// Synthesizes the gadget on the loaded inputs and returns its outputs.
+    fn synthesize(
+        &self,
+        chip: &FieldChip<F>,
+        layouter: impl Layouter<F>,
+        inputs: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error>;
+}
+
+// Runs a gadget on private inputs and exposes its outputs, in order, as
+// public inputs.
+pub(crate) struct GadgetCircuit<F: FieldExt, G: TestableGadget<F>> {
+    gadget: G,
+    inputs: Vec<Option<F>>,
+}
+
+impl<F: FieldExt, G: TestableGadget<F>> GadgetCircuit<F, G> {
+    pub(crate) fn new(gadget: G, inputs: Vec<Option<F>>) -> Self {
+        Self { gadget, inputs }
+    }
+}
+
+impl<F: FieldExt, G: TestableGadget<F>> Circuit<F> for GadgetCircuit<F, G> {
+    type Config = FieldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            gadget: self.gadget.clone(),
+            inputs: vec![None; self.inputs.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FieldChip::configure_default(meta, XOR_BITLENGTH)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FieldChip::<F>::construct(config);
+        chip.load_bitwise_table(layouter.namespace(|| "bitwise table"))?;
+        chip.load_byte_table(layouter.namespace(|| "byte table"))?;
+
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|v| chip.load_private(layouter.namespace(|| "input"), *v))
+            .collect::<Result<Vec<Number<F>>, Error>>()?;
+        let outputs = self
+            .gadget
+            .synthesize(&chip, layouter.namespace(|| "gadget"), &inputs)?;
+        for (row, output) in outputs.into_iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "output"), output, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Cost {
+    pub(crate) samples: usize,
+    // most rows used by a sample, including the loading of the inputs
+    pub(crate) rows: usize,
+}
+
+// Whether the circuit on `inputs` is satisfied with `outputs` as public
+// inputs. Synthesis errors count as unsatisfied.
+fn satisfied<F: FieldExt, G: TestableGadget<F>>(
+    gadget: &G,
+    k: u32,
+    inputs: &[F],
+    outputs: Vec<F>,
+) -> bool {
+    let circuit = GadgetCircuit::new(gadget.clone(), inputs.iter().map(|v| Some(*v)).collect());
+    match MockProver::run(k, &circuit, vec![outputs]) {
+        Ok(prover) => prover.verify().is_ok(),
+        Err(_) => false,
+    }
+}
+
+// Checks `gadget` on its samples over 2^k rows, panicking on the first
+// failure.
+pub(crate) fn check<F: FieldExt, G: TestableGadget<F>>(gadget: &G, k: u32) -> Cost {
+    let samples = gadget.samples();
+    let mut rows = 0;
+    for inputs in samples.iter() {
+        let outputs = gadget.reference(inputs);
+        assert!(
+            satisfied(gadget, k, inputs, outputs.clone()),
+            "{:?} rejects {:?}",
+            gadget,
+            inputs
+        );
+        for i in 0..outputs.len() {
+            let mut tampered = outputs.clone();
+            tampered[i] += F::one();
+            assert!(
+                !satisfied(gadget, k, inputs, tampered),
+                "{:?} accepts a wrong output {} on {:?}",
+                gadget,
+                i,
+                inputs
+            );
+        }
+        let circuit = GadgetCircuit::new(gadget.clone(), inputs.iter().map(|v| Some(*v)).collect());
+        rows = rows.max(coverage::rows_used(&circuit, &[outputs]).unwrap());
+    }
+    for inputs in gadget.invalid_samples().iter() {
+        assert!(
+            !satisfied(gadget, k, inputs, gadget.reference(inputs)),
+            "{:?} accepts invalid {:?}",
+            gadget,
+            inputs
+        );
+    }
+    Cost {
+        samples: samples.len(),
+        rows,
+    }
+}
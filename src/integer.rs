@@ -0,0 +1,199 @@
+// Fixed-width unsigned integers over FieldChip, for circuits modelling VM or
+// protocol arithmetic rather than field arithmetic. A U64 is a Number known
+// to fit in 64 bits, obtained by range checking it with the running sum over
+// the bitwise table, or as the output of an operation on U64s.
+// Every operation follows the Rust integer methods, suffixed by the width to
+// stay apart from the field instructions: `overflowing_*64` returns the
+// wrapped result along with a boolean carry, borrow or overflow flag,
+// `wrapping_*64` drops the flag and `checked_*64` constrains it to be unset,
+// so that an overflowing witness does not satisfy the circuit. The bitwise
+// table must be loaded for the range checks.
+use super::{FieldChip, Number, NumericInstructions};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+#[derive(Clone)]
+pub(crate) struct U64<F: FieldExt>(Number<F>);
+
+impl<F: FieldExt> U64<F> {
+    pub(crate) fn num(&self) -> Number<F> {
+        self.0.clone()
+    }
+}
+
+pub(crate) trait U64Instructions<F: FieldExt> {
+    /// Loads a private integer.
+    fn load_u64(&self, layouter: impl Layouter<F>, value: Option<u64>) -> Result<U64<F>, Error>;
+
+    /// Constrains `a` to fit in 64 bits and returns it as an integer.
+    fn u64(&self, layouter: impl Layouter<F>, a: Number<F>) -> Result<U64<F>, Error>;
+
+    /// Returns `(a + b mod 2^64, carry)`.
+    fn overflowing_add64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<(U64<F>, Number<F>), Error>;
+
+    /// Returns `(a - b mod 2^64, borrow)`.
+    fn overflowing_sub64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<(U64<F>, Number<F>), Error>;
+
+    /// Returns `(a * b mod 2^64, overflow)`.
+    fn overflowing_mul64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<(U64<F>, Number<F>), Error>;
+
+    /// Constrains the flag returned by an `overflowing_*` operation to be
+    /// unset and returns the result.
+    fn unwrap_flag(
+        &self,
+        layouter: impl Layouter<F>,
+        result: (U64<F>, Number<F>),
+    ) -> Result<U64<F>, Error>;
+
+    /// Returns `a + b mod 2^64`.
+    fn wrapping_add64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<U64<F>, Error> {
+        Ok(self.overflowing_add64(layouter, a, b)?.0)
+    }
+
+    /// Returns `a - b mod 2^64`.
+    fn wrapping_sub64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<U64<F>, Error> {
+        Ok(self.overflowing_sub64(layouter, a, b)?.0)
+    }
+
+    /// Returns `a * b mod 2^64`.
+    fn wrapping_mul64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<U64<F>, Error> {
+        Ok(self.overflowing_mul64(layouter, a, b)?.0)
+    }
+
+    /// Returns `a + b`, which must not overflow.
+    fn checked_add64(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<U64<F>, Error> {
+        let res = self.overflowing_add64(layouter.namespace(|| "add"), a, b)?;
+        self.unwrap_flag(layouter.namespace(|| "no carry"), res)
+    }
+
+    /// Returns `a - b`, which must not underflow.
+    fn checked_sub64(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<U64<F>, Error> {
+        let res = self.overflowing_sub64(layouter.namespace(|| "sub"), a, b)?;
+        self.unwrap_flag(layouter.namespace(|| "no borrow"), res)
+    }
+
+    /// Returns `a * b`, which must not overflow.
+    fn checked_mul64(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<U64<F>, Error> {
+        let res = self.overflowing_mul64(layouter.namespace(|| "mul"), a, b)?;
+        self.unwrap_flag(layouter.namespace(|| "no overflow"), res)
+    }
+}
+
+impl<F: FieldExt> U64Instructions<F> for FieldChip<F> {
+    fn load_u64(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<u64>,
+    ) -> Result<U64<F>, Error> {
+        let a = self.load_private(layouter.namespace(|| "u64"), value.map(F::from))?;
+        self.u64(layouter.namespace(|| "range"), a)
+    }
+
+    fn u64(&self, layouter: impl Layouter<F>, a: Number<F>) -> Result<U64<F>, Error> {
+        self.range_check_running_sum(layouter, a.clone(), 64)?;
+        Ok(U64(a))
+    }
+
+    fn overflowing_add64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<(U64<F>, Number<F>), Error> {
+        // the sum is decomposed on 64 bits
+        let (sum, carry) = self.checked_add(layouter, a.0, b.0, 64)?;
+        Ok((U64(sum), carry))
+    }
+
+    fn overflowing_sub64(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<(U64<F>, Number<F>), Error> {
+        // a - b = diff - borrow * 2^64 is laid out as
+        // diff + b = a + borrow * 2^64
+        let diff =
+            a.0.value
+                .and_then(|a| b.0.value.map(|b| lower_64(&a).wrapping_sub(lower_64(&b))));
+        let diff = self.load_u64(layouter.namespace(|| "a - b"), diff)?;
+        let (sum, borrow) =
+            self.overflowing_add64(layouter.namespace(|| "a - b + b"), diff.clone(), b)?;
+        self.assert_equal(layouter.namespace(|| "a"), sum.0, a.0)?;
+        Ok((diff, borrow))
+    }
+
+    fn overflowing_mul64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: U64<F>,
+        b: U64<F>,
+    ) -> Result<(U64<F>, Number<F>), Error> {
+        // the low half of the product is decomposed on 64 bits
+        let (low, overflow) = self.checked_mul(layouter, a.0, b.0, 64)?;
+        Ok((U64(low), overflow))
+    }
+
+    fn unwrap_flag(
+        &self,
+        mut layouter: impl Layouter<F>,
+        result: (U64<F>, Number<F>),
+    ) -> Result<U64<F>, Error> {
+        let zero = self.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        self.assert_equal(layouter.namespace(|| "flag unset"), result.1, zero)?;
+        Ok(result.0)
+    }
+}
+
+// The lower 64 bits of `v`.
+pub(crate) fn lower_64<F: FieldExt>(v: &F) -> u64 {
+    let bytes = v.to_bytes();
+    bytes[..8]
+        .iter()
+        .rev()
+        .fold(0, |acc, byte| (acc << 8) | *byte as u64)
+}
@@ -0,0 +1,246 @@
+// A small declarative IR to describe arithmetic statements without writing a
+// Circuit implementation, and the circuit interpreting it over FieldChip.
+// With the `serde` feature, a program can be deserialized, for example from
+// JSON: a^2 + b^2 + 3 = c^2 with c public and a ^ b exposed is
+// {
+//   "inputs": [{"name": "a"}, {"name": "b"}, {"name": "c", "public": true}],
+//   "ops": [
+//     {"op": "mul", "out": "a2", "a": "a", "b": "a"},
+//     {"op": "mul", "out": "b2", "a": "b", "b": "b"},
+//     {"op": "const", "out": "k", "value": 3},
+//     {"op": "add", "out": "s", "a": "a2", "b": "b2"},
+//     {"op": "add", "out": "lhs", "a": "s", "b": "k"},
+//     {"op": "mul", "out": "c2", "a": "c", "b": "c"},
+//     {"op": "assert_equal", "a": "lhs", "b": "c2"},
+//     {"op": "xor", "out": "x", "a": "a", "b": "b"}
+//   ],
+//   "public": ["x"]
+// }
+// The instance column holds the public inputs in declaration order, followed
+// by the exposed wires.
+use super::{FieldChip, FieldConfig, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use std::collections::HashMap;
+
+// Bit length of the bitwise table, so `xor` operands must fit in 4 bits and
+// the circuit needs at least 5 * 2^8 rows, that is k = 11.
+const XOR_BITLENGTH: usize = 4;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub(crate) struct Program {
+    pub(crate) inputs: Vec<Input>,
+    // operations in evaluation order, each one only refers to wires defined
+    // before it
+    pub(crate) ops: Vec<Op>,
+    // wires exposed as public inputs
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) public: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub(crate) struct Input {
+    pub(crate) name: String,
+    // public inputs are read from the instance column instead of the witness
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) public: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(tag = "op", rename_all = "snake_case")
+)]
+pub(crate) enum Op {
+    Const { out: String, value: u64 },
+    Add { out: String, a: String, b: String },
+    Mul { out: String, a: String, b: String },
+    Xor { out: String, a: String, b: String },
+    AssertEqual { a: String, b: String },
+}
+
+impl Program {
+    // Number of private input values the witness must provide.
+    pub(crate) fn num_private_inputs(&self) -> usize {
+        self.inputs.iter().filter(|i| !i.public).count()
+    }
+}
+
+// Wires defined so far, by name.
+struct Wires<F: FieldExt>(HashMap<String, Number<F>>);
+
+impl<F: FieldExt> Wires<F> {
+    fn get(&self, name: &str) -> Result<Number<F>, Error> {
+        self.0.get(name).cloned().ok_or(Error::SynthesisError)
+    }
+
+    fn define(&mut self, name: &str, num: Number<F>) -> Result<(), Error> {
+        match self.0.insert(name.to_string(), num) {
+            Some(_) => Err(Error::SynthesisError),
+            None => Ok(()),
+        }
+    }
+}
+
+pub(crate) struct IrCircuit<F: FieldExt> {
+    program: Program,
+    // values of the private inputs, in declaration order
+    witness: Vec<Option<F>>,
+}
+
+impl<F: FieldExt> IrCircuit<F> {
+    pub(crate) fn new(program: Program, witness: Vec<Option<F>>) -> Self {
+        Self { program, witness }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for IrCircuit<F> {
+    type Config = FieldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            program: self.program.clone(),
+            witness: vec![None; self.program.num_private_inputs()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FieldChip::configure_default(meta, XOR_BITLENGTH)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let field_chip = FieldChip::<F>::construct(config);
+        field_chip.load_bitwise_table(layouter.namespace(|| "bitwise table"))?;
+
+        let mut wires = Wires(HashMap::new());
+        let mut witness = self.witness.iter();
+        let mut row = 0;
+        for input in self.program.inputs.iter() {
+            let num = if input.public {
+                let num =
+                    field_chip.load_public(layouter.namespace(|| input.name.as_str()), row)?;
+                row += 1;
+                num
+            } else {
+                let value = witness.next().ok_or(Error::SynthesisError)?;
+                field_chip.load_private(layouter.namespace(|| input.name.as_str()), *value)?
+            };
+            wires.define(&input.name, num)?;
+        }
+
+        for op in self.program.ops.iter() {
+            match op {
+                Op::Const { out, value } => {
+                    let num = field_chip
+                        .load_constant(layouter.namespace(|| out.as_str()), F::from(*value))?;
+                    wires.define(out, num)?;
+                }
+                Op::Add { out, a, b } => {
+                    let num = field_chip.add(
+                        layouter.namespace(|| out.as_str()),
+                        wires.get(a)?,
+                        wires.get(b)?,
+                    )?;
+                    wires.define(out, num)?;
+                }
+                Op::Mul { out, a, b } => {
+                    let num = field_chip.mul(
+                        layouter.namespace(|| out.as_str()),
+                        wires.get(a)?,
+                        wires.get(b)?,
+                    )?;
+                    wires.define(out, num)?;
+                }
+                Op::Xor { out, a, b } => {
+                    let num = field_chip.xor(
+                        layouter.namespace(|| out.as_str()),
+                        wires.get(a)?,
+                        wires.get(b)?,
+                    )?;
+                    wires.define(out, num)?;
+                }
+                Op::AssertEqual { a, b } => {
+                    field_chip.assert_equal(
+                        layouter.namespace(|| "assert equal"),
+                        wires.get(a)?,
+                        wires.get(b)?,
+                    )?;
+                }
+            }
+        }
+
+        for name in self.program.public.iter() {
+            field_chip.expose_public(
+                layouter.namespace(|| name.as_str()),
+                wires.get(name)?,
+                row,
+            )?;
+            row += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use halo2::{dev::MockProver, pasta::Fp};
+
+    const PYTHAGORE: &str = r#"{
+        "inputs": [{"name": "a"}, {"name": "b"}, {"name": "c", "public": true}],
+        "ops": [
+            {"op": "mul", "out": "a2", "a": "a", "b": "a"},
+            {"op": "mul", "out": "b2", "a": "b", "b": "b"},
+            {"op": "const", "out": "k", "value": 3},
+            {"op": "add", "out": "s", "a": "a2", "b": "b2"},
+            {"op": "add", "out": "lhs", "a": "s", "b": "k"},
+            {"op": "mul", "out": "c2", "a": "c", "b": "c"},
+            {"op": "assert_equal", "a": "lhs", "b": "c2"},
+            {"op": "xor", "out": "x", "a": "a", "b": "b"}
+        ],
+        "public": ["x"]
+    }"#;
+
+    #[test]
+    fn pythagore() {
+        let program: Program = serde_json::from_str(PYTHAGORE).unwrap();
+        let k = 11;
+        // 2^2 + 3^2 + 3 = 4^2 and 2 ^ 3 = 1
+        let circuit = IrCircuit::new(program.clone(), vec![Some(Fp::from(2)), Some(Fp::from(3))]);
+        let public_inputs = vec![Fp::from(4), Fp::from(1)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let public_inputs = vec![Fp::from(5), Fp::from(1)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+
+        let public_inputs = vec![Fp::from(4), Fp::from(2)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn undefined_wire() {
+        let program: Program = serde_json::from_str(
+            r#"{
+                "inputs": [{"name": "a"}],
+                "ops": [{"op": "add", "out": "b", "a": "a", "b": "c"}]
+            }"#,
+        )
+        .unwrap();
+        let circuit = IrCircuit::new(program, vec![Some(Fp::from(2))]);
+        assert!(MockProver::run(11, &circuit, vec![vec![]]).is_err());
+    }
+}
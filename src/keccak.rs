@@ -0,0 +1,331 @@
+// Keccak-256 over FieldChip, the hash used by Ethereum. The state is made of
+// 25 lanes of 64 bits, indexed by x + 5 * y, each held in a single Number:
+// * XOR and AND are applied chunk by chunk in the bitwise table, with
+//   `xor_wide` and `and_wide`, which also bound the results to 64 bits,
+// * rotations permute the bit decomposition of the lane, with `rotl`,
+// * NOT is the affine map 2^64 - 1 - a, as the lanes fit in 64 bits.
+// Messages are absorbed as bytes looked up in the byte table, so both the
+// bitwise and the byte tables must be loaded.
+use super::{
+    bytes::{Byte, ByteInstructions},
+    pow2, FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+// Bytes absorbed per permutation, for a capacity of 512 bits.
+const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+// Rotation offsets of the rho step, indexed by x + 5 * y.
+const ROTATIONS: [usize; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+// Keccak-f[1600] on the 25 lanes of `a`, computed natively.
+fn keccak_f_native(a: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        let c: Vec<u64> = (0..5)
+            .map(|x| (0..5).fold(0, |acc, y| acc ^ a[x + 5 * y]))
+            .collect();
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y] as u32);
+            }
+        }
+        for x in 0..5 {
+            for y in 0..5 {
+                a[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+        a[0] ^= rc;
+    }
+}
+
+// The Keccak-256 digest of `input`, computed natively.
+pub(crate) fn keccak256_native(input: &[u8]) -> [u8; 32] {
+    let blocks = input.len() / RATE + 1;
+    let mut message = input.to_vec();
+    message.resize(blocks * RATE, 0);
+    message[input.len()] |= 0x01;
+    message[blocks * RATE - 1] |= 0x80;
+    let mut state = [0u64; 25];
+    for block in message.chunks(RATE) {
+        for (i, lane) in block.chunks(8).enumerate() {
+            state[i] ^= lane
+                .iter()
+                .rev()
+                .fold(0, |acc, byte| (acc << 8) | *byte as u64);
+        }
+        keccak_f_native(&mut state);
+    }
+    let mut digest = [0u8; 32];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = (state[i / 8] >> (8 * (i % 8))) as u8;
+    }
+    digest
+}
+
+pub(crate) trait KeccakInstructions<F: FieldExt> {
+    /// Returns the Keccak-f[1600] permutation of the 25 lanes of `state`,
+    /// which must fit in 64 bits.
+    fn keccak_f(
+        &self,
+        layouter: impl Layouter<F>,
+        state: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error>;
+
+    /// Returns the 32 bytes of the Keccak-256 digest of `input`.
+    fn keccak256(
+        &self,
+        layouter: impl Layouter<F>,
+        input: &[Byte<F>],
+    ) -> Result<Vec<Byte<F>>, Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Returns `a ^ b` for 64-bit lanes.
+    fn xor64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        self.xor_wide(layouter, a, b, 64)
+    }
+
+    // Returns `!a & b` for 64-bit lanes.
+    fn andn64(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let neg = self.neg(layouter.namespace(|| "-a"), a)?;
+        let not = self.add_const(layouter.namespace(|| "!a"), neg, pow2::<F>(64) - F::one())?;
+        self.and_wide(layouter.namespace(|| "!a & b"), not, b, 64)
+    }
+
+    // Returns the 64-bit lane `a` rotated left by `n` bits.
+    fn rotl64(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        n: usize,
+    ) -> Result<Number<F>, Error> {
+        match n {
+            0 => Ok(a),
+            n => self.rotl(layouter, a, n, 64),
+        }
+    }
+}
+
+impl<F: FieldExt> KeccakInstructions<F> for FieldChip<F> {
+    fn keccak_f(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error> {
+        if state.len() != 25 {
+            return Err(Error::SynthesisError);
+        }
+        let mut a = state.to_vec();
+        for (round, rc) in ROUND_CONSTANTS.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("round {}", round));
+
+            // theta: every lane is XORed with the parities of two columns
+            let mut c = Vec::with_capacity(5);
+            for x in 0..5 {
+                let mut parity = a[x].clone();
+                for y in 1..5 {
+                    parity = self.xor64(
+                        layouter.namespace(|| "column parity"),
+                        parity,
+                        a[x + 5 * y].clone(),
+                    )?;
+                }
+                c.push(parity);
+            }
+            for x in 0..5 {
+                let rot = self.rotl64(layouter.namespace(|| "rot"), c[(x + 1) % 5].clone(), 1)?;
+                let d = self.xor64(layouter.namespace(|| "d"), c[(x + 4) % 5].clone(), rot)?;
+                for y in 0..5 {
+                    a[x + 5 * y] = self.xor64(
+                        layouter.namespace(|| "theta"),
+                        a[x + 5 * y].clone(),
+                        d.clone(),
+                    )?;
+                }
+            }
+
+            // rho and pi: every lane is rotated and moved
+            let mut b = a.clone();
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = self.rotl64(
+                        layouter.namespace(|| "rho"),
+                        a[x + 5 * y].clone(),
+                        ROTATIONS[x + 5 * y],
+                    )?;
+                }
+            }
+
+            // chi: the only non-linear step
+            for x in 0..5 {
+                for y in 0..5 {
+                    let t = self.andn64(
+                        layouter.namespace(|| "chi"),
+                        b[(x + 1) % 5 + 5 * y].clone(),
+                        b[(x + 2) % 5 + 5 * y].clone(),
+                    )?;
+                    a[x + 5 * y] =
+                        self.xor64(layouter.namespace(|| "chi"), b[x + 5 * y].clone(), t)?;
+                }
+            }
+
+            // iota
+            let rc = self.load_constant(layouter.namespace(|| "round constant"), F::from(*rc))?;
+            a[0] = self.xor64(layouter.namespace(|| "iota"), a[0].clone(), rc)?;
+        }
+        Ok(a)
+    }
+
+    fn keccak256(
+        &self,
+        mut layouter: impl Layouter<F>,
+        input: &[Byte<F>],
+    ) -> Result<Vec<Byte<F>>, Error> {
+        // pad10*1 with the 0x01 domain byte of Keccak, rather than the 0x06
+        // of SHA-3
+        let blocks = input.len() / RATE + 1;
+        let mut padding = vec![0u8; blocks * RATE - input.len()];
+        padding[0] |= 0x01;
+        let last = padding.len() - 1;
+        padding[last] |= 0x80;
+        let mut message = input.to_vec();
+        for byte in padding {
+            let byte =
+                self.load_constant(layouter.namespace(|| "padding"), F::from(byte as u64))?;
+            message.push(self.byte(layouter.namespace(|| "padding byte"), byte)?);
+        }
+
+        let zero = self.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        let mut state = vec![zero; 25];
+        for block in message.chunks(RATE) {
+            // the lanes are little-endian
+            for (i, lane) in block.chunks(8).enumerate() {
+                let lane = self.from_bytes(layouter.namespace(|| "lane"), lane)?;
+                state[i] = self.xor64(layouter.namespace(|| "absorb"), state[i].clone(), lane)?;
+            }
+            state = self.keccak_f(layouter.namespace(|| "keccak-f"), &state)?;
+        }
+
+        let mut digest = Vec::with_capacity(32);
+        for lane in state[..4].iter() {
+            digest.extend(self.to_bytes(layouter.namespace(|| "squeeze"), lane.clone(), 8)?);
+        }
+        Ok(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use halo2::pasta::Fp;
+
+    // [bytes] -> [32 bytes of the digest]
+    #[derive(Clone, Debug)]
+    struct Keccak256;
+
+    impl TestableGadget<Fp> for Keccak256 {
+        // a single sample as every run lays out a whole permutation, the
+        // empty input being checked by the native test only
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            vec![b"abc".iter().map(|b| Fp::from(*b as u64)).collect()]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            // not a byte
+            vec![vec![Fp::from(0x61), Fp::from(0x162)]]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let bytes: Vec<u8> = inputs.iter().map(|b| b.get_lower_32() as u8).collect();
+            keccak256_native(&bytes)
+                .iter()
+                .map(|b| Fp::from(*b as u64))
+                .collect()
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let bytes = inputs
+                .iter()
+                .map(|a| chip.byte(layouter.namespace(|| "byte"), a.clone()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(chip
+                .keccak256(layouter, &bytes)?
+                .iter()
+                .map(|byte| byte.num())
+                .collect())
+        }
+    }
+
+    #[test]
+    fn native() {
+        let hex =
+            |digest: [u8; 32]| -> String { digest.iter().map(|b| format!("{:02x}", b)).collect() };
+        assert_eq!(
+            hex(keccak256_native(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(keccak256_native(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn keccak256() {
+        // a permutation takes about 2^18 rows with the 4-bit bitwise table
+        check(&Keccak256, 19);
+    }
+}
@@ -9,22 +9,52 @@ extern crate halo2;
 
 use halo2::{
     arithmetic::FieldExt,
-    circuit::{Cell, Chip, Layouter, SimpleFloorPlanner},
+    circuit::{Cell, Chip, Layouter, Region, SimpleFloorPlanner},
     dev::CircuitGates,
     plonk::{
-        Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector, TableColumn,
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+        TableColumn,
     },
     poly::Rotation,
 };
 use std::marker::PhantomData;
 
+#[cfg(feature = "serde")]
+mod acir;
+mod bigint;
+mod bytes;
+mod cache;
+mod cost;
+mod coverage;
+mod dynamic;
+mod ecc;
+mod elgamal;
+mod expr;
+mod harness;
+mod integer;
+mod ir;
+mod keccak;
+mod merkle;
+mod merkle_damgard;
+mod mimc;
+mod note;
+mod pedersen;
+mod proof;
+mod r1cs;
+mod rsa;
+mod schnorr;
+mod set;
+mod transcript;
+mod vectors;
+mod word32;
+
 trait NumericInstructions<F: FieldExt>: Chip<F> {
     type Num;
 
     fn load_private(&self, layouter: impl Layouter<F>, a: Option<F>) -> Result<Self::Num, Error>;
     fn load_constant(&self, layouter: impl Layouter<F>, constant: F) -> Result<Self::Num, Error>;
     fn load_public(&self, layouter: impl Layouter<F>, row: usize) -> Result<Self::Num, Error>;
-    fn load_xor_table(&self, layouter: impl Layouter<F>) -> Result<(), Error>;
+    fn load_bitwise_table(&self, layouter: impl Layouter<F>) -> Result<(), Error>;
 
     fn expose_public(
         &self,
@@ -52,6 +82,472 @@ trait NumericInstructions<F: FieldExt>: Chip<F> {
         a: Self::Num,
         b: Self::Num,
     ) -> Result<Self::Num, Error>;
+
+    /// Returns `a & b` with a lookup in the AND table, so both operands must
+    /// fit in the bit length of the table.
+    fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a | b` with a lookup in the OR table.
+    fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `!(a & b)` over the bit length of the tables, with a lookup in
+    /// the NAND table.
+    fn nand(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `!(a ^ b)` over the bit length of the tables, with a lookup in
+    /// the XNOR table.
+    fn xnor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Evaluates at `z` the polynomial of degree `< xs.len()` going through
+    /// the points `(xs[i], ys[i])`. The nodes `xs` must be distinct.
+    fn interpolate(
+        &self,
+        layouter: impl Layouter<F>,
+        xs: &[F],
+        ys: &[Self::Num],
+        z: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Computes the radix-2 NTT `X_k = sum_j x_j * w^(jk)` of `values`, where
+    /// `w` is the root of unity of order `values.len()`, a power of two.
+    fn ntt(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error>;
+
+    /// Constrains `a` to fit in `num_bits` bits.
+    fn range_check(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        num_bits: usize,
+    ) -> Result<(), Error>;
+
+    /// Constrains `a` to fit in `num_bits` bits by looking up its windows of
+    /// the bit length of the bitwise table, the remaining top bits being
+    /// decomposed as in `range_check`. Cheaper than `range_check` for wide
+    /// values.
+    fn range_check_running_sum(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        num_bits: usize,
+    ) -> Result<(), Error>;
+
+    /// Returns the `num_bits` little-endian bits of `a`, which must fit in
+    /// `num_bits` bits.
+    fn decompose_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        num_bits: usize,
+    ) -> Result<Vec<Self::Num>, Error>;
+
+    /// Returns `(a + b mod 2^n_bits, overflow)` where `overflow` is a boolean
+    /// set when the sum does not fit in `n_bits`. Both operands must already be
+    /// known to fit in `n_bits`, e.g. with `range_check`.
+    fn checked_add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        n_bits: usize,
+    ) -> Result<(Self::Num, Self::Num), Error>;
+
+    /// Returns `(a * b mod 2^n_bits, overflow)` where `overflow` is a boolean
+    /// set when the product does not fit in `n_bits`. Both operands must
+    /// already be known to fit in `n_bits`, e.g. with `range_check`.
+    fn checked_mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        n_bits: usize,
+    ) -> Result<(Self::Num, Self::Num), Error>;
+
+    /// Returns `(x >> k, x mod 2^k)` for `x` fitting in `n_bits` bits.
+    fn div_pow2(
+        &self,
+        layouter: impl Layouter<F>,
+        x: Self::Num,
+        k: usize,
+        n_bits: usize,
+    ) -> Result<(Self::Num, Self::Num), Error>;
+
+    /// Returns `n` booleans where only the one at position `index` is set.
+    /// The constraints are unsatisfied if `index >= n`.
+    fn one_hot(
+        &self,
+        layouter: impl Layouter<F>,
+        index: Self::Num,
+        n: usize,
+    ) -> Result<Vec<Self::Num>, Error>;
+
+    /// Returns `vals[index]`. Fails to synthesize if `index >= vals.len()`.
+    fn select_from(
+        &self,
+        layouter: impl Layouter<F>,
+        vals: &[Self::Num],
+        index: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns 1 if the byte string `a` is lexicographically smaller than `b`
+    /// and 0 otherwise. Both must have the same length and every element must
+    /// already be known to be a byte, e.g. with `range_check`.
+    fn lex_lt(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Bit<F>, Error>;
+
+    /// Returns `a^-1`. Fails to synthesize if `a` is zero.
+    fn invert(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error>;
+
+    /// Returns `a / b`. Fails to synthesize if `b` is zero.
+    fn div(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a * b + c` in a single region.
+    fn mul_add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        c: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `-a`.
+    fn neg(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error>;
+
+    /// Returns `a^2`, copying `a` only once.
+    fn square(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error>;
+
+    /// Returns `sum_i c_i * x_i` over the `terms` `(c_i, x_i)`, laid out as a
+    /// running sum in a single region.
+    fn linear_combination(
+        &self,
+        layouter: impl Layouter<F>,
+        terms: &[(F, Self::Num)],
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `sum_i a_i * b_i`. Both slices must have the same length.
+    fn inner_product(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a + k` without loading `k` in a cell.
+    fn add_const(&self, layouter: impl Layouter<F>, a: Self::Num, k: F)
+        -> Result<Self::Num, Error>;
+
+    /// Returns `a * k` without loading `k` in a cell.
+    fn mul_const(&self, layouter: impl Layouter<F>, a: Self::Num, k: F)
+        -> Result<Self::Num, Error>;
+
+    /// Returns 1 if `a` is zero and 0 otherwise.
+    fn is_zero(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Bit<F>, Error>;
+
+    /// Returns 1 if `a == b` and 0 otherwise.
+    fn is_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Bit<F>, Error>;
+
+    /// Constrains `a * (a - 1) == 0` and returns `a` as a bit.
+    fn assert_bool(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Bit<F>, Error>;
+
+    /// Constrains `a` and `b` to be equal with a copy constraint, without
+    /// assigning any cell.
+    fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<(), Error>;
+
+    /// Returns `a^-1`, or 0 if `a` is zero.
+    fn invert_or_zero(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error>;
+
+    /// Returns `a[i] + b[i]` for every `i`, in a single region.
+    fn add_many(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error>;
+
+    /// Returns `a[i] * b[i]` for every `i`, in a single region.
+    fn mul_many(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error>;
+
+    /// Returns `a[i] - b[i]` for every `i`, in a single region.
+    fn sub_many(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error>;
+
+    /// Returns the sum of `values` as a running sum down a single region.
+    /// Fails to synthesize if `values` is empty.
+    fn accumulate(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Self::Num],
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `out` such that `q_l * a + q_r * b + q_m * a * b + q_o * out +
+    /// q_c = 0`. Fails to synthesize if `q_o` is zero.
+    fn generic_op(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        q: GenericCoeffs<F>,
+    ) -> Result<Self::Num, Error>;
+
+    /// Constrains `a` and `b` to be different by showing `a - b` has an
+    /// inverse. Fails to synthesize if they are equal.
+    fn assert_not_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<(), Error>;
+
+    /// Returns `a * x + b`.
+    fn affine(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        x: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the complement `2^bitlength - 1 - a` of `a`, which must fit in
+    /// `bitlength` bits, at most the bit length of the tables.
+    fn not(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        bitlength: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a << n` truncated to `word_size` bits. `a` must fit in
+    /// `word_size` bits, as for the other shifts and rotations.
+    fn shl(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a >> n`.
+    fn shr(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a` rotated left by `n` bits within a word of `word_size`
+    /// bits, `word_size` being non zero and at most the field capacity.
+    fn rotl(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a` rotated right by `n` bits within a word of `word_size`
+    /// bits, as `rotl`.
+    fn rotr(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a ^ b` for operands of `total_bits` bits, XORing them chunk by
+    /// chunk in the bitwise table. `total_bits` is rounded up to a multiple of
+    /// the bit length of the table.
+    fn xor_wide(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        total_bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns `a & b` for operands of `total_bits` bits, as `xor_wide`.
+    fn and_wide(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        total_bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the number of set bits among the `bits` low bits of `a`,
+    /// which must fit in `bits` bits.
+    fn popcount(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the XOR of the `bits` low bits of `a`, which must fit in
+    /// `bits` bits.
+    fn parity(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the number whose bits are `bits`, in the given order.
+    fn pack_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[Bit<F>],
+        order: BitOrder,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the `n` bits of `a` in the given order. `a` must fit in `n`
+    /// bits.
+    fn unpack(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        order: BitOrder,
+    ) -> Result<Vec<Bit<F>>, Error>;
+
+    /// Returns the most significant of the `bits` bits of `a`, which must
+    /// fit in `bits` bits.
+    fn msb(&self, layouter: impl Layouter<F>, a: Self::Num, bits: usize) -> Result<Bit<F>, Error>;
+
+    /// Returns 1 if `a < b` and 0 otherwise. Both operands must already be
+    /// known to fit in `bits` bits, e.g. with `range_check`.
+    fn lt(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Bit<F>, Error>;
+
+    /// Returns `a` if `bit` is set and `b` otherwise.
+    fn select(
+        &self,
+        layouter: impl Layouter<F>,
+        bit: Bit<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the smaller of `a` and `b`, which must fit in `bits` bits.
+    fn min(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns the larger of `a` and `b`, which must fit in `bits` bits.
+    fn max(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Constrains `lo <= a <= hi`. Fails to synthesize if `lo > hi`.
+    fn assert_in_range(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        lo: u64,
+        hi: u64,
+    ) -> Result<(), Error>;
+
+    /// Returns `(b, a)` if `bit` is set and `(a, b)` otherwise.
+    fn cond_swap(
+        &self,
+        layouter: impl Layouter<F>,
+        bit: Bit<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<(Self::Num, Self::Num), Error>;
+
+    /// Constrains `values` to be sorted in non-decreasing order. Every value
+    /// must already be known to fit in `bits` bits, e.g. with `range_check`.
+    fn assert_sorted(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Self::Num],
+        bits: usize,
+    ) -> Result<(), Error>;
+
+    /// Returns `|a - b|`. Both operands must already be known to fit in
+    /// `bits` bits, e.g. with `range_check`.
+    fn abs_diff(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error>;
+
+    /// Returns 1 if at least `k` of `bits` are set and 0 otherwise. Fails to
+    /// synthesize if `bits` is empty.
+    fn at_least_k_of_n(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[Bit<F>],
+        k: u64,
+    ) -> Result<Bit<F>, Error>;
 }
 
 struct FieldChip<F: FieldExt> {
@@ -80,36 +576,180 @@ struct FieldConfig {
     smul: Selector,
     sadd: Selector,
     constant: Column<Fixed>,
+    // per-row coefficients for the gates working on a run of rows, such as
+    // interpolation nodes and barycentric weights
+    coeff: Column<Fixed>,
+    // running product of (z - x_i) along the rows
+    sprod: Selector,
+    // accumulation of the barycentric sum
+    sbary: Selector,
+    // NTT butterfly with the twiddle factor in coeff
+    sbfly: Selector,
+    // boolean decomposition, accumulating bit * weight with the weight in coeff
+    sbits: Selector,
+    // additions and multiplications split at 2^n, with 2^n in coeff
+    scadd: Selector,
+    scmul: Selector,
+    // division by 2^k, with 2^k in coeff
+    sdivp2: Selector,
+    // accumulation of a_i * b_i over every other row
+    sdot: Selector,
+    // b - a + 2^n - 1 against its decomposition, with 2^n - 1 in coeff
+    slt: Selector,
+    // lexicographic comparison carrying the prefix equality flag
+    slex: Selector,
+    // a * inv = 1 on a single row
+    sinv: Selector,
+    // fused a * b + c over two rows
+    smuladd: Selector,
+    // a + out = 0 on a single row
+    sneg: Selector,
+    // a * a = out on a single row
+    ssquare: Selector,
+    // running sum of c * x with c in coeff
+    slc: Selector,
+    // a + k and a * k on a single row, with k in coeff
+    saddc: Selector,
+    smulc: Selector,
+    // out = 1 - a * inv with a * out = 0, inv being a^-1 or 0
+    siszero: Selector,
+    // a * out = 1 - z with a * z = 0 and z * out = 0, z being a == 0
+    sinvz: Selector,
+    // running sum acc = acc_prev + v
+    saccum: Selector,
+    // coefficients q_l, q_r, q_m, q_o, q_c of the generic gate, which is off
+    // wherever they are all zero
+    generic: [Column<Fixed>; 5],
+    // a + out = 2^bitlength - 1 with the mask in coeff, a and out being
+    // looked up as XOR operands in the bitwise table on the same row
+    snot: Selector,
+    // (a + k + c)^5 = a_next over two rows, with the key k in advices[1] and
+    // the round constant c in coeff
+    smimc: Selector,
+    // l_next = r + (l + c)^5 and r_next = l over two rows, with (l, r) in the
+    // advices and the round constant c in coeff
+    sfeistel: Selector,
 
-    // lookup is only relevant when this selector is set
+    // XOR lookup is only relevant when this selector is set
     stable: Selector,
     // size of the bits we are considering - directly impacting size of the
     // table
     xor_bitlength: usize,
-    // XOR table with three columns: a XOR b = c
-    xor_table: [TableColumn; 3],
+    // bitwise table with four columns: the tag of op, a, b and op(a, b)
+    bitwise_table: [TableColumn; 4],
     // the witness of the XOR'd value that we must give and then we can verify
     // if the triplet a XOR b = xord is inside the table columns
     xord: Column<Advice>,
+    // same as stable for AND, OR, NAND and XNOR, with the result in xord
+    sand: Selector,
+    sor: Selector,
+    snand: Selector,
+    sxnor: Selector,
+    // running sum z_i = w_i + 2^K * z_{i+1}, looking up the window w_i among
+    // the operands of the bitwise table, K being its bit length
+    srunsum: Selector,
+    // byte table with the values 0 to 255, looked up from advices[0] when
+    // sbyte is set
+    byte_table: TableColumn,
+    sbyte: Selector,
+    // set table with three columns: a tag, then (1, s, 0) for every element
+    // s of the set and (2, lo, hi) for every gap [lo, hi] between them. smember
+    // looks up (1, a, 0) and sgap (2, lo, hi), from advices[0] and advices[1]
+    set_table: [TableColumn; 3],
+    smember: Selector,
+    sgap: Selector,
+    // number of lookup arguments registered by configure
+    lookups: usize,
 }
 
-impl<F: FieldExt> FieldChip<F> {
-    fn construct(config: <Self as Chip<F>>::Config) -> Self {
-        Self {
-            config,
-            _marker: PhantomData,
-        }
+impl FieldConfig {
+    // The advice columns, xord included.
+    fn advice_columns(&self) -> [Column<Advice>; 3] {
+        [self.advices[0], self.advices[1], self.xord]
     }
-    fn configure(
-        meta: &mut ConstraintSystem<F>,
-        advices: [Column<Advice>; 2],
-        instance: Column<Instance>,
-        constant: Column<Fixed>,
-        xor_size: usize,
-        xor_table: [TableColumn; 3],
-        xord: Column<Advice>,
-    ) -> <Self as Chip<F>>::Config {
-        // enable equality because we will constraint it later on with another
+
+    // The fixed columns, lookup tables excluded.
+    fn fixed_columns(&self) -> [Column<Fixed>; 7] {
+        let g = self.generic;
+        [self.constant, self.coeff, g[0], g[1], g[2], g[3], g[4]]
+    }
+
+    // The columns of the bitwise, byte and set tables.
+    fn table_columns(&self) -> [TableColumn; 8] {
+        let (b, s) = (self.bitwise_table, self.set_table);
+        [b[0], b[1], b[2], b[3], self.byte_table, s[0], s[1], s[2]]
+    }
+
+    // The selector of each operation of the bitwise table.
+    fn bitwise_selectors(&self) -> [(Selector, BitwiseOp); 5] {
+        [
+            (self.stable, BitwiseOp::Xor),
+            (self.sand, BitwiseOp::And),
+            (self.sor, BitwiseOp::Or),
+            (self.snand, BitwiseOp::Nand),
+            (self.sxnor, BitwiseOp::Xnor),
+        ]
+    }
+}
+
+// The operations of the bitwise table, tagged by their discriminant. XOR has
+// tag 0 so that rows without any bitwise selector look up (0, 0, 0, 0).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BitwiseOp {
+    Xor,
+    And,
+    Or,
+    Nand,
+    Xnor,
+}
+
+impl BitwiseOp {
+    const ALL: [BitwiseOp; 5] = [
+        BitwiseOp::Xor,
+        BitwiseOp::And,
+        BitwiseOp::Or,
+        BitwiseOp::Nand,
+        BitwiseOp::Xnor,
+    ];
+
+    fn tag(self) -> u64 {
+        self as u64
+    }
+
+    // Computes a op b, truncated to `bits` bits.
+    fn apply(self, a: u64, b: u64, bits: usize) -> u64 {
+        let res = match self {
+            BitwiseOp::Xor => a ^ b,
+            BitwiseOp::And => a & b,
+            BitwiseOp::Or => a | b,
+            BitwiseOp::Nand => !(a & b),
+            BitwiseOp::Xnor => !(a ^ b),
+        };
+        res & ((1 << bits) - 1)
+    }
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    fn construct(config: <Self as Chip<F>>::Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advices: [Column<Advice>; 2],
+        instance: Column<Instance>,
+        constant: Column<Fixed>,
+        coeff: Column<Fixed>,
+        generic: [Column<Fixed>; 5],
+        xor_size: usize,
+        bitwise_table: [TableColumn; 4],
+        xord: Column<Advice>,
+        byte_table: TableColumn,
+        set_table: [TableColumn; 3],
+    ) -> <Self as Chip<F>>::Config {
+        // enable equality because we will constraint it later on with another
         // cell
         meta.enable_equality(instance.into());
         meta.enable_constant(constant);
@@ -121,6 +761,37 @@ impl<F: FieldExt> FieldChip<F> {
         let smul = meta.selector();
         let sadd = meta.selector();
         let stable = meta.complex_selector();
+        let sand = meta.complex_selector();
+        let sor = meta.complex_selector();
+        let snand = meta.complex_selector();
+        let sxnor = meta.complex_selector();
+        let srunsum = meta.complex_selector();
+        let sbyte = meta.complex_selector();
+        let smember = meta.complex_selector();
+        let sgap = meta.complex_selector();
+        let sprod = meta.selector();
+        let sbary = meta.selector();
+        let sbfly = meta.selector();
+        let sbits = meta.selector();
+        let scadd = meta.selector();
+        let scmul = meta.selector();
+        let sdivp2 = meta.selector();
+        let sdot = meta.selector();
+        let slt = meta.selector();
+        let slex = meta.selector();
+        let sinv = meta.selector();
+        let smuladd = meta.selector();
+        let sneg = meta.selector();
+        let ssquare = meta.selector();
+        let slc = meta.selector();
+        let saddc = meta.selector();
+        let smulc = meta.selector();
+        let siszero = meta.selector();
+        let sinvz = meta.selector();
+        let saccum = meta.selector();
+        let snot = meta.selector();
+        let smimc = meta.selector();
+        let sfeistel = meta.selector();
 
         meta.create_gate("mul", |meta| {
             let lhs = meta.query_advice(advices[0], Rotation::cur());
@@ -138,290 +809,2732 @@ impl<F: FieldExt> FieldChip<F> {
             vec![sel * ((lhs + rhs) - out)]
         });
 
-        meta.lookup(|meta| {
+        // prod | z      <- x in coeff
+        // prod * (z - x) | z
+        meta.create_gate("running product", |meta| {
+            let prod = meta.query_advice(advices[0], Rotation::cur());
+            let z = meta.query_advice(advices[1], Rotation::cur());
+            let x = meta.query_fixed(coeff, Rotation::cur());
+            let next_prod = meta.query_advice(advices[0], Rotation::next());
+            let next_z = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(sprod);
+            vec![
+                sel.clone() * (prod * (z.clone() - x) - next_prod),
+                sel * (next_z - z),
+            ]
+        });
+
+        //   -    | acc
+        // prefix | suffix  <- weight in coeff
+        //   y    | acc + weight * y * prefix * suffix
+        meta.create_gate("barycentric sum", |meta| {
+            let acc = meta.query_advice(advices[1], Rotation::prev());
+            let prefix = meta.query_advice(advices[0], Rotation::cur());
+            let suffix = meta.query_advice(advices[1], Rotation::cur());
+            let weight = meta.query_fixed(coeff, Rotation::cur());
+            let y = meta.query_advice(advices[0], Rotation::next());
+            let next_acc = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(sbary);
+            vec![sel * (acc + weight * y * prefix * suffix - next_acc)]
+        });
+
+        //   a   |   b      <- w in coeff
+        // a + wb | a - wb
+        meta.create_gate("butterfly", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let w = meta.query_fixed(coeff, Rotation::cur());
+            let sum = meta.query_advice(advices[0], Rotation::next());
+            let diff = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(sbfly);
+            let wb = w * b;
+            vec![
+                sel.clone() * (a.clone() + wb.clone() - sum),
+                sel * (a - wb - diff),
+            ]
+        });
+
+        // acc | bit   <- weight in coeff
+        // acc + weight * bit
+        meta.create_gate("bit decomposition", |meta| {
+            let acc = meta.query_advice(advices[0], Rotation::cur());
+            let bit = meta.query_advice(advices[1], Rotation::cur());
+            let weight = meta.query_fixed(coeff, Rotation::cur());
+            let next_acc = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(sbits);
+            let one = Expression::Constant(F::one());
+            vec![
+                sel.clone() * (acc + weight * bit.clone() - next_acc),
+                sel * bit.clone() * (one - bit),
+            ]
+        });
+
+        //   a    |    b      <- 2^n in coeff
+        // result | overflow
+        meta.create_gate("checked add", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let pow = meta.query_fixed(coeff, Rotation::cur());
+            let result = meta.query_advice(advices[0], Rotation::next());
+            let overflow = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(scadd);
+            let one = Expression::Constant(F::one());
+            vec![
+                sel.clone() * (a + b - result - pow * overflow.clone()),
+                sel * overflow.clone() * (one - overflow),
+            ]
+        });
+
+        //  a  |    b      <- 2^n in coeff
+        // low |   high
+        // inv | overflow
+        // overflow = high * inv is 1 iff high != 0, inv being high^-1 or 0
+        meta.create_gate("checked mul", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let pow = meta.query_fixed(coeff, Rotation::cur());
+            let low = meta.query_advice(advices[0], Rotation::next());
+            let high = meta.query_advice(advices[1], Rotation::next());
+            let inv = meta.query_advice(advices[0], Rotation(2));
+            let overflow = meta.query_advice(advices[1], Rotation(2));
+            let sel = meta.query_selector(scmul);
+            let one = Expression::Constant(F::one());
+            vec![
+                sel.clone() * (a * b - low - pow * high.clone()),
+                sel.clone() * (high.clone() * inv - overflow.clone()),
+                sel * high * (one - overflow),
+            ]
+        });
+
+        // x | q   <- 2^k in coeff
+        // r |
+        meta.create_gate("div by 2^k", |meta| {
+            let x = meta.query_advice(advices[0], Rotation::cur());
+            let q = meta.query_advice(advices[1], Rotation::cur());
+            let pow = meta.query_fixed(coeff, Rotation::cur());
+            let r = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(sdivp2);
+            vec![sel * (x - pow * q - r)]
+        });
+
+        // acc |
+        //  a  | b
+        // acc + a * b |
+        meta.create_gate("inner product", |meta| {
+            let acc = meta.query_advice(advices[0], Rotation::prev());
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let next_acc = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(sdot);
+            vec![sel * (acc + a * b - next_acc)]
+        });
+
+        // d |        (decomposed on the rows above)
+        // a | b      <- 2^n - 1 in coeff
+        // d has its bit n set iff a < b, when both fit in n bits
+        meta.create_gate("less than", |meta| {
+            let d = meta.query_advice(advices[0], Rotation::prev());
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let shift = meta.query_fixed(coeff, Rotation::cur());
+            let sel = meta.query_selector(slt);
+            vec![sel * (b - a + shift - d)]
+        });
+
+        // eq | lt
+        // a < b | a > b
+        // eq * (a == b) | lt + eq * (a < b)
+        meta.create_gate("lexicographic order", |meta| {
+            let eq = meta.query_advice(advices[0], Rotation::prev());
+            let lt = meta.query_advice(advices[1], Rotation::prev());
+            let less = meta.query_advice(advices[0], Rotation::cur());
+            let greater = meta.query_advice(advices[1], Rotation::cur());
+            let next_eq = meta.query_advice(advices[0], Rotation::next());
+            let next_lt = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(slex);
+            let one = Expression::Constant(F::one());
+            vec![
+                sel.clone() * (eq.clone() * (one - less.clone() - greater) - next_eq),
+                sel * (lt + eq * less - next_lt),
+            ]
+        });
+
+        // a | inv
+        meta.create_gate("invert", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let inv = meta.query_advice(advices[1], Rotation::cur());
+            let sel = meta.query_selector(sinv);
+            let one = Expression::Constant(F::one());
+            vec![sel * (a * inv - one)]
+        });
+
+        // a | b
+        // c | a * b + c
+        meta.create_gate("mul add", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let c = meta.query_advice(advices[0], Rotation::next());
+            let out = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(smuladd);
+            vec![sel * (a * b + c - out)]
+        });
+
+        // a | -a
+        meta.create_gate("neg", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let out = meta.query_advice(advices[1], Rotation::cur());
+            let sel = meta.query_selector(sneg);
+            vec![sel * (a + out)]
+        });
+
+        // a | a^2
+        meta.create_gate("square", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let out = meta.query_advice(advices[1], Rotation::cur());
+            let sel = meta.query_selector(ssquare);
+            vec![sel * (a.clone() * a - out)]
+        });
+
+        // acc | x   <- c in coeff
+        // acc + c * x
+        meta.create_gate("linear combination", |meta| {
+            let acc = meta.query_advice(advices[0], Rotation::cur());
+            let x = meta.query_advice(advices[1], Rotation::cur());
+            let c = meta.query_fixed(coeff, Rotation::cur());
+            let next_acc = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(slc);
+            vec![sel * (acc + c * x - next_acc)]
+        });
+
+        // a | a + k   <- k in coeff
+        meta.create_gate("add constant", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let out = meta.query_advice(advices[1], Rotation::cur());
+            let k = meta.query_fixed(coeff, Rotation::cur());
+            let sel = meta.query_selector(saddc);
+            vec![sel * (a + k - out)]
+        });
+
+        // a | a * k   <- k in coeff
+        meta.create_gate("mul constant", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let out = meta.query_advice(advices[1], Rotation::cur());
+            let k = meta.query_fixed(coeff, Rotation::cur());
+            let sel = meta.query_selector(smulc);
+            vec![sel * (a * k - out)]
+        });
+
+        //  a  | inv
+        // out |
+        meta.create_gate("is zero", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let inv = meta.query_advice(advices[1], Rotation::cur());
+            let out = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(siszero);
+            let one = Expression::Constant(F::one());
+            vec![
+                sel.clone() * (a.clone() * inv + out.clone() - one),
+                sel * a * out,
+            ]
+        });
+
+        // a | out
+        // z |
+        meta.create_gate("invert or zero", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let out = meta.query_advice(advices[1], Rotation::cur());
+            let z = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(sinvz);
+            let one = Expression::Constant(F::one());
+            vec![
+                sel.clone() * (a.clone() * out.clone() + z.clone() - one),
+                sel.clone() * a * z.clone(),
+                sel * z * out,
+            ]
+        });
+
+        // acc_prev |
+        //   acc    | v
+        meta.create_gate("accumulate", |meta| {
+            let prev = meta.query_advice(advices[0], Rotation::prev());
+            let acc = meta.query_advice(advices[0], Rotation::cur());
+            let v = meta.query_advice(advices[1], Rotation::cur());
+            let sel = meta.query_selector(saccum);
+            vec![sel * (prev + v - acc)]
+        });
+
+        // a   | b     <- q_l, q_r, q_m, q_o, q_c in the generic columns
+        // out |
+        meta.create_gate("generic", |meta| {
             let a = meta.query_advice(advices[0], Rotation::cur());
             let b = meta.query_advice(advices[1], Rotation::cur());
-            let xored = meta.query_advice(xord, Rotation::cur());
-            let sel = meta.query_selector(stable);
+            let out = meta.query_advice(advices[0], Rotation::next());
+            let q: Vec<_> = generic
+                .iter()
+                .map(|c| meta.query_fixed(*c, Rotation::cur()))
+                .collect();
+            vec![
+                q[0].clone() * a.clone()
+                    + q[1].clone() * b.clone()
+                    + q[2].clone() * a * b
+                    + q[3].clone() * out
+                    + q[4].clone(),
+            ]
+        });
+
+        // a | out   <- 2^bitlength - 1 in coeff
+        meta.create_gate("not", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let out = meta.query_advice(advices[1], Rotation::cur());
+            let mask = meta.query_fixed(coeff, Rotation::cur());
+            let sel = meta.query_selector(snot);
+            vec![sel * (a + out - mask)]
+        });
+
+        meta.create_gate("mimc round", |meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let k = meta.query_advice(advices[1], Rotation::cur());
+            let c = meta.query_fixed(coeff, Rotation::cur());
+            let out = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(smimc);
+            let t = a + k + c;
+            vec![sel * (t.clone() * t.clone() * t.clone() * t.clone() * t - out)]
+        });
+
+        meta.create_gate("feistel round", |meta| {
+            let l = meta.query_advice(advices[0], Rotation::cur());
+            let r = meta.query_advice(advices[1], Rotation::cur());
+            let c = meta.query_fixed(coeff, Rotation::cur());
+            let l_next = meta.query_advice(advices[0], Rotation::next());
+            let r_next = meta.query_advice(advices[1], Rotation::next());
+            let sel = meta.query_selector(sfeistel);
+            let t = l.clone() + c;
             vec![
-                (sel.clone() * a, xor_table[0]),
-                (sel.clone() * b, xor_table[1]),
-                (sel.clone() * xored, xor_table[2]),
+                sel.clone() * (r + t.clone() * t.clone() * t.clone() * t.clone() * t - l_next),
+                sel * (r_next - l),
             ]
         });
 
-        FieldConfig {
+        let mut config = FieldConfig {
             advices: advices,
             instance: instance,
             constant: constant,
+            coeff: coeff,
+            sprod: sprod,
+            sbary: sbary,
+            sbfly: sbfly,
             smul: smul,
             sadd: sadd,
             stable: stable,
             xor_bitlength: xor_size,
-            xor_table: xor_table,
+            bitwise_table: bitwise_table,
             xord: xord,
+            sand: sand,
+            sor: sor,
+            snand: snand,
+            sxnor: sxnor,
+            srunsum: srunsum,
+            byte_table: byte_table,
+            sbyte: sbyte,
+            set_table: set_table,
+            smember: smember,
+            sgap: sgap,
+            sbits: sbits,
+            scadd: scadd,
+            scmul: scmul,
+            sdivp2: sdivp2,
+            sdot: sdot,
+            slt: slt,
+            slex: slex,
+            sinv: sinv,
+            smuladd: smuladd,
+            sneg: sneg,
+            ssquare: ssquare,
+            slc: slc,
+            saddc: saddc,
+            smulc: smulc,
+            siszero: siszero,
+            sinvz: sinvz,
+            saccum: saccum,
+            generic: generic,
+            snot: snot,
+            smimc: smimc,
+            sfeistel: sfeistel,
+            lookups: 0,
+        };
+
+        // The bitwise operations all look up (tag, a, b, xord) in the bitwise
+        // table. At most one of their selectors is set on a row, so the sum
+        // of the selectors turns the lookup on and the sum of the selectors
+        // weighted by the tags selects the operation.
+        let selectors = config.bitwise_selectors();
+        let bitwise = meta.lookup(|meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let res = meta.query_advice(xord, Rotation::cur());
+            let mut sel = Expression::Constant(F::zero());
+            let mut tag = Expression::Constant(F::zero());
+            for (selector, op) in selectors.iter() {
+                let s = meta.query_selector(*selector);
+                sel = sel + s.clone();
+                tag = tag + s * Expression::Constant(F::from(op.tag()));
+            }
+            vec![
+                (tag, bitwise_table[0]),
+                (sel.clone() * a, bitwise_table[1]),
+                (sel.clone() * b, bitwise_table[2]),
+                (sel * res, bitwise_table[3]),
+            ]
+        });
+
+        // z_cur  <- a window is z_cur - 2^K * z_next
+        // z_next
+        let running_sum = meta.lookup(|meta| {
+            let z_cur = meta.query_advice(advices[0], Rotation::cur());
+            let z_next = meta.query_advice(advices[0], Rotation::next());
+            let sel = meta.query_selector(srunsum);
+            let shift = Expression::Constant(pow2(xor_size));
+            vec![(sel * (z_cur - z_next * shift), bitwise_table[1])]
+        });
+
+        let bytes = meta.lookup(|meta| {
+            let byte = meta.query_advice(advices[0], Rotation::cur());
+            let sel = meta.query_selector(sbyte);
+            vec![(sel * byte, byte_table)]
+        });
+
+        // rows without either selector look up (0, 0, 0), the first row of
+        // the table
+        let set = meta.lookup(|meta| {
+            let a = meta.query_advice(advices[0], Rotation::cur());
+            let b = meta.query_advice(advices[1], Rotation::cur());
+            let member = meta.query_selector(smember);
+            let gap = meta.query_selector(sgap);
+            let tag = member.clone() + gap.clone() * Expression::Constant(F::from(2));
+            vec![
+                (tag, set_table[0]),
+                ((member + gap.clone()) * a, set_table[1]),
+                (gap * b, set_table[2]),
+            ]
+        });
+
+        config.lookups = [bitwise, running_sum, bytes, set].len();
+        config
+    }
+
+    // Allocates fresh columns and configures the chip over them, with a
+    // bitwise table of `xor_size` bits.
+    fn configure_default(meta: &mut ConstraintSystem<F>, xor_size: usize) -> FieldConfig {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        let coeff = meta.fixed_column();
+        let generic = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        let bitwise_table = [
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+        ];
+        let xord = meta.advice_column();
+        let byte_table = meta.lookup_table_column();
+        let set_table = [
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+        ];
+        Self::configure(
+            meta,
+            advice,
+            instance,
+            constant,
+            coeff,
+            generic,
+            xor_size,
+            bitwise_table,
+            xord,
+            byte_table,
+            set_table,
+        )
+    }
+
+    // Lays out a running product starting at 1 and multiplied by
+    // (z - nodes[k]) at each row. Returns the cells of the `nodes.len()`
+    // partial products, the last node being left out of the product.
+    fn assign_product_run(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        nodes: &[F],
+        products: &[Option<F>],
+        z: &Number<F>,
+    ) -> Result<Vec<Cell>, Error> {
+        let config = self.config();
+        let mut cells = Vec::with_capacity(nodes.len());
+        for (k, (x, prod)) in nodes.iter().zip(products.iter()).enumerate() {
+            let row = offset + k;
+            let cell = if k == 0 {
+                region.assign_advice_from_constant(|| "1", config.advices[0], row, F::one())?
+            } else {
+                region.assign_advice(
+                    || "product",
+                    config.advices[0],
+                    row,
+                    || prod.ok_or(Error::SynthesisError),
+                )?
+            };
+            let zcell = region.assign_advice(
+                || "z",
+                config.advices[1],
+                row,
+                || z.value.ok_or(Error::SynthesisError),
+            )?;
+            if k == 0 {
+                region.constrain_equal(z.cell, zcell)?;
+            }
+            if k + 1 < nodes.len() {
+                config.sprod.enable(region, row)?;
+                region.assign_fixed(|| "node", config.coeff, row, || Ok(*x))?;
+            }
+            cells.push(cell);
         }
+        Ok(cells)
     }
-}
 
-#[derive(Clone)]
-struct Number<F: FieldExt> {
-    cell: Cell,
-    value: Option<F>,
-}
-impl<F: FieldExt> NumericInstructions<F> for FieldChip<F> {
-    type Num = Number<F>;
+    // Lays out the butterfly (a, b) -> (a + w * b, a - w * b) on the two rows
+    // starting at `offset`.
+    fn assign_butterfly(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &Number<F>,
+        b: &Number<F>,
+        twiddle: F,
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        let config = self.config();
+        config.sbfly.enable(region, offset)?;
+        region.assign_fixed(|| "twiddle", config.coeff, offset, || Ok(twiddle))?;
+        let lhs = region.assign_advice(
+            || "a",
+            config.advices[0],
+            offset,
+            || a.value.ok_or(Error::SynthesisError),
+        )?;
+        let rhs = region.assign_advice(
+            || "b",
+            config.advices[1],
+            offset,
+            || b.value.ok_or(Error::SynthesisError),
+        )?;
+        region.constrain_equal(a.cell, lhs)?;
+        region.constrain_equal(b.cell, rhs)?;
+        let wb = b.value.map(|b| twiddle * b);
+        let sum = a.value.and_then(|a| wb.map(|wb| a + wb));
+        let diff = a.value.and_then(|a| wb.map(|wb| a - wb));
+        let sum_cell = region.assign_advice(
+            || "a + wb",
+            config.advices[0],
+            offset + 1,
+            || sum.ok_or(Error::SynthesisError),
+        )?;
+        let diff_cell = region.assign_advice(
+            || "a - wb",
+            config.advices[1],
+            offset + 1,
+            || diff.ok_or(Error::SynthesisError),
+        )?;
+        Ok((
+            Number {
+                cell: sum_cell,
+                value: sum,
+            },
+            Number {
+                cell: diff_cell,
+                value: diff,
+            },
+        ))
+    }
 
-    fn load_xor_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+    // Lays out the sum of `weights[i] * bits[i]` over the `bits.len() + 1`
+    // rows starting at `offset`, constraining every bit to be boolean:
+    //   acc_i | b_i   <- weights[i] in coeff
+    // with acc_0 = 0. Returns the bits and the partial sums acc_0, ..., acc_n.
+    fn assign_weighted_bits(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bits: &[Option<F>],
+        weights: &[F],
+    ) -> Result<(Vec<Number<F>>, Vec<Number<F>>), Error> {
         let config = self.config();
-        let bits = config.xor_bitlength;
-        let max = 1 << bits;
-        layouter.assign_table(
-            || "xor table",
-            |mut table| {
-                let mut row = 0;
-                for i in 0..max {
-                    for j in 0..max {
-                        let xor = i ^ j;
-                        table.assign_cell(
-                            || "table_a",
-                            config.xor_table[0],
-                            row,
-                            || Ok(F::from(i)),
-                        )?;
-                        table.assign_cell(
-                            || "table_b",
-                            config.xor_table[1],
-                            row,
-                            || Ok(F::from(j)),
-                        )?;
-                        table.assign_cell(
-                            || "table_xor",
-                            config.xor_table[2],
-                            row,
-                            || Ok(F::from(xor)),
-                        )?;
-                        row += 1;
-                    }
+        let mut acc = Some(F::zero());
+        let mut acc_cell =
+            region.assign_advice_from_constant(|| "acc", config.advices[0], offset, F::zero())?;
+        let mut out = Vec::with_capacity(bits.len());
+        let mut sums = vec![Number {
+            cell: acc_cell,
+            value: acc,
+        }];
+        for (i, (bit, weight)) in bits.iter().zip(weights.iter()).enumerate() {
+            let row = offset + i;
+            config.sbits.enable(region, row)?;
+            region.assign_fixed(|| "weight", config.coeff, row, || Ok(*weight))?;
+            let bit_cell = region.assign_advice(
+                || "bit",
+                config.advices[1],
+                row,
+                || bit.ok_or(Error::SynthesisError),
+            )?;
+            acc = acc.and_then(|acc| bit.map(|bit| acc + *weight * bit));
+            acc_cell = region.assign_advice(
+                || "acc",
+                config.advices[0],
+                row + 1,
+                || acc.ok_or(Error::SynthesisError),
+            )?;
+            out.push(Number {
+                cell: bit_cell,
+                value: *bit,
+            });
+            sums.push(Number {
+                cell: acc_cell,
+                value: acc,
+            });
+        }
+        Ok((out, sums))
+    }
+
+    // Decomposes `value` into `num_bits` little-endian bits over the
+    // `num_bits + 1` rows starting at `offset`, the weights being 2^i. The
+    // last partial sum is constrained equal to `value`, which therefore fits
+    // in `num_bits` bits. Returns the bits and the partial sums.
+    fn assign_bits(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &Number<F>,
+        num_bits: usize,
+    ) -> Result<(Vec<Number<F>>, Vec<Number<F>>), Error> {
+        if num_bits > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        let bits: Vec<Option<F>> = match value.value {
+            Some(v) => to_le_bits(&v, num_bits)
+                .into_iter()
+                .map(|b| Some(F::from(b)))
+                .collect(),
+            None => vec![None; num_bits],
+        };
+        let weights: Vec<F> = (0..num_bits).map(pow2).collect();
+        let (bits, sums) = self.assign_weighted_bits(region, offset, &bits, &weights)?;
+        region.constrain_equal(value.cell, sums[num_bits].cell)?;
+        Ok((bits, sums))
+    }
+
+    // Lays out sum_i a_i * b_i over the `2 * a.len() + 1` rows starting at
+    // `offset`, the partial sums being on the even rows and the pairs
+    // (a_i, b_i) on the odd rows. Returns the sum.
+    fn assign_inner_product(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &[Number<F>],
+        b: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        let config = self.config();
+        if a.len() != b.len() {
+            return Err(Error::SynthesisError);
+        }
+        let mut acc = Some(F::zero());
+        let mut acc_cell =
+            region.assign_advice_from_constant(|| "acc", config.advices[0], offset, F::zero())?;
+        for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            let row = offset + 1 + 2 * i;
+            config.sdot.enable(region, row)?;
+            let lhs = region.assign_advice(
+                || "a",
+                config.advices[0],
+                row,
+                || a.value.ok_or(Error::SynthesisError),
+            )?;
+            let rhs = region.assign_advice(
+                || "b",
+                config.advices[1],
+                row,
+                || b.value.ok_or(Error::SynthesisError),
+            )?;
+            region.constrain_equal(a.cell, lhs)?;
+            region.constrain_equal(b.cell, rhs)?;
+            acc = acc.and_then(|acc| a.value.and_then(|a| b.value.map(|b| acc + a * b)));
+            acc_cell = region.assign_advice(
+                || "acc",
+                config.advices[0],
+                row + 1,
+                || acc.ok_or(Error::SynthesisError),
+            )?;
+        }
+        Ok(Number {
+            cell: acc_cell,
+            value: acc,
+        })
+    }
+
+    // Compares `a` and `b`, which must fit in `n_bits` bits, over the
+    // `n_bits + 3` rows starting at `offset`. Returns a boolean set iff a < b.
+    fn assign_lt(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &Number<F>,
+        b: &Number<F>,
+        n_bits: usize,
+    ) -> Result<Number<F>, Error> {
+        let config = self.config();
+        if n_bits + 1 > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        let shift = pow2::<F>(n_bits) - F::one();
+        let bits: Vec<Option<F>> = match a.value.and_then(|a| b.value.map(|b| b - a + shift)) {
+            Some(d) => to_le_bits(&d, n_bits + 1)
+                .into_iter()
+                .map(|b| Some(F::from(b)))
+                .collect(),
+            None => vec![None; n_bits + 1],
+        };
+        let weights: Vec<F> = (0..n_bits + 1).map(pow2).collect();
+        let (bits, _) = self.assign_weighted_bits(region, offset, &bits, &weights)?;
+
+        let row = offset + n_bits + 2;
+        config.slt.enable(region, row)?;
+        region.assign_fixed(|| "2^n - 1", config.coeff, row, || Ok(shift))?;
+        let lhs = region.assign_advice(
+            || "a",
+            config.advices[0],
+            row,
+            || a.value.ok_or(Error::SynthesisError),
+        )?;
+        let rhs = region.assign_advice(
+            || "b",
+            config.advices[1],
+            row,
+            || b.value.ok_or(Error::SynthesisError),
+        )?;
+        region.constrain_equal(a.cell, lhs)?;
+        region.constrain_equal(b.cell, rhs)?;
+        Ok(bits[n_bits].clone())
+    }
+
+    // Lays out `a | a op k` on a single row with `k` in coeff, `selector`
+    // enabling the gate for `op`.
+    fn assign_const_op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        selector: Selector,
+        a: Number<F>,
+        k: F,
+        res: Option<F>,
+    ) -> Result<Number<F>, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "constant op",
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                region.assign_fixed(|| "k", config.coeff, 0, || Ok(k))?;
+                let lhs = region.assign_advice(
+                    || "a",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                let cell = region.assign_advice(
+                    || "a op k",
+                    config.advices[1],
+                    0,
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: res });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    // Lays out `a[i] op b[i]` for every `i` in a single region, each operation
+    // taking two rows as for a single add or mul. With `sub`, the add gate is
+    // used as (a - b) + b = a, the difference being the first operand.
+    fn assign_elementwise(
+        &self,
+        mut layouter: impl Layouter<F>,
+        selector: Selector,
+        a: &[Number<F>],
+        b: &[Number<F>],
+        op: fn(F, F) -> F,
+        sub: bool,
+    ) -> Result<Vec<Number<F>>, Error> {
+        let config = self.config();
+        if a.len() != b.len() {
+            return Err(Error::SynthesisError);
+        }
+        let mut out = vec![];
+        layouter.assign_region(
+            || "element-wise",
+            |mut region| {
+                out.clear();
+                for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    let row = 2 * i;
+                    selector.enable(&mut region, row)?;
+                    let res = a.value.and_then(|a| b.value.map(|b| op(a, b)));
+                    // cells of a and of the result, swapped for a subtraction
+                    let (a_pos, res_pos) = if sub { (row + 1, row) } else { (row, row + 1) };
+                    let lhs = region.assign_advice(
+                        || "a",
+                        config.advices[0],
+                        a_pos,
+                        || a.value.ok_or(Error::SynthesisError),
+                    )?;
+                    let rhs = region.assign_advice(
+                        || "b",
+                        config.advices[1],
+                        row,
+                        || b.value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(a.cell, lhs)?;
+                    region.constrain_equal(b.cell, rhs)?;
+                    let cell = region.assign_advice(
+                        || "a op b",
+                        config.advices[0],
+                        res_pos,
+                        || res.ok_or(Error::SynthesisError),
+                    )?;
+                    out.push(Number { cell, value: res });
                 }
                 Ok(())
             },
-        )
+        )?;
+        Ok(out)
     }
-    fn load_private(
+
+    // Assigns a and b next to op(a, b) in xord, on a row where the selector
+    // of op looks them up in the bitwise table.
+    fn assign_bitwise(
         &self,
         mut layouter: impl Layouter<F>,
-        value: Option<F>,
-    ) -> Result<Self::Num, Error> {
+        op: BitwiseOp,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
         let config = self.config();
-        let mut num = None;
+        let selector = config
+            .bitwise_selectors()
+            .iter()
+            .find(|(_, o)| *o == op)
+            .map(|(s, _)| *s)
+            .unwrap();
+        let mut out = None;
         layouter.assign_region(
-            || "load private",
+            || "bitwise",
             |mut region| {
-                let cell = region.assign_advice(
-                    || "private_input",
+                selector.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "a",
                     config.advices[0],
                     0,
-                    || value.ok_or(Error::SynthesisError),
+                    || a.value.ok_or(Error::SynthesisError),
                 )?;
-                num = Some(Number { cell: cell, value });
+                let rhs = region.assign_advice(
+                    || "b",
+                    config.advices[1],
+                    0,
+                    || b.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                region.constrain_equal(b.cell, rhs)?;
+                let res = a.value.and_then(|a| {
+                    b.value.map(|b| {
+                        F::from(op.apply(
+                            a.get_lower_32() as u64,
+                            b.get_lower_32() as u64,
+                            config.xor_bitlength,
+                        ))
+                    })
+                });
+                let cell = region.assign_advice(
+                    || "a op b",
+                    config.xord,
+                    0,
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { value: res, cell });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    // Decomposes `a` into `word_size` bits b_i and recomposes them as the sum
+    // of b_i * 2^position(i), the bits without a position being dropped:
+    //   acc_i | b_i   <- 2^i in coeff
+    //   ...
+    //   a     |
+    //   acc_i | b_i   <- 2^position(i) or 0 in coeff
+    //   ...
+    //   out   |
+    fn assign_bit_permutation(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        word_size: usize,
+        position: impl Fn(usize) -> Option<usize>,
+    ) -> Result<Number<F>, Error> {
+        if word_size == 0 || word_size > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        let weights: Vec<F> = (0..word_size)
+            .map(|i| position(i).map(pow2).unwrap_or_else(F::zero))
+            .collect();
+        let mut out = None;
+        layouter.assign_region(
+            || "bit permutation",
+            |mut region| {
+                let (bits, _) = self.assign_bits(&mut region, 0, &a, word_size)?;
+                let (copies, sums) = self.assign_weighted_bits(
+                    &mut region,
+                    word_size + 1,
+                    &bits.iter().map(|b| b.value).collect::<Vec<_>>(),
+                    &weights,
+                )?;
+                for (bit, copy) in bits.iter().zip(copies.iter()) {
+                    region.constrain_equal(bit.cell, copy.cell)?;
+                }
+                out = Some(sums[word_size].clone());
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    // Computes a op b for operands of `total_bits` bits, applying op chunk
+    // by chunk in the bitwise table and recomposing the chunks.
+    fn assign_bitwise_wide(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: BitwiseOp,
+        a: Number<F>,
+        b: Number<F>,
+        total_bits: usize,
+    ) -> Result<Number<F>, Error> {
+        let window = self.config().xor_bitlength;
+        let chunks = total_bits / window + usize::from(total_bits % window != 0);
+        if chunks * window > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        // little-endian chunks of window bits of v
+        let split = |v: Option<F>| -> Vec<Option<F>> {
+            let bits = v.map(|v| to_le_bits(&v, chunks * window));
+            (0..chunks)
+                .map(|i| {
+                    bits.as_ref()
+                        .map(|bits| from_le_bits(&bits[i * window..(i + 1) * window]))
+                })
+                .collect()
+        };
+        let (a_chunks, b_chunks) = (split(a.value), split(b.value));
+
+        let mut a_terms = Vec::with_capacity(chunks);
+        let mut b_terms = Vec::with_capacity(chunks);
+        let mut out_terms = Vec::with_capacity(chunks);
+        for (i, (a_chunk, b_chunk)) in a_chunks.into_iter().zip(b_chunks).enumerate() {
+            let weight = pow2(i * window);
+            let a_chunk = self.load_private(layouter.namespace(|| "a chunk"), a_chunk)?;
+            let b_chunk = self.load_private(layouter.namespace(|| "b chunk"), b_chunk)?;
+            // the lookup bounds both chunks by the table size
+            let res = self.assign_bitwise(
+                layouter.namespace(|| "chunk"),
+                op,
+                a_chunk.clone(),
+                b_chunk.clone(),
+            )?;
+            a_terms.push((weight, a_chunk));
+            b_terms.push((weight, b_chunk));
+            out_terms.push((weight, res));
+        }
+        let a_sum = self.linear_combination(layouter.namespace(|| "recompose a"), &a_terms)?;
+        self.assert_equal(layouter.namespace(|| "a chunks"), a, a_sum)?;
+        let b_sum = self.linear_combination(layouter.namespace(|| "recompose b"), &b_terms)?;
+        self.assert_equal(layouter.namespace(|| "b chunks"), b, b_sum)?;
+        self.linear_combination(layouter.namespace(|| "recompose a op b"), &out_terms)
+    }
+}
+
+// Reverses the `bits` least significant bits of `i`.
+fn bit_reverse(i: usize, bits: u32) -> usize {
+    if bits == 0 {
+        return i;
+    }
+    i.reverse_bits() >> (usize::BITS - bits)
+}
+
+// Returns the primitive root of unity of order 2^log_n, if the field has one.
+fn root_of_unity<F: FieldExt>(log_n: u32) -> Option<F> {
+    if log_n > F::S {
+        return None;
+    }
+    let mut omega = F::root_of_unity();
+    for _ in log_n..F::S {
+        omega = omega.square();
+    }
+    Some(omega)
+}
+
+// Computes the barycentric weights w_i = 1 / prod_{j != i} (x_i - x_j).
+// Returns None if two nodes are equal.
+fn barycentric_weights<F: FieldExt>(xs: &[F]) -> Option<Vec<F>> {
+    xs.iter()
+        .enumerate()
+        .map(|(i, xi)| {
+            let denom = xs
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(F::one(), |acc, (_, xj)| acc * (*xi - *xj));
+            if denom == F::zero() {
+                None
+            } else {
+                Some(denom.invert().unwrap())
+            }
+        })
+        .collect()
+}
+
+// Returns the `num_bits` least significant bits of `value`, little-endian.
+fn to_le_bits<F: FieldExt>(value: &F, num_bits: usize) -> Vec<bool> {
+    let bytes = value.to_bytes();
+    (0..num_bits)
+        .map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1)
+        .collect()
+}
+
+// Recomposes little-endian bits into a field element.
+fn from_le_bits<F: FieldExt>(bits: &[bool]) -> F {
+    bits.iter()
+        .rev()
+        .fold(F::zero(), |acc, bit| acc.double() + F::from(*bit))
+}
+
+// Returns 2^n as a field element.
+fn pow2<F: FieldExt>(n: usize) -> F {
+    F::from(2u64).pow_vartime(&[n as u64])
+}
+
+// Little-endian encoding of the field modulus.
+fn modulus_bytes<F: FieldExt>() -> [u8; 32] {
+    // p = (p - 1) + 1
+    let mut bytes = (-F::one()).to_bytes();
+    for b in bytes.iter_mut() {
+        let (sum, carry) = b.overflowing_add(1);
+        *b = sum;
+        if !carry {
+            break;
+        }
+    }
+    bytes
+}
+
+// Coefficients of the generic gate q_l * a + q_r * b + q_m * a * b + q_o * out
+// + q_c = 0.
+#[derive(Clone, Copy, Debug)]
+struct GenericCoeffs<F> {
+    q_l: F,
+    q_r: F,
+    q_m: F,
+    q_o: F,
+    q_c: F,
+}
+
+// Maps a signed integer to the field.
+fn from_i64<F: FieldExt>(v: i64) -> F {
+    if v < 0 {
+        -F::from(v.unsigned_abs())
+    } else {
+        F::from(v as u64)
+    }
+}
+
+#[derive(Clone)]
+struct Number<F: FieldExt> {
+    cell: Cell,
+    value: Option<F>,
+}
+
+// A Number constrained to be 0 or 1, as returned by the comparisons.
+#[derive(Clone)]
+struct Bit<F: FieldExt>(Number<F>);
+
+impl<F: FieldExt> Bit<F> {
+    fn num(&self) -> Number<F> {
+        self.0.clone()
+    }
+}
+
+// The order of the bits packed in a number: `LittleEndian` puts the least
+// significant bit first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+enum BitOrder {
+    LittleEndian,
+    BigEndian,
+}
+impl<F: FieldExt> NumericInstructions<F> for FieldChip<F> {
+    type Num = Number<F>;
+
+    fn load_bitwise_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let config = self.config();
+        let bits = config.xor_bitlength;
+        let max: u64 = 1 << bits;
+        layouter.assign_table(
+            || "bitwise table",
+            |mut table| {
+                let mut row = 0;
+                for op in BitwiseOp::ALL.iter() {
+                    for i in 0..max {
+                        for j in 0..max {
+                            let res = op.apply(i, j, bits);
+                            table.assign_cell(
+                                || "table_tag",
+                                config.bitwise_table[0],
+                                row,
+                                || Ok(F::from(op.tag())),
+                            )?;
+                            table.assign_cell(
+                                || "table_a",
+                                config.bitwise_table[1],
+                                row,
+                                || Ok(F::from(i)),
+                            )?;
+                            table.assign_cell(
+                                || "table_b",
+                                config.bitwise_table[2],
+                                row,
+                                || Ok(F::from(j)),
+                            )?;
+                            table.assign_cell(
+                                || "table_res",
+                                config.bitwise_table[3],
+                                row,
+                                || Ok(F::from(res)),
+                            )?;
+                            row += 1;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut num = None;
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                let cell = region.assign_advice(
+                    || "private_input",
+                    config.advices[0],
+                    0,
+                    || value.ok_or(Error::SynthesisError),
+                )?;
+                num = Some(Number { cell: cell, value });
+                Ok(())
+            },
+        )?;
+        Ok(num.unwrap())
+    }
+
+    fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constant: F,
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut num = None;
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                let cell = region.assign_advice_from_constant(
+                    || "constant",
+                    config.advices[0],
+                    0,
+                    constant,
+                )?;
+                num = Some(Number {
+                    cell: cell,
+                    value: Some(constant),
+                });
+                Ok(())
+            },
+        )?;
+        Ok(num.unwrap())
+    }
+
+    fn load_public(&self, mut layouter: impl Layouter<F>, row: usize) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut num = None;
+        layouter.assign_region(
+            || "load public",
+            |mut region| {
+                let cell = region.assign_advice_from_instance(
+                    || "instance",
+                    config.instance,
+                    row,
+                    config.advices[0],
+                    0,
+                )?;
+                num = Some(Number {
+                    cell: cell.0,
+                    value: cell.1,
+                });
                 Ok(())
             },
         )?;
         Ok(num.unwrap())
     }
 
-    fn load_constant(
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: Self::Num,
+        row: usize,
+    ) -> Result<(), Error> {
+        let config = self.config();
+        // Constrains a [`Cell`] to equal an instance column's row value at an
+        // absolute position.
+        layouter.constrain_instance(num.cell, config.instance, row)
+    }
+
+    fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                config.sadd.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "lhs",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                let rhs = region.assign_advice(
+                    || "rhs",
+                    config.advices[1],
+                    0,
+                    || b.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                region.constrain_equal(b.cell, rhs)?;
+                let res = a.value.and_then(|a| b.value.map(|b| a + b));
+                let cell = region.assign_advice(
+                    || "lhs + rhs",
+                    config.advices[0],
+                    1, // OFFSET
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number {
+                    value: res,
+                    cell: cell,
+                });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "mul",
+            |mut region| {
+                config.smul.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "lhs",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                let rhs = region.assign_advice(
+                    || "rhs",
+                    config.advices[1],
+                    0,
+                    || b.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                region.constrain_equal(b.cell, rhs)?;
+                let res = a.value.and_then(|a| b.value.map(|b| a * b));
+                let cell = region.assign_advice(
+                    || "lhs * rhs",
+                    config.advices[0],
+                    1, // OFFSET !
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number {
+                    value: res,
+                    cell: cell,
+                });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise(layouter, BitwiseOp::Xor, a, b)
+    }
+
+    fn interpolate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xs: &[F],
+        ys: &[Self::Num],
+        z: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        if xs.is_empty() || xs.len() != ys.len() {
+            return Err(Error::SynthesisError);
+        }
+        let weights = barycentric_weights(xs).ok_or(Error::SynthesisError)?;
+        let n = xs.len();
+        // p(z) = sum_i w_i * y_i * prod_{j < i} (z - x_j) * prod_{j > i} (z - x_j)
+        // which, unlike the usual barycentric formula, has no division by
+        // (z - x_i) so it also holds when z is one of the nodes.
+        let mut prefix = vec![Some(F::one()); n];
+        for i in 1..n {
+            prefix[i] = prefix[i - 1].and_then(|p| z.value.map(|z| p * (z - xs[i - 1])));
+        }
+        let mut suffix = vec![Some(F::one()); n];
+        for i in (0..n - 1).rev() {
+            suffix[i] = suffix[i + 1].and_then(|s| z.value.map(|z| s * (z - xs[i + 1])));
+        }
+        let rev_xs: Vec<F> = xs.iter().rev().cloned().collect();
+        let rev_suffix: Vec<Option<F>> = suffix.iter().rev().cloned().collect();
+
+        let mut out = None;
+        layouter.assign_region(
+            || "interpolate",
+            |mut region| {
+                let prefix_cells = self.assign_product_run(&mut region, 0, xs, &prefix, &z)?;
+                // the suffix products are computed over the nodes in reverse
+                let mut suffix_cells =
+                    self.assign_product_run(&mut region, n, &rev_xs, &rev_suffix, &z)?;
+                suffix_cells.reverse();
+
+                let offset = 2 * n;
+                let mut acc = Some(F::zero());
+                let mut acc_cell = region.assign_advice_from_constant(
+                    || "acc",
+                    config.advices[1],
+                    offset,
+                    F::zero(),
+                )?;
+                for i in 0..n {
+                    let row = offset + 1 + 2 * i;
+                    config.sbary.enable(&mut region, row)?;
+                    region.assign_fixed(|| "weight", config.coeff, row, || Ok(weights[i]))?;
+                    let p = region.assign_advice(
+                        || "prefix",
+                        config.advices[0],
+                        row,
+                        || prefix[i].ok_or(Error::SynthesisError),
+                    )?;
+                    let s = region.assign_advice(
+                        || "suffix",
+                        config.advices[1],
+                        row,
+                        || suffix[i].ok_or(Error::SynthesisError),
+                    )?;
+                    let y = region.assign_advice(
+                        || "y",
+                        config.advices[0],
+                        row + 1,
+                        || ys[i].value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(prefix_cells[i], p)?;
+                    region.constrain_equal(suffix_cells[i], s)?;
+                    region.constrain_equal(ys[i].cell, y)?;
+                    acc = acc.and_then(|acc| {
+                        prefix[i].and_then(|p| {
+                            suffix[i]
+                                .and_then(|s| ys[i].value.map(|y| acc + weights[i] * y * p * s))
+                        })
+                    });
+                    acc_cell = region.assign_advice(
+                        || "acc",
+                        config.advices[1],
+                        row + 1,
+                        || acc.ok_or(Error::SynthesisError),
+                    )?;
+                }
+                out = Some(Number {
+                    cell: acc_cell,
+                    value: acc,
+                });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn ntt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error> {
+        let n = values.len();
+        if !n.is_power_of_two() {
+            return Err(Error::SynthesisError);
+        }
+        let log_n = n.trailing_zeros();
+        let omega = root_of_unity::<F>(log_n).ok_or(Error::SynthesisError)?;
+
+        let mut out = None;
+        layouter.assign_region(
+            || "ntt",
+            |mut region| {
+                // iterative Cooley-Tukey: the butterflies operate in place on
+                // the inputs taken in bit-reversed order
+                let mut nums: Vec<Number<F>> = (0..n)
+                    .map(|i| values[bit_reverse(i, log_n)].clone())
+                    .collect();
+                let mut offset = 0;
+                for s in 1..=log_n {
+                    let m = 1 << s;
+                    let w_m = omega.pow_vartime(&[(n / m) as u64]);
+                    for k in (0..n).step_by(m) {
+                        let mut w = F::one();
+                        for j in 0..m / 2 {
+                            let (sum, diff) = self.assign_butterfly(
+                                &mut region,
+                                offset,
+                                &nums[k + j],
+                                &nums[k + j + m / 2],
+                                w,
+                            )?;
+                            nums[k + j] = sum;
+                            nums[k + j + m / 2] = diff;
+                            w = w * w_m;
+                            offset += 2;
+                        }
+                    }
+                }
+                out = Some(nums);
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "range check",
+            |mut region| {
+                self.assign_bits(&mut region, 0, &a, num_bits)?;
+                Ok(())
+            },
+        )
+    }
+
+    fn range_check_running_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        let config = self.config();
+        if num_bits > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        let window = config.xor_bitlength;
+        let windows = num_bits / window;
+        // z_i = sum_{j >= i} w_j * 2^(K * (j - i)), the top bits that do not
+        // fill a window being left in z_windows
+        let bits = a.value.map(|a| to_le_bits(&a, num_bits));
+        let zs: Vec<Option<F>> = (0..=windows)
+            .map(|i| match i {
+                0 => a.value,
+                i => bits.as_ref().map(|bits| from_le_bits(&bits[i * window..])),
+            })
+            .collect();
+        layouter.assign_region(
+            || "running sum range check",
+            |mut region| {
+                let mut z = a.clone();
+                for (i, value) in zs.iter().enumerate() {
+                    let cell = region.assign_advice(
+                        || "z",
+                        config.advices[0],
+                        i,
+                        || value.ok_or(Error::SynthesisError),
+                    )?;
+                    if i == 0 {
+                        region.constrain_equal(a.cell, cell)?;
+                    } else {
+                        config.srunsum.enable(&mut region, i - 1)?;
+                    }
+                    z = Number {
+                        cell,
+                        value: *value,
+                    };
+                }
+                match num_bits % window {
+                    0 => region.constrain_constant(z.cell, F::zero())?,
+                    top => {
+                        self.assign_bits(&mut region, windows + 1, &z, top)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn decompose_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        num_bits: usize,
+    ) -> Result<Vec<Self::Num>, Error> {
+        let mut out = None;
+        layouter.assign_region(
+            || "decompose bits",
+            |mut region| {
+                let (bits, _) = self.assign_bits(&mut region, 0, &a, num_bits)?;
+                out = Some(bits);
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn checked_add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        n_bits: usize,
+    ) -> Result<(Self::Num, Self::Num), Error> {
+        let config = self.config();
+        // the sum must not wrap around the modulus
+        if n_bits + 1 > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        let bound = pow2::<F>(n_bits);
+        let sum = a.value.and_then(|a| b.value.map(|b| a + b));
+        let bits = sum.map(|sum| to_le_bits(&sum, n_bits + 1));
+        let overflow = bits.as_ref().map(|bits| F::from(bits[n_bits]));
+        let result = sum.and_then(|sum| overflow.map(|o| sum - o * bound));
+
+        let mut out = None;
+        layouter.assign_region(
+            || "checked add",
+            |mut region| {
+                config.scadd.enable(&mut region, 0)?;
+                region.assign_fixed(|| "2^n", config.coeff, 0, || Ok(bound))?;
+                let lhs = region.assign_advice(
+                    || "lhs",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                let rhs = region.assign_advice(
+                    || "rhs",
+                    config.advices[1],
+                    0,
+                    || b.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                region.constrain_equal(b.cell, rhs)?;
+                let result_cell = region.assign_advice(
+                    || "result",
+                    config.advices[0],
+                    1,
+                    || result.ok_or(Error::SynthesisError),
+                )?;
+                let overflow_cell = region.assign_advice(
+                    || "overflow",
+                    config.advices[1],
+                    1,
+                    || overflow.ok_or(Error::SynthesisError),
+                )?;
+                let result = Number {
+                    cell: result_cell,
+                    value: result,
+                };
+                self.assign_bits(&mut region, 2, &result, n_bits)?;
+                out = Some((
+                    result,
+                    Number {
+                        cell: overflow_cell,
+                        value: overflow,
+                    },
+                ));
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn checked_mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        n_bits: usize,
+    ) -> Result<(Self::Num, Self::Num), Error> {
+        let config = self.config();
+        // the product must not wrap around the modulus
+        if 2 * n_bits > F::CAPACITY as usize {
+            return Err(Error::SynthesisError);
+        }
+        let bound = pow2::<F>(n_bits);
+        let bits = a
+            .value
+            .and_then(|a| b.value.map(|b| to_le_bits(&(a * b), 2 * n_bits)));
+        let low = bits.as_ref().map(|bits| from_le_bits::<F>(&bits[..n_bits]));
+        let high = bits.as_ref().map(|bits| from_le_bits::<F>(&bits[n_bits..]));
+        let inv = high.map(|h| h.invert().unwrap_or(F::zero()));
+        let overflow = high.map(|h| F::from(h != F::zero()));
+
+        let mut out = None;
+        layouter.assign_region(
+            || "checked mul",
+            |mut region| {
+                config.scmul.enable(&mut region, 0)?;
+                region.assign_fixed(|| "2^n", config.coeff, 0, || Ok(bound))?;
+                let lhs = region.assign_advice(
+                    || "lhs",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                let rhs = region.assign_advice(
+                    || "rhs",
+                    config.advices[1],
+                    0,
+                    || b.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                region.constrain_equal(b.cell, rhs)?;
+                let low_cell = region.assign_advice(
+                    || "low",
+                    config.advices[0],
+                    1,
+                    || low.ok_or(Error::SynthesisError),
+                )?;
+                let high_cell = region.assign_advice(
+                    || "high",
+                    config.advices[1],
+                    1,
+                    || high.ok_or(Error::SynthesisError),
+                )?;
+                region.assign_advice(
+                    || "high inverse",
+                    config.advices[0],
+                    2,
+                    || inv.ok_or(Error::SynthesisError),
+                )?;
+                let overflow_cell = region.assign_advice(
+                    || "overflow",
+                    config.advices[1],
+                    2,
+                    || overflow.ok_or(Error::SynthesisError),
+                )?;
+                let low = Number {
+                    cell: low_cell,
+                    value: low,
+                };
+                let high = Number {
+                    cell: high_cell,
+                    value: high,
+                };
+                self.assign_bits(&mut region, 3, &low, n_bits)?;
+                self.assign_bits(&mut region, 3 + n_bits + 1, &high, n_bits)?;
+                out = Some((
+                    low,
+                    Number {
+                        cell: overflow_cell,
+                        value: overflow,
+                    },
+                ));
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn div_pow2(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: Self::Num,
+        k: usize,
+        n_bits: usize,
+    ) -> Result<(Self::Num, Self::Num), Error> {
+        let config = self.config();
+        if k > n_bits {
+            return Err(Error::SynthesisError);
+        }
+        let bits = x.value.map(|x| to_le_bits(&x, n_bits));
+        let q = bits.as_ref().map(|bits| from_le_bits::<F>(&bits[k..]));
+
+        let mut out = None;
+        layouter.assign_region(
+            || "div by 2^k",
+            |mut region| {
+                // the remainder is the partial sum of the k low bits, and the
+                // last row of the decomposition holds x
+                let (_, sums) = self.assign_bits(&mut region, 0, &x, n_bits)?;
+                let r = sums[k].clone();
+                config.sdivp2.enable(&mut region, n_bits)?;
+                region.assign_fixed(|| "2^k", config.coeff, n_bits, || Ok(pow2::<F>(k)))?;
+                let q_cell = region.assign_advice(
+                    || "q",
+                    config.advices[1],
+                    n_bits,
+                    || q.ok_or(Error::SynthesisError),
+                )?;
+                let r_cell = region.assign_advice(
+                    || "r",
+                    config.advices[0],
+                    n_bits + 1,
+                    || r.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(r.cell, r_cell)?;
+                out = Some((
+                    Number {
+                        cell: q_cell,
+                        value: q,
+                    },
+                    r,
+                ));
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn one_hot(
+        &self,
+        mut layouter: impl Layouter<F>,
+        index: Self::Num,
+        n: usize,
+    ) -> Result<Vec<Self::Num>, Error> {
+        let bits: Vec<Option<F>> = (0..n)
+            .map(|i| index.value.map(|idx| F::from(idx == F::from(i as u64))))
+            .collect();
+        let ones = vec![F::one(); n];
+        let positions: Vec<F> = (0..n).map(|i| F::from(i as u64)).collect();
+
+        let mut out = None;
+        layouter.assign_region(
+            || "one hot",
+            |mut region| {
+                // sum_i b_i = 1
+                let (bits, sums) = self.assign_weighted_bits(&mut region, 0, &bits, &ones)?;
+                region.constrain_constant(sums[n].cell, F::one())?;
+                // sum_i i * b_i = index
+                let (copies, sums) = self.assign_weighted_bits(
+                    &mut region,
+                    n + 1,
+                    &bits.iter().map(|b| b.value).collect::<Vec<_>>(),
+                    &positions,
+                )?;
+                for (bit, copy) in bits.iter().zip(copies.iter()) {
+                    region.constrain_equal(bit.cell, copy.cell)?;
+                }
+                region.constrain_equal(index.cell, sums[n].cell)?;
+                out = Some(bits);
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn select_from(
+        &self,
+        mut layouter: impl Layouter<F>,
+        vals: &[Self::Num],
+        index: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let bits = self.one_hot(layouter.namespace(|| "one hot"), index, vals.len())?;
+        let mut out = None;
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                out = Some(self.assign_inner_product(&mut region, 0, &bits, vals)?);
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn lex_lt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Bit<F>, Error> {
+        let config = self.config();
+        if a.len() != b.len() {
+            return Err(Error::SynthesisError);
+        }
+        let mut out = None;
+        layouter.assign_region(
+            || "lex lt",
+            |mut region| {
+                let mut offset = 0;
+                let mut flags = Vec::with_capacity(a.len());
+                for (a, b) in a.iter().zip(b.iter()) {
+                    let less = self.assign_lt(&mut region, offset, a, b, 8)?;
+                    let greater = self.assign_lt(&mut region, offset + 11, b, a, 8)?;
+                    flags.push((less, greater));
+                    offset += 22;
+                }
+
+                // eq_i tells whether the first i bytes are equal, lt_i whether
+                // a is smaller than b on these bytes
+                let mut eq = Some(F::one());
+                let mut lt = Some(F::zero());
+                region.assign_advice_from_constant(|| "eq", config.advices[0], offset, F::one())?;
+                let mut lt_cell = region.assign_advice_from_constant(
+                    || "lt",
+                    config.advices[1],
+                    offset,
+                    F::zero(),
+                )?;
+                for (i, (less, greater)) in flags.iter().enumerate() {
+                    let row = offset + 1 + 2 * i;
+                    config.slex.enable(&mut region, row)?;
+                    let less_cell = region.assign_advice(
+                        || "a_i < b_i",
+                        config.advices[0],
+                        row,
+                        || less.value.ok_or(Error::SynthesisError),
+                    )?;
+                    let greater_cell = region.assign_advice(
+                        || "a_i > b_i",
+                        config.advices[1],
+                        row,
+                        || greater.value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(less.cell, less_cell)?;
+                    region.constrain_equal(greater.cell, greater_cell)?;
+                    lt = lt.and_then(|lt| eq.and_then(|eq| less.value.map(|l| lt + eq * l)));
+                    eq = eq.and_then(|eq| {
+                        less.value
+                            .and_then(|l| greater.value.map(|g| eq * (F::one() - l - g)))
+                    });
+                    region.assign_advice(
+                        || "eq",
+                        config.advices[0],
+                        row + 1,
+                        || eq.ok_or(Error::SynthesisError),
+                    )?;
+                    lt_cell = region.assign_advice(
+                        || "lt",
+                        config.advices[1],
+                        row + 1,
+                        || lt.ok_or(Error::SynthesisError),
+                    )?;
+                }
+                out = Some(Bit(Number {
+                    cell: lt_cell,
+                    value: lt,
+                }));
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn invert(&self, mut layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let inv = match a.value {
+            Some(a) if a == F::zero() => return Err(Error::SynthesisError),
+            Some(a) => Some(a.invert().unwrap()),
+            None => None,
+        };
+        let mut out = None;
+        layouter.assign_region(
+            || "invert",
+            |mut region| {
+                config.sinv.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "a",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                let cell = region.assign_advice(
+                    || "1 / a",
+                    config.advices[1],
+                    0,
+                    || inv.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: inv });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn div(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let inv = self.invert(layouter.namespace(|| "1 / b"), b)?;
+        self.mul(layouter.namespace(|| "a * 1 / b"), a, inv)
+    }
+
+    fn mul_add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        c: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "mul add",
+            |mut region| {
+                config.smuladd.enable(&mut region, 0)?;
+                for (num, column, offset) in [
+                    (&a, config.advices[0], 0),
+                    (&b, config.advices[1], 0),
+                    (&c, config.advices[0], 1),
+                ]
+                .iter()
+                {
+                    let cell = region.assign_advice(
+                        || "operand",
+                        *column,
+                        *offset,
+                        || num.value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(num.cell, cell)?;
+                }
+                let res = a
+                    .value
+                    .and_then(|a| b.value.and_then(|b| c.value.map(|c| a * b + c)));
+                let cell = region.assign_advice(
+                    || "a * b + c",
+                    config.advices[1],
+                    1,
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: res });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn neg(&self, mut layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "neg",
+            |mut region| {
+                config.sneg.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "a",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                let res = a.value.map(|a| -a);
+                let cell = region.assign_advice(
+                    || "-a",
+                    config.advices[1],
+                    0,
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: res });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn square(&self, mut layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.ssquare.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "a",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                let res = a.value.map(|a| a.square());
+                let cell = region.assign_advice(
+                    || "a^2",
+                    config.advices[1],
+                    0,
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: res });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn linear_combination(
+        &self,
+        mut layouter: impl Layouter<F>,
+        terms: &[(F, Self::Num)],
+    ) -> Result<Self::Num, Error> {
+        let config = self.config();
+        let mut out = None;
+        layouter.assign_region(
+            || "linear combination",
+            |mut region| {
+                let mut acc = Some(F::zero());
+                let mut cell = region.assign_advice_from_constant(
+                    || "acc",
+                    config.advices[0],
+                    0,
+                    F::zero(),
+                )?;
+                for (row, (c, x)) in terms.iter().enumerate() {
+                    config.slc.enable(&mut region, row)?;
+                    region.assign_fixed(|| "c", config.coeff, row, || Ok(*c))?;
+                    let xcell = region.assign_advice(
+                        || "x",
+                        config.advices[1],
+                        row,
+                        || x.value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(x.cell, xcell)?;
+                    acc = acc.and_then(|acc| x.value.map(|x| acc + *c * x));
+                    cell = region.assign_advice(
+                        || "acc",
+                        config.advices[0],
+                        row + 1,
+                        || acc.ok_or(Error::SynthesisError),
+                    )?;
+                }
+                out = Some(Number { cell, value: acc });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn inner_product(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Self::Num, Error> {
+        layouter.assign_region(
+            || "inner product",
+            |mut region| self.assign_inner_product(&mut region, 0, a, b),
+        )
+    }
+
+    fn add_const(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        k: F,
+    ) -> Result<Self::Num, Error> {
+        let res = a.value.map(|a| a + k);
+        self.assign_const_op(layouter, self.config().saddc, a, k, res)
+    }
+
+    fn mul_const(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        k: F,
+    ) -> Result<Self::Num, Error> {
+        let res = a.value.map(|a| a * k);
+        self.assign_const_op(layouter, self.config().smulc, a, k, res)
+    }
+
+    fn is_zero(&self, mut layouter: impl Layouter<F>, a: Self::Num) -> Result<Bit<F>, Error> {
+        let config = self.config();
+        let inv = a.value.map(|a| a.invert().unwrap_or(F::zero()));
+        let res = a.value.map(|a| F::from(a == F::zero()));
+        let mut out = None;
+        layouter.assign_region(
+            || "is zero",
+            |mut region| {
+                config.siszero.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "a",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, lhs)?;
+                region.assign_advice(
+                    || "inv",
+                    config.advices[1],
+                    0,
+                    || inv.ok_or(Error::SynthesisError),
+                )?;
+                let cell = region.assign_advice(
+                    || "a == 0",
+                    config.advices[0],
+                    1,
+                    || res.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Bit(Number { cell, value: res }));
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn is_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Bit<F>, Error> {
+        let diff = self.linear_combination(
+            layouter.namespace(|| "a - b"),
+            &[(F::one(), a), (-F::one(), b)],
+        )?;
+        self.is_zero(layouter.namespace(|| "a - b == 0"), diff)
+    }
+
+    fn assert_bool(&self, mut layouter: impl Layouter<F>, a: Self::Num) -> Result<Bit<F>, Error> {
+        // a * (a - 1) == 0 iff a^2 == a
+        let square = self.square(layouter.namespace(|| "a^2"), a.clone())?;
+        self.assert_equal(layouter.namespace(|| "a^2 == a"), square, a.clone())?;
+        Ok(Bit(a))
+    }
+
+    fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert equal",
+            |mut region| region.constrain_equal(a.cell, b.cell),
+        )
+    }
+
+    fn invert_or_zero(
         &self,
         mut layouter: impl Layouter<F>,
-        constant: F,
+        a: Self::Num,
     ) -> Result<Self::Num, Error> {
         let config = self.config();
-        let mut num = None;
+        let inv = a.value.map(|a| a.invert().unwrap_or(F::zero()));
+        let is_zero = a.value.map(|a| F::from(a == F::zero()));
+        let mut out = None;
         layouter.assign_region(
-            || "load constant",
+            || "invert or zero",
             |mut region| {
-                let cell = region.assign_advice_from_constant(
-                    || "constant",
+                config.sinvz.enable(&mut region, 0)?;
+                let lhs = region.assign_advice(
+                    || "a",
                     config.advices[0],
                     0,
-                    constant,
+                    || a.value.ok_or(Error::SynthesisError),
                 )?;
-                num = Some(Number {
-                    cell: cell,
-                    value: Some(constant),
-                });
+                region.constrain_equal(a.cell, lhs)?;
+                let cell = region.assign_advice(
+                    || "1 / a",
+                    config.advices[1],
+                    0,
+                    || inv.ok_or(Error::SynthesisError),
+                )?;
+                region.assign_advice(
+                    || "a == 0",
+                    config.advices[0],
+                    1,
+                    || is_zero.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: inv });
                 Ok(())
             },
         )?;
-        Ok(num.unwrap())
+        Ok(out.unwrap())
     }
 
-    fn load_public(&self, mut layouter: impl Layouter<F>, row: usize) -> Result<Self::Num, Error> {
+    fn add_many(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error> {
+        self.assign_elementwise(layouter, self.config().sadd, a, b, |a, b| a + b, false)
+    }
+
+    fn mul_many(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error> {
+        self.assign_elementwise(layouter, self.config().smul, a, b, |a, b| a * b, false)
+    }
+
+    fn sub_many(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &[Self::Num],
+        b: &[Self::Num],
+    ) -> Result<Vec<Self::Num>, Error> {
+        self.assign_elementwise(layouter, self.config().sadd, a, b, |a, b| a - b, true)
+    }
+
+    fn accumulate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Self::Num],
+    ) -> Result<Self::Num, Error> {
         let config = self.config();
-        let mut num = None;
+        let (first, rest) = values.split_first().ok_or(Error::SynthesisError)?;
+        let mut out = None;
         layouter.assign_region(
-            || "load public",
+            || "accumulate",
             |mut region| {
-                let cell = region.assign_advice_from_instance(
-                    || "instance",
-                    config.instance,
-                    row,
+                let mut acc = first.value;
+                let mut cell = region.assign_advice(
+                    || "acc",
                     config.advices[0],
                     0,
+                    || acc.ok_or(Error::SynthesisError),
                 )?;
-                num = Some(Number {
-                    cell: cell.0,
-                    value: cell.1,
-                });
+                region.constrain_equal(first.cell, cell)?;
+                for (i, v) in rest.iter().enumerate() {
+                    let row = i + 1;
+                    config.saccum.enable(&mut region, row)?;
+                    let vcell = region.assign_advice(
+                        || "v",
+                        config.advices[1],
+                        row,
+                        || v.value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(v.cell, vcell)?;
+                    acc = acc.and_then(|acc| v.value.map(|v| acc + v));
+                    cell = region.assign_advice(
+                        || "acc",
+                        config.advices[0],
+                        row,
+                        || acc.ok_or(Error::SynthesisError),
+                    )?;
+                }
+                out = Some(Number { cell, value: acc });
                 Ok(())
             },
         )?;
-        Ok(num.unwrap())
-    }
-
-    fn expose_public(
-        &self,
-        mut layouter: impl Layouter<F>,
-        num: Self::Num,
-        row: usize,
-    ) -> Result<(), Error> {
-        let config = self.config();
-        // Constrains a [`Cell`] to equal an instance column's row value at an
-        // absolute position.
-        layouter.constrain_instance(num.cell, config.instance, row)
+        Ok(out.unwrap())
     }
 
-    fn add(
+    fn generic_op(
         &self,
         mut layouter: impl Layouter<F>,
         a: Self::Num,
         b: Self::Num,
+        q: GenericCoeffs<F>,
     ) -> Result<Self::Num, Error> {
         let config = self.config();
+        if q.q_o == F::zero() {
+            return Err(Error::SynthesisError);
+        }
+        let q_o_inv = q.q_o.invert().unwrap();
+        let res = a.value.and_then(|a| {
+            b.value
+                .map(|b| -(q.q_l * a + q.q_r * b + q.q_m * a * b + q.q_c) * q_o_inv)
+        });
         let mut out = None;
         layouter.assign_region(
-            || "add",
+            || "generic",
             |mut region| {
-                config.sadd.enable(&mut region, 0)?;
+                for (column, value) in config
+                    .generic
+                    .iter()
+                    .zip([q.q_l, q.q_r, q.q_m, q.q_o, q.q_c].iter())
+                {
+                    region.assign_fixed(|| "q", *column, 0, || Ok(*value))?;
+                }
                 let lhs = region.assign_advice(
-                    || "lhs",
+                    || "a",
                     config.advices[0],
                     0,
                     || a.value.ok_or(Error::SynthesisError),
                 )?;
                 let rhs = region.assign_advice(
-                    || "rhs",
+                    || "b",
                     config.advices[1],
                     0,
                     || b.value.ok_or(Error::SynthesisError),
                 )?;
                 region.constrain_equal(a.cell, lhs)?;
                 region.constrain_equal(b.cell, rhs)?;
-                let res = a.value.and_then(|a| b.value.map(|b| a + b));
                 let cell = region.assign_advice(
-                    || "lhs + rhs",
+                    || "out",
                     config.advices[0],
-                    1, // OFFSET
+                    1,
                     || res.ok_or(Error::SynthesisError),
                 )?;
-                out = Some(Number {
-                    value: res,
-                    cell: cell,
-                });
+                out = Some(Number { cell, value: res });
                 Ok(())
             },
         )?;
         Ok(out.unwrap())
     }
 
-    fn mul(
+    fn assert_not_equal(
         &self,
         mut layouter: impl Layouter<F>,
         a: Self::Num,
         b: Self::Num,
+    ) -> Result<(), Error> {
+        let diff = self.linear_combination(
+            layouter.namespace(|| "a - b"),
+            &[(F::one(), a), (-F::one(), b)],
+        )?;
+        self.invert(layouter.namespace(|| "1 / (a - b)"), diff)?;
+        Ok(())
+    }
+
+    fn affine(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        x: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        // the fused multiply-add gate already lays it out in one region
+        self.mul_add(layouter, a, x, b)
+    }
+
+    fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise(layouter, BitwiseOp::And, a, b)
+    }
+
+    fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise(layouter, BitwiseOp::Or, a, b)
+    }
+
+    fn nand(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise(layouter, BitwiseOp::Nand, a, b)
+    }
+
+    fn xnor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise(layouter, BitwiseOp::Xnor, a, b)
+    }
+
+    fn not(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        bitlength: usize,
     ) -> Result<Self::Num, Error> {
         let config = self.config();
+        if bitlength > config.xor_bitlength {
+            return Err(Error::SynthesisError);
+        }
+        let mask = F::from((1u64 << bitlength) - 1);
+        let res = a.value.map(|a| mask - a);
         let mut out = None;
         layouter.assign_region(
-            || "mul",
+            || "not",
             |mut region| {
-                config.smul.enable(&mut region, 0)?;
+                // both a and out fit in the table and add up to the mask, so
+                // a is at most the mask
+                config.snot.enable(&mut region, 0)?;
+                config.stable.enable(&mut region, 0)?;
+                region.assign_fixed(|| "mask", config.coeff, 0, || Ok(mask))?;
                 let lhs = region.assign_advice(
-                    || "lhs",
+                    || "a",
                     config.advices[0],
                     0,
                     || a.value.ok_or(Error::SynthesisError),
                 )?;
-                let rhs = region.assign_advice(
-                    || "rhs",
-                    config.advices[1],
-                    0,
-                    || b.value.ok_or(Error::SynthesisError),
-                )?;
                 region.constrain_equal(a.cell, lhs)?;
-                region.constrain_equal(b.cell, rhs)?;
-                let res = a.value.and_then(|a| b.value.map(|b| a * b));
                 let cell = region.assign_advice(
-                    || "lhs * rhs",
-                    config.advices[0],
-                    1, // OFFSET !
+                    || "not a",
+                    config.advices[1],
+                    0,
                     || res.ok_or(Error::SynthesisError),
                 )?;
-                out = Some(Number {
-                    value: res,
-                    cell: cell,
+                let xord = a.value.and_then(|a| {
+                    res.map(|res| F::from((a.get_lower_32() ^ res.get_lower_32()) as u64))
                 });
+                region.assign_advice(
+                    || "a xor not a",
+                    config.xord,
+                    0,
+                    || xord.ok_or(Error::SynthesisError),
+                )?;
+                out = Some(Number { cell, value: res });
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn shl(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bit_permutation(layouter, a, word_size, |i| {
+            Some(i + n).filter(|j| *j < word_size)
+        })
+    }
+
+    fn shr(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bit_permutation(layouter, a, word_size, |i| i.checked_sub(n))
+    }
+
+    fn rotl(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bit_permutation(layouter, a, word_size, |i| Some((i + n) % word_size))
+    }
+
+    fn rotr(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        word_size: usize,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bit_permutation(layouter, a, word_size, |i| {
+            Some((i + word_size - n % word_size) % word_size)
+        })
+    }
+
+    fn xor_wide(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        total_bits: usize,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise_wide(layouter, BitwiseOp::Xor, a, b, total_bits)
+    }
+
+    fn and_wide(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        total_bits: usize,
+    ) -> Result<Self::Num, Error> {
+        self.assign_bitwise_wide(layouter, BitwiseOp::And, a, b, total_bits)
+    }
+
+    fn popcount(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error> {
+        let bits = self.decompose_bits(layouter.namespace(|| "bits"), a, bits)?;
+        let terms: Vec<(F, Number<F>)> = bits.into_iter().map(|bit| (F::one(), bit)).collect();
+        self.linear_combination(layouter.namespace(|| "sum of bits"), &terms)
+    }
+
+    fn parity(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error> {
+        // the parity is the low bit of the popcount, which is at most `bits`
+        let count = self.popcount(layouter.namespace(|| "popcount"), a, bits)?;
+        let count_bits = (usize::BITS - bits.leading_zeros()).max(1) as usize;
+        let (_, parity) = self.div_pow2(layouter.namespace(|| "low bit"), count, 1, count_bits)?;
+        Ok(parity)
+    }
+
+    fn pack_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[Bit<F>],
+        order: BitOrder,
+    ) -> Result<Self::Num, Error> {
+        let n = bits.len();
+        let terms: Vec<(F, Number<F>)> = bits
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                let weight = match order {
+                    BitOrder::LittleEndian => pow2(i),
+                    BitOrder::BigEndian => pow2(n - 1 - i),
+                };
+                (weight, bit.num())
+            })
+            .collect();
+        self.linear_combination(layouter, &terms)
+    }
+
+    fn unpack(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        n: usize,
+        order: BitOrder,
+    ) -> Result<Vec<Bit<F>>, Error> {
+        // the decomposition constrains every bit to be boolean
+        let mut bits: Vec<Bit<F>> = self
+            .decompose_bits(layouter, a, n)?
+            .into_iter()
+            .map(Bit)
+            .collect();
+        if order == BitOrder::BigEndian {
+            bits.reverse();
+        }
+        Ok(bits)
+    }
+
+    fn msb(&self, layouter: impl Layouter<F>, a: Self::Num, bits: usize) -> Result<Bit<F>, Error> {
+        if bits == 0 {
+            return Err(Error::SynthesisError);
+        }
+        // a fits in `bits` bits, so a >> (bits - 1) is the top bit
+        let (top, _) = self.div_pow2(layouter, a, bits - 1, bits)?;
+        Ok(Bit(top))
+    }
+
+    fn lt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Bit<F>, Error> {
+        let mut out = None;
+        layouter.assign_region(
+            || "less than",
+            |mut region| {
+                out = Some(Bit(self.assign_lt(&mut region, 0, &a, &b, bits)?));
                 Ok(())
             },
         )?;
         Ok(out.unwrap())
     }
 
-    fn xor(
+    fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bit: Bit<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        // bit * (a - b) + b
+        let diff = self.linear_combination(
+            layouter.namespace(|| "a - b"),
+            &[(F::one(), a), (-F::one(), b.clone())],
+        )?;
+        self.mul_add(layouter.namespace(|| "select"), bit.num(), diff, b)
+    }
+
+    fn min(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error> {
+        let less = self.lt(layouter.namespace(|| "a < b"), a.clone(), b.clone(), bits)?;
+        self.select(layouter.namespace(|| "min"), less, a, b)
+    }
+
+    fn max(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error> {
+        let less = self.lt(layouter.namespace(|| "a < b"), a.clone(), b.clone(), bits)?;
+        self.select(layouter.namespace(|| "max"), less, b, a)
+    }
+
+    fn assert_in_range(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        lo: u64,
+        hi: u64,
+    ) -> Result<(), Error> {
+        if lo > hi {
+            return Err(Error::SynthesisError);
+        }
+        // a - lo and hi - a both fit in the bit length of hi - lo, and add up
+        // to hi - lo, so neither wraps around
+        let num_bits = (u64::BITS - (hi - lo).leading_zeros()) as usize;
+        let above = self.add_const(layouter.namespace(|| "a - lo"), a.clone(), -F::from(lo))?;
+        let neg = self.neg(layouter.namespace(|| "-a"), a)?;
+        let below = self.add_const(layouter.namespace(|| "hi - a"), neg, F::from(hi))?;
+        self.range_check_running_sum(layouter.namespace(|| "a >= lo"), above, num_bits)?;
+        self.range_check_running_sum(layouter.namespace(|| "a <= hi"), below, num_bits)
+    }
+
+    fn cond_swap(
         &self,
         mut layouter: impl Layouter<F>,
+        bit: Bit<F>,
         a: Self::Num,
         b: Self::Num,
-    ) -> Result<Self::Num, Error> {
-        let config = self.config();
-        let mut out = None;
+    ) -> Result<(Self::Num, Self::Num), Error> {
+        let first = self.select(layouter.namespace(|| "first"), bit, b.clone(), a.clone())?;
+        // the outputs add up to a + b
+        let second = self.linear_combination(
+            layouter.namespace(|| "second"),
+            &[(F::one(), a), (F::one(), b), (-F::one(), first.clone())],
+        )?;
+        Ok((first, second))
+    }
+
+    fn assert_sorted(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Self::Num],
+        bits: usize,
+    ) -> Result<(), Error> {
         layouter.assign_region(
-            || "mul",
+            || "sorted",
             |mut region| {
-                config.stable.enable(&mut region, 0)?;
-                let lhs = region.assign_advice(
-                    || "xor a",
-                    config.advices[0],
-                    0,
-                    || a.value.ok_or(Error::SynthesisError),
-                )?;
-                let rhs = region.assign_advice(
-                    || "xor b",
-                    config.advices[1],
-                    0,
-                    || b.value.ok_or(Error::SynthesisError),
-                )?;
-                region.constrain_equal(a.cell, lhs)?;
-                region.constrain_equal(b.cell, rhs)?;
-                let res = a.value.and_then(|a| {
-                    b.value
-                        .map(|b| F::from((a.get_lower_32() ^ b.get_lower_32()) as u64))
-                });
-                let xord = region.assign_advice(
-                    || "xor-d",
-                    config.xord,
-                    0, // Could use offset as well - but here we use a new column
-                    || res.ok_or(Error::SynthesisError),
-                )?;
-                out = Some(Number {
-                    value: res,
-                    cell: xord,
-                });
+                // values[i + 1] < values[i] is false for every pair
+                for (i, pair) in values.windows(2).enumerate() {
+                    let less =
+                        self.assign_lt(&mut region, i * (bits + 3), &pair[1], &pair[0], bits)?;
+                    region.constrain_constant(less.cell, F::zero())?;
+                }
                 Ok(())
             },
-        )?;
-        Ok(out.unwrap())
+        )
+    }
+
+    fn abs_diff(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+        bits: usize,
+    ) -> Result<Self::Num, Error> {
+        // swapped when a < b, so that the difference is non-negative
+        let less = self.lt(layouter.namespace(|| "a < b"), a.clone(), b.clone(), bits)?;
+        let (hi, lo) = self.cond_swap(layouter.namespace(|| "order"), less, a, b)?;
+        self.linear_combination(
+            layouter.namespace(|| "hi - lo"),
+            &[(F::one(), hi), (-F::one(), lo)],
+        )
+    }
+
+    fn at_least_k_of_n(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[Bit<F>],
+        k: u64,
+    ) -> Result<Bit<F>, Error> {
+        let values: Vec<Number<F>> = bits.iter().map(|bit| bit.num()).collect();
+        let count = self.accumulate(layouter.namespace(|| "count"), &values)?;
+        if k == 0 {
+            let one = self.load_constant(layouter.namespace(|| "true"), F::one())?;
+            return Ok(Bit(one));
+        }
+        // count >= k iff k - 1 < count, both fitting in the bit length of
+        // max(n, k)
+        let max = (bits.len() as u64).max(k);
+        let width = (u64::BITS - max.leading_zeros()) as usize;
+        let threshold = self.load_constant(layouter.namespace(|| "k - 1"), F::from(k - 1))?;
+        self.lt(
+            layouter.namespace(|| "k - 1 < count"),
+            threshold,
+            count,
+            width,
+        )
     }
 }
 
@@ -429,7 +3542,14 @@ impl<F: FieldExt> NumericInstructions<F> for FieldChip<F> {
 mod tests {
 
     use super::*;
-    use halo2::{dev::MockProver, pasta::Fp};
+    use halo2::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    // Allocates fresh columns and configures a FieldChip with a 2-bit XOR
+    // table over them.
+    fn configure_chip<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> FieldConfig {
+        FieldChip::configure_default(meta, 2)
+    }
+
     #[test]
     fn pasta_bits() {
         let exp: u32 = 13;
@@ -456,18 +3576,7 @@ mod tests {
             }
 
             fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-                let advice = [meta.advice_column(), meta.advice_column()];
-                let instance = meta.instance_column();
-                let constant = meta.fixed_column();
-
-                let xor_table = [
-                    meta.lookup_table_column(),
-                    meta.lookup_table_column(),
-                    meta.lookup_table_column(),
-                ];
-                let xord = meta.advice_column();
-                let bitsize = 2;
-                FieldChip::configure(meta, advice, instance, constant, bitsize, xor_table, xord)
+                configure_chip(meta)
             }
             fn synthesize(
                 &self,
@@ -493,28 +3602,7 @@ mod tests {
                     constant,
                 )?;
                 let c2 = field_chip.mul(layouter.namespace(|| "c^2"), c.clone(), c.clone())?;
-                //// TODO can we do it better ? would be nice to have
-                //// layouter.constrain_equal instead of spawning a region just for this?
-                layouter.namespace(|| "check equal").assign_region(
-                    || "check",
-                    |mut region| {
-                        let lhs = region.assign_advice(
-                            || "lhs",
-                            field_chip.config().advices[0],
-                            0,
-                            || ab2const.value.ok_or(Error::SynthesisError),
-                        )?;
-                        let rhs = region.assign_advice(
-                            || "rhs",
-                            field_chip.config().advices[1],
-                            0,
-                            || c2.value.ok_or(Error::SynthesisError),
-                        )?;
-                        region.constrain_equal(ab2const.cell, lhs)?;
-                        region.constrain_equal(c2.cell, rhs)?;
-                        region.constrain_equal(lhs, rhs)
-                    },
-                )?;
+                field_chip.assert_equal(layouter.namespace(|| "check equal"), ab2const, c2)?;
 
                 // Second test
                 let xord =
@@ -551,4 +3639,437 @@ mod tests {
         let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
         assert_eq!(prover.verify(), Ok(()));
     }
+
+    #[test]
+    fn interpolate() {
+        // evaluates the polynomial through (xs[i], ys[i]) at z
+        #[derive(Default)]
+        struct Interpolation<F: FieldExt> {
+            xs: Vec<F>,
+            ys: Vec<Option<F>>,
+            z: Option<F>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for Interpolation<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    xs: self.xs.clone(),
+                    ys: vec![None; self.ys.len()],
+                    z: None,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let ys = self
+                    .ys
+                    .iter()
+                    .map(|y| field_chip.load_private(layouter.namespace(|| "load y"), *y))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let z = field_chip.load_private(layouter.namespace(|| "load z"), self.z)?;
+                let res =
+                    field_chip.interpolate(layouter.namespace(|| "p(z)"), &self.xs, &ys, z)?;
+                field_chip.expose_public(layouter.namespace(|| "expose p(z)"), res, 0)
+            }
+        }
+
+        // p(x) = x^3 + 2x + 1
+        let p = |x: Fp| x * x * x + x + x + Fp::one();
+        let xs: Vec<Fp> = (0..4).map(|i| Fp::from(i)).collect();
+        let ys = xs.iter().map(|x| Some(p(*x))).collect();
+        let k = 6;
+
+        // outside and on the nodes
+        for z in [Fp::from(5), Fp::from(2)].iter() {
+            let circuit = Interpolation {
+                xs: xs.clone(),
+                ys: ys.clone(),
+                z: Some(*z),
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![p(*z)]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            let prover = MockProver::run(k, &circuit, vec![vec![p(*z) + Fp::one()]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn ntt() {
+        #[derive(Default)]
+        struct Ntt<F: FieldExt> {
+            values: Vec<Option<F>>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for Ntt<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    values: vec![None; self.values.len()],
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let values = self
+                    .values
+                    .iter()
+                    .map(|v| field_chip.load_private(layouter.namespace(|| "load value"), *v))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let res = field_chip.ntt(layouter.namespace(|| "ntt"), &values)?;
+                for (i, r) in res.into_iter().enumerate() {
+                    field_chip.expose_public(layouter.namespace(|| "expose"), r, i)?;
+                }
+                Ok(())
+            }
+        }
+
+        let n = 8;
+        let omega = root_of_unity::<Fp>(3).unwrap();
+        let values: Vec<Fp> = (0..n).map(|i| Fp::from(i * i + 7)).collect();
+        // naive DFT
+        let expected: Vec<Fp> = (0..n)
+            .map(|k| {
+                values.iter().enumerate().fold(Fp::zero(), |acc, (j, x)| {
+                    acc + *x * omega.pow_vartime(&[j as u64 * k])
+                })
+            })
+            .collect();
+
+        let circuit = Ntt {
+            values: values.iter().map(|v| Some(*v)).collect(),
+        };
+        let k = 6;
+        let prover = MockProver::run(k, &circuit, vec![expected.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mut swapped = expected;
+        swapped.swap(1, 2);
+        let prover = MockProver::run(k, &circuit, vec![swapped]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        // exposes (result, overflow) of a checked operation over 8 bits
+        #[derive(Default)]
+        struct Checked<F: FieldExt> {
+            a: Option<F>,
+            b: Option<F>,
+            mul: bool,
+        }
+
+        impl<F: FieldExt> Circuit<F> for Checked<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    mul: self.mul,
+                    ..Self::default()
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let a = field_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+                let b = field_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+                field_chip.range_check(layouter.namespace(|| "a fits"), a.clone(), 8)?;
+                field_chip.range_check(layouter.namespace(|| "b fits"), b.clone(), 8)?;
+                let (res, overflow) = if self.mul {
+                    field_chip.checked_mul(layouter.namespace(|| "a * b"), a, b, 8)?
+                } else {
+                    field_chip.checked_add(layouter.namespace(|| "a + b"), a, b, 8)?
+                };
+                field_chip.expose_public(layouter.namespace(|| "expose result"), res, 0)?;
+                field_chip.expose_public(layouter.namespace(|| "expose overflow"), overflow, 1)
+            }
+        }
+
+        let k = 7;
+        let cases = [
+            // (a, b, mul, result, overflow)
+            (20, 30, false, 50, 0),
+            (200, 100, false, 44, 1),
+            (10, 12, true, 120, 0),
+            (20, 30, true, 88, 1),
+        ];
+        for (a, b, mul, res, overflow) in cases.iter() {
+            let circuit = Checked {
+                a: Some(Fp::from(*a)),
+                b: Some(Fp::from(*b)),
+                mul: *mul,
+            };
+            let public_inputs = vec![Fp::from(*res), Fp::from(*overflow)];
+            let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // the wrapped result without the overflow flag
+            let public_inputs = vec![Fp::from(*res), Fp::zero()];
+            let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+            assert_eq!(prover.verify().is_ok(), *overflow == 0);
+        }
+
+        // operands must fit in 8 bits
+        let circuit = Checked {
+            a: Some(Fp::from(256)),
+            b: Some(Fp::from(1)),
+            mul: false,
+        };
+        let public_inputs = vec![Fp::from(1), Fp::one()];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn div_pow2() {
+        // exposes (x >> 3, x mod 8) for an 8-bit x
+        #[derive(Default)]
+        struct DivPow2<F: FieldExt> {
+            x: Option<F>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for DivPow2<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self::default()
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let x = field_chip.load_private(layouter.namespace(|| "load x"), self.x)?;
+                let (q, r) = field_chip.div_pow2(layouter.namespace(|| "x / 8"), x, 3, 8)?;
+                field_chip.expose_public(layouter.namespace(|| "expose q"), q, 0)?;
+                field_chip.expose_public(layouter.namespace(|| "expose r"), r, 1)
+            }
+        }
+
+        let k = 6;
+        let circuit = DivPow2 {
+            x: Some(Fp::from(182)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(22), Fp::from(6)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(21), Fp::from(14)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // x does not fit in 8 bits
+        let circuit = DivPow2 {
+            x: Some(Fp::from(300)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(5), Fp::from(4)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn one_hot() {
+        #[derive(Default)]
+        struct OneHot<F: FieldExt> {
+            index: Option<F>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for OneHot<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self::default()
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let index =
+                    field_chip.load_private(layouter.namespace(|| "load index"), self.index)?;
+                let bits = field_chip.one_hot(layouter.namespace(|| "one hot"), index, 5)?;
+                for (i, bit) in bits.into_iter().enumerate() {
+                    field_chip.expose_public(layouter.namespace(|| "expose bit"), bit, i)?;
+                }
+                Ok(())
+            }
+        }
+
+        let k = 6;
+        let bits = |set: usize| -> Vec<Fp> { (0..5).map(|i| Fp::from(i == set)).collect() };
+        let circuit = OneHot {
+            index: Some(Fp::from(3)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![bits(3)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(k, &circuit, vec![bits(2)]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the index is out of bounds so no bit can be set
+        let circuit = OneHot {
+            index: Some(Fp::from(5)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::zero(); 5]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn select_from() {
+        #[derive(Default)]
+        struct Select<F: FieldExt> {
+            vals: Vec<Option<F>>,
+            index: Option<F>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for Select<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    vals: vec![None; self.vals.len()],
+                    index: None,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let vals = self
+                    .vals
+                    .iter()
+                    .map(|v| field_chip.load_private(layouter.namespace(|| "load value"), *v))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let index =
+                    field_chip.load_private(layouter.namespace(|| "load index"), self.index)?;
+                let res = field_chip.select_from(layouter.namespace(|| "select"), &vals, index)?;
+                field_chip.expose_public(layouter.namespace(|| "expose"), res, 0)
+            }
+        }
+
+        let k = 6;
+        let vals: Vec<Option<Fp>> = [10, 20, 30, 40]
+            .iter()
+            .map(|v| Some(Fp::from(*v)))
+            .collect();
+        let circuit = Select {
+            vals: vals.clone(),
+            index: Some(Fp::from(2)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(30)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(20)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        let circuit = Select {
+            vals,
+            index: Some(Fp::from(4)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::zero()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn lex_lt() {
+        #[derive(Default)]
+        struct LexLt<F: FieldExt> {
+            a: Vec<Option<F>>,
+            b: Vec<Option<F>>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for LexLt<F> {
+            type Config = FieldConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    a: vec![None; self.a.len()],
+                    b: vec![None; self.b.len()],
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                configure_chip(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let field_chip = FieldChip::<F>::construct(config);
+                let mut load = |bytes: &[Option<F>]| {
+                    bytes
+                        .iter()
+                        .map(|v| field_chip.load_private(layouter.namespace(|| "load byte"), *v))
+                        .collect::<Result<Vec<_>, _>>()
+                };
+                let a = load(&self.a)?;
+                let b = load(&self.b)?;
+                let res = field_chip.lex_lt(layouter.namespace(|| "a < b"), &a, &b)?;
+                field_chip.expose_public(layouter.namespace(|| "expose"), res.num(), 0)
+            }
+        }
+
+        let k = 8;
+        let bytes =
+            |v: &[u64]| -> Vec<Option<Fp>> { v.iter().map(|b| Some(Fp::from(*b))).collect() };
+        let cases: [(&[u64], &[u64], bool); 5] = [
+            (&[1, 2, 3], &[1, 2, 4], true),
+            (&[1, 3, 0], &[1, 2, 9], false),
+            (&[1, 2, 3], &[1, 2, 3], false),
+            (&[0, 200, 7], &[1, 0, 0], true),
+            (&[255, 0, 0], &[254, 255, 255], false),
+        ];
+        for (a, b, lt) in cases.iter() {
+            let circuit = LexLt {
+                a: bytes(a),
+                b: bytes(b),
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(*lt)]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(!*lt)]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
 }
@@ -0,0 +1,200 @@
+// Merkle trees over MiMC, for proving properties of a small committed dataset
+// and updates of its leaves. A node is the Feistel hash of its two children,
+// and the leaves are padded with zeros up to the next power of two.
+use super::{
+    mimc::{feistel_hash_native, MimcInstructions},
+    Bit, FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+pub(crate) trait MerkleInstructions<F: FieldExt> {
+    /// Returns the root of the tree with the leaves `leaves`, padded with
+    /// zeros. Fails to synthesize without leaves.
+    fn compute_root(
+        &self,
+        layouter: impl Layouter<F>,
+        leaves: &[Number<F>],
+    ) -> Result<Number<F>, Error>;
+
+    /// Constrains `old_root` to be the root of a tree with `leaf_old` at the
+    /// end of `path`, and `new_root` the root of the same tree with
+    /// `leaf_new` in its place.
+    fn verify_update(
+        &self,
+        layouter: impl Layouter<F>,
+        old_root: Number<F>,
+        new_root: Number<F>,
+        leaf_old: Number<F>,
+        leaf_new: Number<F>,
+        path: &MerklePath<F>,
+    ) -> Result<(), Error>;
+}
+
+// The position of a leaf and its siblings from the leaf up. The i-th bit of
+// `index`, little-endian, is set when the node at height i is a right child.
+#[derive(Clone)]
+pub(crate) struct MerklePath<F: FieldExt> {
+    pub(crate) index: Vec<Bit<F>>,
+    pub(crate) siblings: Vec<Number<F>>,
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Returns the root of the tree with `leaf` at the end of `path`.
+    fn assign_root_from_path(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: Number<F>,
+        path: &MerklePath<F>,
+    ) -> Result<Number<F>, Error> {
+        if path.index.len() != path.siblings.len() {
+            return Err(Error::SynthesisError);
+        }
+        let mut node = leaf;
+        for (bit, sibling) in path.index.iter().zip(path.siblings.iter()) {
+            let (left, right) = self.cond_swap(
+                layouter.namespace(|| "order"),
+                bit.clone(),
+                node,
+                sibling.clone(),
+            )?;
+            node = self.feistel_hash(layouter.namespace(|| "node"), &[left, right])?;
+        }
+        Ok(node)
+    }
+}
+
+impl<F: FieldExt> MerkleInstructions<F> for FieldChip<F> {
+    fn compute_root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaves: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        if leaves.is_empty() {
+            return Err(Error::SynthesisError);
+        }
+        let mut level = leaves.to_vec();
+        let zero = self.load_constant(layouter.namespace(|| "padding"), F::zero())?;
+        level.resize(leaves.len().next_power_of_two(), zero);
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| self.feistel_hash(layouter.namespace(|| "node"), pair))
+                .collect::<Result<Vec<_>, Error>>()?;
+        }
+        Ok(level.pop().unwrap())
+    }
+
+    fn verify_update(
+        &self,
+        mut layouter: impl Layouter<F>,
+        old_root: Number<F>,
+        new_root: Number<F>,
+        leaf_old: Number<F>,
+        leaf_new: Number<F>,
+        path: &MerklePath<F>,
+    ) -> Result<(), Error> {
+        let root = self.assign_root_from_path(layouter.namespace(|| "old"), leaf_old, path)?;
+        self.assert_equal(layouter.namespace(|| "old root"), root, old_root)?;
+        let root = self.assign_root_from_path(layouter.namespace(|| "new"), leaf_new, path)?;
+        self.assert_equal(layouter.namespace(|| "new root"), root, new_root)
+    }
+}
+
+// The root of the tree with the leaves `leaves`, computed natively.
+pub(crate) fn root_native<F: FieldExt>(leaves: &[F]) -> F {
+    let mut level = leaves.to_vec();
+    level.resize(leaves.len().next_power_of_two(), F::zero());
+    while level.len() > 1 {
+        level = level.chunks(2).map(feistel_hash_native).collect();
+    }
+    level[0]
+}
+
+// The root of the tree with `leaf` at position `index` and the siblings
+// `siblings`, computed natively.
+pub(crate) fn root_from_path_native<F: FieldExt>(leaf: F, index: u64, siblings: &[F]) -> F {
+    siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (i, sibling)| match (index >> i) & 1 {
+            0 => feistel_hash_native(&[node, *sibling]),
+            _ => feistel_hash_native(&[*sibling, node]),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use halo2::pasta::Fp;
+
+    const DEPTH: usize = 2;
+
+    // [old_root, new_root, leaf_old, leaf_new, index bits, siblings] -> []
+    #[derive(Clone, Debug)]
+    struct Update;
+
+    fn sample(leaf_old: u64, leaf_new: u64, index: u64, siblings: &[u64]) -> Vec<Fp> {
+        let siblings: Vec<Fp> = siblings.iter().map(|s| Fp::from(*s)).collect();
+        let mut inputs = vec![
+            root_from_path_native(Fp::from(leaf_old), index, &siblings),
+            root_from_path_native(Fp::from(leaf_new), index, &siblings),
+            Fp::from(leaf_old),
+            Fp::from(leaf_new),
+        ];
+        inputs.extend((0..DEPTH).map(|i| Fp::from((index >> i) & 1)));
+        inputs.extend(siblings);
+        inputs
+    }
+
+    impl TestableGadget<Fp> for Update {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            vec![sample(5, 6, 2, &[3, 4]), sample(0, 9, 1, &[7, 8])]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            // a new root for another leaf, then for another position
+            let mut wrong_leaf = sample(5, 6, 2, &[3, 4]);
+            wrong_leaf[1] = sample(5, 7, 2, &[3, 4])[1];
+            let mut wrong_index = sample(5, 6, 2, &[3, 4]);
+            wrong_index[1] = sample(5, 6, 3, &[3, 4])[1];
+            vec![wrong_leaf, wrong_index]
+        }
+
+        fn reference(&self, _: &[Fp]) -> Vec<Fp> {
+            vec![]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let index = inputs[4..4 + DEPTH]
+                .iter()
+                .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let path = MerklePath {
+                index,
+                siblings: inputs[4 + DEPTH..].to_vec(),
+            };
+            chip.verify_update(
+                layouter,
+                inputs[0].clone(),
+                inputs[1].clone(),
+                inputs[2].clone(),
+                inputs[3].clone(),
+                &path,
+            )?;
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn update() {
+        // two paths of two nodes, each node taking two permutations of 221
+        // rows
+        check(&Update, 12);
+    }
+}
@@ -0,0 +1,94 @@
+// The Merkle–Damgård construction over any compression function, so that a
+// new compression chip only has to lay out a single compression. The input is
+// padded with a 1, then zeros, then its length, so that the padded input
+// splits into whole blocks and no two inputs share a padded input. The
+// chaining value starts at the initial value of the compression function and
+// goes through one compression per block, the last one being the hash.
+// Blocks and chaining values are field elements: byte-oriented functions such
+// as SHA-256 pack their words in Numbers before compressing.
+use super::{FieldChip, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::Error,
+};
+
+pub(crate) trait CompressionChip<F: FieldExt>: Chip<F> {
+    /// The number of field elements in a block.
+    const BLOCK_LEN: usize;
+
+    /// Returns the initial chaining value.
+    fn initial_value(&self, layouter: impl Layouter<F>) -> Result<Vec<Number<F>>, Error>;
+
+    /// Returns the chaining value following `chaining` once `block` is
+    /// compressed, `block` having BLOCK_LEN elements.
+    fn compress(
+        &self,
+        layouter: impl Layouter<F>,
+        chaining: &[Number<F>],
+        block: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error>;
+}
+
+// The padding of an input of `n` elements: 1, the zeros completing the last
+// block, and `n`.
+fn padding<F: FieldExt>(n: usize, block_len: usize) -> Vec<F> {
+    let zeros = (block_len - (n + 2) % block_len) % block_len;
+    let mut padding = vec![F::one()];
+    padding.extend(vec![F::zero(); zeros]);
+    padding.push(F::from(n as u64));
+    padding
+}
+
+// The Merkle–Damgård hash of `inputs` with the initial value `iv` and the
+// compression function `compress`, computed natively.
+pub(crate) fn hash_native<F: FieldExt>(
+    inputs: &[F],
+    block_len: usize,
+    iv: Vec<F>,
+    compress: impl Fn(&[F], &[F]) -> Vec<F>,
+) -> Vec<F> {
+    let mut padded = inputs.to_vec();
+    padded.extend(padding::<F>(inputs.len(), block_len));
+    padded
+        .chunks(block_len)
+        .fold(iv, |chaining, block| compress(&chaining, block))
+}
+
+// Hashes with the compression chip C, loading the padding with a FieldChip.
+// Both chips are borrowed, so FieldChip can be its own compression chip.
+pub(crate) struct MerkleDamgard<'a, F: FieldExt, C: CompressionChip<F>> {
+    field: &'a FieldChip<F>,
+    compression: &'a C,
+}
+
+impl<'a, F: FieldExt, C: CompressionChip<F>> MerkleDamgard<'a, F, C> {
+    pub(crate) fn construct(field: &'a FieldChip<F>, compression: &'a C) -> Self {
+        Self { field, compression }
+    }
+
+    // Returns the final chaining value once the padded `inputs` are
+    // compressed.
+    pub(crate) fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error> {
+        let mut padded = inputs.to_vec();
+        for value in padding::<F>(inputs.len(), C::BLOCK_LEN) {
+            padded.push(
+                self.field
+                    .load_constant(layouter.namespace(|| "padding"), value)?,
+            );
+        }
+        let mut chaining = self
+            .compression
+            .initial_value(layouter.namespace(|| "iv"))?;
+        for block in padded.chunks(C::BLOCK_LEN) {
+            chaining =
+                self.compression
+                    .compress(layouter.namespace(|| "compress"), &chaining, block)?;
+        }
+        Ok(chaining)
+    }
+}
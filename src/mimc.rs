@@ -0,0 +1,245 @@
+// MiMC over FieldChip, a hash much cheaper to write and to lay out than
+// Poseidon, for tutorials and quick prototypes. Every round is a single row
+// raising a sum to the fifth power, which is a permutation of the field as
+// long as 5 does not divide p - 1, as for the Pasta fields. Two modes:
+// * `permute` is the MiMC-p/p block cipher, x <- (x + k + c_i)^5 over ROUNDS
+//   rounds, the key being added again at the end,
+// * `feistel_hash` is the sponge over the MiMC-2p/p Feistel network with a
+//   zero key, absorbing one field element per permutation.
+use super::{merkle_damgard::CompressionChip, FieldChip, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::Error,
+};
+
+// ceil(255 / log2(5)), enough rounds for the degree of the permutation to
+// reach the size of the field.
+const ROUNDS: usize = 110;
+// The Feistel network only updates one half of the state per round.
+const FEISTEL_ROUNDS: usize = 2 * ROUNDS;
+
+fn pow5<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+// c_0 = 0 as in the MiMC paper, the other constants iterating x -> x^5 + 1
+// from 1. Any fixed constants do, so long as they are the same for the prover
+// and the verifier.
+fn round_constants<F: FieldExt>(n: usize) -> Vec<F> {
+    let mut constants = vec![F::zero()];
+    let mut c = F::one();
+    while constants.len() < n {
+        constants.push(c);
+        c = pow5(c) + F::one();
+    }
+    constants
+}
+
+pub(crate) trait MimcInstructions<F: FieldExt> {
+    /// Returns the encryption of `x` under the key `k`.
+    fn permute(
+        &self,
+        layouter: impl Layouter<F>,
+        x: Number<F>,
+        k: Number<F>,
+    ) -> Result<Number<F>, Error>;
+
+    /// Returns the Feistel network applied to `(l, r)`, one round per row.
+    fn feistel(
+        &self,
+        layouter: impl Layouter<F>,
+        l: Number<F>,
+        r: Number<F>,
+    ) -> Result<(Number<F>, Number<F>), Error>;
+
+    /// Returns the hash of `inputs`, absorbed one by one in the left half of
+    /// the state of the Feistel network.
+    fn feistel_hash(
+        &self,
+        layouter: impl Layouter<F>,
+        inputs: &[Number<F>],
+    ) -> Result<Number<F>, Error>;
+}
+
+impl<F: FieldExt> MimcInstructions<F> for FieldChip<F> {
+    fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: Number<F>,
+        k: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let config = self.config();
+        let constants = round_constants::<F>(ROUNDS);
+        let mut out = None;
+        layouter.assign_region(
+            || "mimc",
+            |mut region| {
+                let mut cell = region.assign_advice(
+                    || "x",
+                    config.advices[0],
+                    0,
+                    || x.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(x.cell, cell)?;
+                let mut value = x.value;
+                for (i, c) in constants.iter().enumerate() {
+                    config.smimc.enable(&mut region, i)?;
+                    region.assign_fixed(|| "c", config.coeff, i, || Ok(*c))?;
+                    let k_cell = region.assign_advice(
+                        || "k",
+                        config.advices[1],
+                        i,
+                        || k.value.ok_or(Error::SynthesisError),
+                    )?;
+                    region.constrain_equal(k.cell, k_cell)?;
+                    value = value.and_then(|v| k.value.map(|k| pow5(v + k + *c)));
+                    cell = region.assign_advice(
+                        || "x",
+                        config.advices[0],
+                        i + 1,
+                        || value.ok_or(Error::SynthesisError),
+                    )?;
+                }
+                out = Some(Number { cell, value });
+                Ok(())
+            },
+        )?;
+        self.add(layouter.namespace(|| "x + k"), out.unwrap(), k)
+    }
+
+    fn feistel(
+        &self,
+        mut layouter: impl Layouter<F>,
+        l: Number<F>,
+        r: Number<F>,
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        let config = self.config();
+        let constants = round_constants::<F>(FEISTEL_ROUNDS);
+        let mut out = None;
+        layouter.assign_region(
+            || "feistel",
+            |mut region| {
+                let l_cell = region.assign_advice(
+                    || "l",
+                    config.advices[0],
+                    0,
+                    || l.value.ok_or(Error::SynthesisError),
+                )?;
+                let r_cell = region.assign_advice(
+                    || "r",
+                    config.advices[1],
+                    0,
+                    || r.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(l.cell, l_cell)?;
+                region.constrain_equal(r.cell, r_cell)?;
+
+                let mut state = (l.clone(), r.clone());
+                for (i, c) in constants.iter().enumerate() {
+                    config.sfeistel.enable(&mut region, i)?;
+                    region.assign_fixed(|| "c", config.coeff, i, || Ok(*c))?;
+                    let (l, r) = state;
+                    let l_next = l.value.and_then(|l| r.value.map(|r| r + pow5(l + *c)));
+                    let l_cell = region.assign_advice(
+                        || "l",
+                        config.advices[0],
+                        i + 1,
+                        || l_next.ok_or(Error::SynthesisError),
+                    )?;
+                    let r_cell = region.assign_advice(
+                        || "r",
+                        config.advices[1],
+                        i + 1,
+                        || l.value.ok_or(Error::SynthesisError),
+                    )?;
+                    state = (
+                        Number {
+                            cell: l_cell,
+                            value: l_next,
+                        },
+                        Number {
+                            cell: r_cell,
+                            value: l.value,
+                        },
+                    );
+                }
+                out = Some(state);
+                Ok(())
+            },
+        )?;
+        Ok(out.unwrap())
+    }
+
+    fn feistel_hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        let mut l = self.load_constant(layouter.namespace(|| "l"), F::zero())?;
+        let mut r = self.load_constant(layouter.namespace(|| "r"), F::zero())?;
+        for input in inputs.iter() {
+            let absorbed = self.add(layouter.namespace(|| "absorb"), l, input.clone())?;
+            let (l_next, r_next) = self.feistel(layouter.namespace(|| "permute"), absorbed, r)?;
+            l = l_next;
+            r = r_next;
+        }
+        Ok(l)
+    }
+}
+
+// The Miyaguchi–Preneel compression h <- E_h(m) + h + m, for Merkle–Damgård
+// hashing of one field element per block.
+impl<F: FieldExt> CompressionChip<F> for FieldChip<F> {
+    const BLOCK_LEN: usize = 1;
+
+    fn initial_value(&self, layouter: impl Layouter<F>) -> Result<Vec<Number<F>>, Error> {
+        Ok(vec![self.load_constant(layouter, F::zero())?])
+    }
+
+    fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        chaining: &[Number<F>],
+        block: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error> {
+        let (h, m) = (chaining[0].clone(), block[0].clone());
+        let e = self.permute(layouter.namespace(|| "E_h(m)"), m.clone(), h.clone())?;
+        let one = F::one();
+        Ok(vec![self.linear_combination(
+            layouter.namespace(|| "E_h(m) + h + m"),
+            &[(one, e), (one, h), (one, m)],
+        )?])
+    }
+}
+
+// The Miyaguchi–Preneel compression of `m` with `h`, computed natively.
+pub(crate) fn compress_native<F: FieldExt>(h: F, m: F) -> F {
+    permute_native(m, h) + h + m
+}
+
+// The encryption of `x` under `k`, computed natively.
+pub(crate) fn permute_native<F: FieldExt>(x: F, k: F) -> F {
+    round_constants::<F>(ROUNDS)
+        .iter()
+        .fold(x, |x, c| pow5(x + k + *c))
+        + k
+}
+
+// The Feistel network applied to (l, r), computed natively.
+pub(crate) fn feistel_native<F: FieldExt>(l: F, r: F) -> (F, F) {
+    round_constants::<F>(FEISTEL_ROUNDS)
+        .iter()
+        .fold((l, r), |(l, r), c| (r + pow5(l + *c), l))
+}
+
+// The Feistel hash of `inputs`, computed natively.
+pub(crate) fn feistel_hash_native<F: FieldExt>(inputs: &[F]) -> F {
+    let (mut l, mut r) = (F::zero(), F::zero());
+    for input in inputs.iter() {
+        let (l_next, r_next) = feistel_native(l + *input, r);
+        l = l_next;
+        r = r_next;
+    }
+    l
+}
@@ -0,0 +1,73 @@
+// Zcash-style notes for privacy pools: the commitment cm = H(value, rho, r)
+// hides the note in a public tree, and the nullifier nf = H(nk, rho) is
+// revealed once, on spending, without linking it to cm. H is the MiMC Feistel
+// hash of mimc.rs, the crate having no Poseidon chip, with a tag absorbed
+// first so that commitments and nullifiers never collide.
+use super::{
+    mimc::{feistel_hash_native, MimcInstructions},
+    FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+const COMMITMENT_TAG: u64 = 1;
+const NULLIFIER_TAG: u64 = 2;
+
+pub(crate) trait NoteInstructions<F: FieldExt> {
+    /// Returns the commitment and the nullifier of the note of `value` with
+    /// the nonce `rho` and the randomness `r`, owned by the nullifier key
+    /// `nk`, and exposes them at the rows `row` and `row + 1` of the
+    /// instance column.
+    fn commit_and_nullify(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Number<F>,
+        rho: Number<F>,
+        r: Number<F>,
+        nk: Number<F>,
+        row: usize,
+    ) -> Result<(Number<F>, Number<F>), Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Returns the hash of `inputs` following `tag`.
+    fn hash_tagged(
+        &self,
+        mut layouter: impl Layouter<F>,
+        tag: u64,
+        inputs: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        let mut tagged = vec![self.load_constant(layouter.namespace(|| "tag"), F::from(tag))?];
+        tagged.extend_from_slice(inputs);
+        self.feistel_hash(layouter.namespace(|| "hash"), &tagged)
+    }
+}
+
+impl<F: FieldExt> NoteInstructions<F> for FieldChip<F> {
+    fn commit_and_nullify(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Number<F>,
+        rho: Number<F>,
+        r: Number<F>,
+        nk: Number<F>,
+        row: usize,
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        let cm = self.hash_tagged(
+            layouter.namespace(|| "cm"),
+            COMMITMENT_TAG,
+            &[value, rho.clone(), r],
+        )?;
+        let nf = self.hash_tagged(layouter.namespace(|| "nf"), NULLIFIER_TAG, &[nk, rho])?;
+        self.expose_public(layouter.namespace(|| "expose cm"), cm.clone(), row)?;
+        self.expose_public(layouter.namespace(|| "expose nf"), nf.clone(), row + 1)?;
+        Ok((cm, nf))
+    }
+}
+
+// The commitment and the nullifier of a note, computed natively.
+pub(crate) fn note_native<F: FieldExt>(value: F, rho: F, r: F, nk: F) -> (F, F) {
+    (
+        feistel_hash_native(&[F::from(COMMITMENT_TAG), value, rho, r]),
+        feistel_hash_native(&[F::from(NULLIFIER_TAG), nk, rho]),
+    )
+}
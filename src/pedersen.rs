@@ -0,0 +1,189 @@
+// Pedersen commitments over FieldChip: value * G + blinding * H on the curve
+// of ecc.rs.
+// G, H and the offset Q are the points with the smallest x coordinates, so
+// nobody knows the discrete logarithms between them. The scalar
+// multiplications are fixed-base: the doublings of the bases are constants,
+// added to an accumulator starting at Q whenever the bit of the scalar is set.
+// Starting at Q keeps the identity out of the incomplete additions, and Q is
+// subtracted at the end.
+use super::{
+    ecc::{add_native, double_native, find_point, Affine, EccInstructions, Point},
+    to_le_bits, Bit, BitOrder, FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+// G, H and Q.
+fn generators<F: FieldExt>() -> [Affine<F>; 3] {
+    let (x, g) = find_point(1);
+    let (x, h) = find_point(x + 1);
+    let (_, q) = find_point(x + 1);
+    [g, h, q]
+}
+
+// value * G + blinding * H, computed natively.
+pub(crate) fn commit_native<F: FieldExt>(value: F, blinding: F) -> Affine<F> {
+    let [g, h, q] = generators::<F>();
+    let mut acc = q;
+    for (scalar, base) in [(value, g), (blinding, h)].iter() {
+        let mut base = *base;
+        for bit in to_le_bits(scalar, F::CAPACITY as usize) {
+            if bit {
+                acc = add_native(acc, base);
+            }
+            base = double_native(base);
+        }
+    }
+    add_native(acc, (q.0, -q.1))
+}
+
+pub(crate) trait CommitmentInstructions<F: FieldExt> {
+    /// Returns value * G + blinding * H, both scalars fitting in
+    /// `F::CAPACITY` bits. Fails to synthesize when an incomplete addition
+    /// meets two points with the same x coordinate, which only happens with
+    /// negligible probability or for the identity, the commitment to (0, 0).
+    fn commit(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Number<F>,
+        blinding: Number<F>,
+    ) -> Result<Point<F>, Error>;
+
+    /// Constrains `commitment` to be the commitment to `value` with
+    /// `blinding`.
+    fn open(
+        &self,
+        layouter: impl Layouter<F>,
+        commitment: &Point<F>,
+        value: Number<F>,
+        blinding: Number<F>,
+    ) -> Result<(), Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Returns acc + scalar * base, adding the doublings of base selected by
+    // the bits of scalar.
+    fn assign_fixed_base_mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        acc: Point<F>,
+        scalar: Number<F>,
+        base: Affine<F>,
+    ) -> Result<Point<F>, Error> {
+        let bits: Vec<Bit<F>> = self.unpack(
+            layouter.namespace(|| "bits"),
+            scalar,
+            F::CAPACITY as usize,
+            BitOrder::LittleEndian,
+        )?;
+        let mut acc = acc;
+        let mut base = base;
+        for bit in bits {
+            let point = self.constant_point(layouter.namespace(|| "2^i base"), base)?;
+            let sum = self.add_incomplete(layouter.namespace(|| "acc + 2^i base"), &acc, &point)?;
+            acc = Point {
+                x: self.select(layouter.namespace(|| "x"), bit.clone(), sum.x, acc.x)?,
+                y: self.select(layouter.namespace(|| "y"), bit, sum.y, acc.y)?,
+            };
+            base = double_native(base);
+        }
+        Ok(acc)
+    }
+}
+
+impl<F: FieldExt> CommitmentInstructions<F> for FieldChip<F> {
+    fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Number<F>,
+        blinding: Number<F>,
+    ) -> Result<Point<F>, Error> {
+        let [g, h, q] = generators::<F>();
+        let offset = self.constant_point(layouter.namespace(|| "Q"), q)?;
+        let acc =
+            self.assign_fixed_base_mul(layouter.namespace(|| "value * G"), offset, value, g)?;
+        let acc =
+            self.assign_fixed_base_mul(layouter.namespace(|| "blinding * H"), acc, blinding, h)?;
+        let neg_offset = self.constant_point(layouter.namespace(|| "-Q"), (q.0, -q.1))?;
+        self.add_incomplete(layouter.namespace(|| "acc - Q"), &acc, &neg_offset)
+    }
+
+    fn open(
+        &self,
+        mut layouter: impl Layouter<F>,
+        commitment: &Point<F>,
+        value: Number<F>,
+        blinding: Number<F>,
+    ) -> Result<(), Error> {
+        let expected = self.commit(layouter.namespace(|| "commit"), value, blinding)?;
+        self.assert_equal(layouter.namespace(|| "x"), commitment.x.clone(), expected.x)?;
+        self.assert_equal(layouter.namespace(|| "y"), commitment.y.clone(), expected.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecc::B;
+    use crate::harness::{check, TestableGadget};
+    use halo2::{arithmetic::Field, pasta::Fp};
+
+    // [value, blinding] -> [x, y] of the commitment
+    #[derive(Clone, Debug)]
+    struct Commit;
+
+    impl TestableGadget<Fp> for Commit {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            vec![
+                vec![Fp::from(1), Fp::from(2)],
+                vec![Fp::from(42), Fp::from(0x1234_5678_9abc)],
+            ]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            // p - 1 does not fit in F::CAPACITY bits
+            vec![vec![-Fp::one(), Fp::from(2)]]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let (x, y) = commit_native(inputs[0], inputs[1]);
+            vec![x, y]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let commitment = chip.commit(
+                layouter.namespace(|| "commit"),
+                inputs[0].clone(),
+                inputs[1].clone(),
+            )?;
+            chip.open(
+                layouter.namespace(|| "open"),
+                &commitment,
+                inputs[0].clone(),
+                inputs[1].clone(),
+            )?;
+            Ok(vec![commitment.x, commitment.y])
+        }
+    }
+
+    #[test]
+    fn native() {
+        let [g, h, q] = generators::<Fp>();
+        for (x, y) in [g, h, q].iter() {
+            assert_eq!(*y * y, *x * x * x + Fp::from(B));
+        }
+        // 3 * G + 0 * H is G + 2 * G
+        let g2 = double_native(g);
+        assert_eq!(commit_native(Fp::from(3), Fp::from(0)), add_native(g, g2));
+    }
+
+    #[test]
+    fn commit() {
+        // a commitment takes about 2^15 rows
+        check(&Commit, 16);
+    }
+}
@@ -0,0 +1,296 @@
+// Real proofs, beyond MockProver: the circuits of the crate over Fp are
+// proven with halo2's inner product argument on the Vesta curve, whose scalar
+// field is Fp, and a Blake2b transcript. The parameters need no trusted setup
+// and only depend on k, the circuit having at most 2^k rows.
+// Instances are given per instance column, in order.
+// A Proof bundles the proof bytes with their public inputs in a versioned
+// little-endian encoding, for transport between processes or storage. The
+// halo2 version used cannot serialize verifying keys, which are regenerated
+// from the parameters and the circuit.
+use halo2::{
+    arithmetic::FieldExt,
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use std::io::{self, Read, Write};
+
+// The version of the encoding of proofs.
+const ENCODING_VERSION: u8 = 1;
+
+// The parameters for circuits of at most 2^k rows.
+pub(crate) fn setup(k: u32) -> Params<EqAffine> {
+    Params::new(k)
+}
+
+// The proving key of the circuit `circuit` is an instance of, which may be
+// without witnesses. The verifying key is kept in the proving key, see
+// `ProvingKey::get_vk`.
+pub(crate) fn keygen<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    let vk = keygen_vk(params, circuit)?;
+    keygen_pk(params, vk, circuit)
+}
+
+// A proof that `circuit` is satisfied with the public inputs `instances`.
+pub(crate) fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instances: &[&[Fp]],
+) -> Result<Vec<u8>, Error> {
+    prove_batch(params, pk, vec![circuit], &[instances])
+}
+
+// A single proof that each of `circuits`, all instances of the circuit of
+// `pk`, is satisfied with its public inputs in `instances`. The proof is
+// shorter than separate proofs as the openings are shared.
+pub(crate) fn prove_batch<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: Vec<C>,
+    instances: &[&[&[Fp]]],
+) -> Result<Vec<u8>, Error> {
+    if circuits.len() != instances.len() {
+        return Err(Error::IncompatibleParams);
+    }
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof(params, pk, &circuits, instances, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+// Checks `proof` against the verifying key `vk` and the public inputs
+// `instances`.
+pub(crate) fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instances: &[&[Fp]],
+) -> Result<(), Error> {
+    verify_batch(params, vk, proof, &[instances])
+}
+
+// Checks a proof of `prove_batch` against the public inputs of each circuit,
+// in the order they were proven.
+pub(crate) fn verify_batch(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instances: &[&[&[Fp]]],
+) -> Result<(), Error> {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let guard = verify_proof(params, vk, params.empty_msm(), instances, &mut transcript)?;
+    if guard.use_challenges().eval() {
+        Ok(())
+    } else {
+        Err(Error::ConstraintSystemFailure)
+    }
+}
+
+// A proof along with the public inputs it was made for, per instance column.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Proof {
+    pub(crate) instances: Vec<Vec<Fp>>,
+    pub(crate) bytes: Vec<u8>,
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    if len > u32::MAX as usize {
+        return Err(invalid("length does not fit in 32 bits"));
+    }
+    writer.write_all(&(len as u32).to_le_bytes())
+}
+
+impl Proof {
+    // Checks the proof against the verifying key `vk`.
+    pub(crate) fn verify(
+        &self,
+        params: &Params<EqAffine>,
+        vk: &VerifyingKey<EqAffine>,
+    ) -> Result<(), Error> {
+        let instances: Vec<&[Fp]> = self.instances.iter().map(|c| &c[..]).collect();
+        verify(params, vk, &self.bytes, &instances)
+    }
+
+    // Writes the version byte, the number of instance columns, then each
+    // column as its length followed by its elements in 32 little-endian
+    // bytes, and finally the length of the proof and its bytes. Lengths are
+    // little-endian u32.
+    pub(crate) fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[ENCODING_VERSION])?;
+        write_len(&mut writer, self.instances.len())?;
+        for column in self.instances.iter() {
+            write_len(&mut writer, column.len())?;
+            for a in column.iter() {
+                writer.write_all(&a.to_bytes())?;
+            }
+        }
+        write_len(&mut writer, self.bytes.len())?;
+        writer.write_all(&self.bytes)
+    }
+
+    pub(crate) fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != ENCODING_VERSION {
+            return Err(invalid("unknown encoding version"));
+        }
+        let columns = read_u32(&mut reader)?;
+        let instances = (0..columns)
+            .map(|_| {
+                let len = read_u32(&mut reader)?;
+                (0..len)
+                    .map(|_| {
+                        let mut buf = [0u8; 32];
+                        reader.read_exact(&mut buf)?;
+                        let a = Fp::from_bytes(&buf);
+                        if bool::from(a.is_some()) {
+                            Ok(a.unwrap())
+                        } else {
+                            Err(invalid("non canonical field element"))
+                        }
+                    })
+                    .collect::<io::Result<Vec<Fp>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let len = read_u32(&mut reader)?;
+        let mut bytes = vec![];
+        reader.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len as usize {
+            return Err(invalid("unexpected end of proof"));
+        }
+        Ok(Self { instances, bytes })
+    }
+}
+
+// Proofs are serialized as the bytes of their encoding.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Proof {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = vec![];
+        self.write(&mut bytes).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Proof {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = <Vec<u8> as serde::Deserialize>::deserialize(deserializer)?;
+        Proof::read(&bytes[..]).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{GadgetCircuit, TestableGadget};
+    use crate::vectors::Gadget;
+
+    #[test]
+    fn prove_and_verify() {
+        // the bitwise table needs k = 11
+        let params = setup(11);
+        let inputs = vec![Fp::from(6), Fp::from(7)];
+        let outputs = Gadget::Mul.reference(&inputs);
+        let circuit = GadgetCircuit::new(Gadget::Mul, inputs.iter().map(|v| Some(*v)).collect());
+        let pk = keygen(&params, &circuit).unwrap();
+        let vk = pk.get_vk();
+
+        let proof = prove(&params, &pk, circuit, &[&outputs]).unwrap();
+        assert!(verify(&params, vk, &proof, &[&outputs]).is_ok());
+
+        let wrong = vec![outputs[0] + Fp::from(1)];
+        assert!(verify(&params, vk, &proof, &[&wrong]).is_err());
+        let mut tampered = proof.clone();
+        tampered[0] ^= 1;
+        assert!(verify(&params, vk, &tampered, &[&outputs]).is_err());
+
+        // through the encoding
+        let mut bytes = vec![];
+        Proof {
+            instances: vec![outputs],
+            bytes: proof,
+        }
+        .write(&mut bytes)
+        .unwrap();
+        assert!(Proof::read(&bytes[..]).unwrap().verify(&params, vk).is_ok());
+    }
+
+    #[test]
+    fn batch() {
+        let params = setup(11);
+        let samples = [[Fp::from(2), Fp::from(3)], [Fp::from(4), Fp::from(5)]];
+        let circuits: Vec<_> = samples
+            .iter()
+            .map(|inputs| {
+                GadgetCircuit::new(Gadget::Mul, inputs.iter().map(|v| Some(*v)).collect())
+            })
+            .collect();
+        let outputs: Vec<Vec<Fp>> = samples
+            .iter()
+            .map(|inputs| Gadget::Mul.reference(inputs))
+            .collect();
+        let pk = keygen(&params, &circuits[0]).unwrap();
+        let vk = pk.get_vk();
+
+        let proof = prove_batch(&params, &pk, circuits, &[&[&outputs[0]], &[&outputs[1]]]).unwrap();
+        assert!(verify_batch(&params, vk, &proof, &[&[&outputs[0]], &[&outputs[1]]]).is_ok());
+        // swapped, then with a single circuit
+        assert!(verify_batch(&params, vk, &proof, &[&[&outputs[1]], &[&outputs[0]]]).is_err());
+        assert!(verify(&params, vk, &proof, &[&outputs[0]]).is_err());
+    }
+
+    #[test]
+    fn encoding() {
+        let proof = Proof {
+            instances: vec![vec![Fp::from(42), -Fp::from(1)], vec![]],
+            bytes: vec![1, 2, 3],
+        };
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 1 + 4 + (4 + 2 * 32) + 4 + (4 + 3));
+        assert_eq!(Proof::read(&bytes[..]).unwrap(), proof);
+
+        // truncated, then with another version
+        assert!(Proof::read(&bytes[..bytes.len() - 1]).is_err());
+        let mut other = bytes.clone();
+        other[0] += 1;
+        assert!(Proof::read(&other[..]).is_err());
+        // p - 1 + 1 is not canonical
+        let mut modulus = bytes;
+        let mut carry = true;
+        for b in modulus[9 + 32..9 + 64].iter_mut() {
+            let (sum, c) = b.overflowing_add(carry as u8);
+            *b = sum;
+            carry = c;
+        }
+        assert!(Proof::read(&modulus[..]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let proof = Proof {
+            instances: vec![vec![Fp::from(7)]],
+            bytes: vec![0xff; 10],
+        };
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<Proof>(&json).unwrap(), proof);
+    }
+}
@@ -0,0 +1,394 @@
+// Imports R1CS artifacts produced by circom (the binary .r1cs constraint
+// system and .wtns witness files) and re-proves them with FieldChip.
+// Each constraint <A, w> * <B, w> = <C, w> is synthesized by computing the
+// three linear combinations and constraining their product.
+// The circom wire layout is kept: wire 0 is the constant 1, followed by the
+// public outputs and public inputs, which are read from the instance column
+// in that order, then the private wires. The R1CS must be defined over the
+// circuit field, e.g. compiled with `circom --prime pallas`.
+// Malformed files are rejected with an error: the counts of the headers are
+// checked against each other and against the bytes they describe.
+use super::{modulus_bytes, FieldChip, FieldConfig, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use std::io::{self, Read};
+
+// Terms (wire, coefficient) of a linear combination over the wires.
+type LinearCombination<F> = Vec<(usize, F)>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct R1cs<F: FieldExt> {
+    num_wires: usize,
+    // number of public outputs and inputs, following the constant wire
+    num_public: usize,
+    constraints: Vec<[LinearCombination<F>; 3]>,
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Cursor over the bytes of a circom binary file.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() - self.pos < n {
+            return Err(invalid("unexpected end of file"));
+        }
+        self.pos += n;
+        Ok(&self.bytes[self.pos - n..self.pos])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    // Checks that `n` items of at least `size` bytes each fit in the
+    // remaining bytes, so that a corrupted count cannot allocate more.
+    fn check_count(&self, n: u32, size: usize) -> io::Result<usize> {
+        let n = n as usize;
+        if n > (self.bytes.len() - self.pos) / size {
+            return Err(invalid("count exceeds the remaining bytes"));
+        }
+        Ok(n)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn field<F: FieldExt>(&mut self) -> io::Result<F> {
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(self.take(32)?);
+        let f = F::from_bytes(&buf);
+        if bool::from(f.is_some()) {
+            Ok(f.unwrap())
+        } else {
+            Err(invalid("non canonical field element"))
+        }
+    }
+
+    // Checks the field size and modulus of a header section.
+    fn check_field<F: FieldExt>(&mut self) -> io::Result<()> {
+        if self.u32()? != 32 || self.take(32)? != &modulus_bytes::<F>()[..] {
+            return Err(invalid("field does not match the circuit field"));
+        }
+        Ok(())
+    }
+}
+
+// Reads the header of a circom binary file and returns its sections as
+// (type, content) pairs.
+fn read_sections<'a>(bytes: &'a [u8], magic: &[u8]) -> io::Result<Vec<(u32, Cursor<'a>)>> {
+    let mut cursor = Cursor { bytes, pos: 0 };
+    if cursor.take(4)? != magic {
+        return Err(invalid("bad magic number"));
+    }
+    let _version = cursor.u32()?;
+    // a section is at least its type and size
+    let num_sections = cursor.u32()?;
+    let num_sections = cursor.check_count(num_sections, 12)?;
+    let mut sections = Vec::with_capacity(num_sections);
+    for _ in 0..num_sections {
+        let kind = cursor.u32()?;
+        let size = cursor.u64()? as usize;
+        let content = cursor.take(size)?;
+        sections.push((
+            kind,
+            Cursor {
+                bytes: content,
+                pos: 0,
+            },
+        ));
+    }
+    Ok(sections)
+}
+
+fn section<'a>(sections: &[(u32, Cursor<'a>)], kind: u32) -> io::Result<Cursor<'a>> {
+    sections
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, c)| Cursor {
+            bytes: c.bytes,
+            pos: 0,
+        })
+        .ok_or_else(|| invalid("missing section"))
+}
+
+impl<F: FieldExt> R1cs<F> {
+    // Reads a circom .r1cs file.
+    pub(crate) fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let sections = read_sections(&bytes, b"r1cs")?;
+
+        let mut header = section(&sections, 1)?;
+        header.check_field::<F>()?;
+        let num_wires = header.u32()? as usize;
+        let num_pub_out = header.u32()? as usize;
+        let num_pub_in = header.u32()? as usize;
+        let _num_prv_in = header.u32()?;
+        let _num_labels = header.u64()?;
+        let num_constraints = header.u32()?;
+        if num_wires < 1 + num_pub_out + num_pub_in {
+            return Err(invalid("more public wires than wires"));
+        }
+        // the wire to label map holds a u64 per wire
+        if section(&sections, 3)?.bytes.len() as u64 != 8 * num_wires as u64 {
+            return Err(invalid("wire count does not match the label map"));
+        }
+
+        let mut body = section(&sections, 2)?;
+        // a constraint is at least three term counts, a term a wire and a
+        // field element
+        let num_constraints = body.check_count(num_constraints, 12)?;
+        let mut constraints = Vec::with_capacity(num_constraints);
+        for _ in 0..num_constraints {
+            let mut read_lc = || -> io::Result<LinearCombination<F>> {
+                let num_terms = body.u32()?;
+                let num_terms = body.check_count(num_terms, 36)?;
+                (0..num_terms)
+                    .map(|_| {
+                        let wire = body.u32()? as usize;
+                        if wire >= num_wires {
+                            return Err(invalid("wire out of bounds"));
+                        }
+                        Ok((wire, body.field()?))
+                    })
+                    .collect()
+            };
+            constraints.push([read_lc()?, read_lc()?, read_lc()?]);
+        }
+
+        Ok(R1cs {
+            num_wires,
+            num_public: num_pub_out + num_pub_in,
+            constraints,
+        })
+    }
+}
+
+// Reads the wire values of a circom .wtns file, starting with the constant 1.
+pub(crate) fn read_witness<F: FieldExt, R: Read>(mut reader: R) -> io::Result<Vec<F>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let sections = read_sections(&bytes, b"wtns")?;
+
+    let mut header = section(&sections, 1)?;
+    header.check_field::<F>()?;
+    let num_values = header.u32()?;
+    let mut body = section(&sections, 2)?;
+    let num_values = body.check_count(num_values, 32)?;
+    (0..num_values).map(|_| body.field()).collect()
+}
+
+pub(crate) struct R1csCircuit<F: FieldExt> {
+    r1cs: R1cs<F>,
+    // values of all the wires, as read from a .wtns file
+    witness: Option<Vec<F>>,
+}
+
+impl<F: FieldExt> R1csCircuit<F> {
+    pub(crate) fn new(r1cs: R1cs<F>, witness: Option<Vec<F>>) -> Result<Self, Error> {
+        match &witness {
+            Some(w) if w.len() != r1cs.num_wires || w[0] != F::one() => Err(Error::SynthesisError),
+            _ => Ok(Self { r1cs, witness }),
+        }
+    }
+
+    // The values of the public wires, to be given as the instance column.
+    pub(crate) fn public_inputs(&self) -> Option<Vec<F>> {
+        self.witness
+            .as_ref()
+            .map(|w| w[1..=self.r1cs.num_public].to_vec())
+    }
+
+    fn wire(&self, i: usize) -> Option<F> {
+        self.witness.as_ref().map(|w| w[i])
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for R1csCircuit<F> {
+    type Config = FieldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            r1cs: self.r1cs.clone(),
+            witness: None,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // R1CS has no use for the lookups, keep the bitwise table minimal and
+        // the byte and set tables unloaded
+        FieldChip::configure_default(meta, 1)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FieldChip::<F>::construct(config);
+        chip.load_bitwise_table(layouter.namespace(|| "bitwise table"))?;
+
+        let mut wires = Vec::with_capacity(self.r1cs.num_wires);
+        wires.push(chip.load_constant(layouter.namespace(|| "one"), F::one())?);
+        for i in 1..self.r1cs.num_wires {
+            let wire = if i <= self.r1cs.num_public {
+                chip.load_public(layouter.namespace(|| "public wire"), i - 1)?
+            } else {
+                chip.load_private(layouter.namespace(|| "private wire"), self.wire(i))?
+            };
+            wires.push(wire);
+        }
+
+        let terms = |lc: &LinearCombination<F>| -> Vec<(F, Number<F>)> {
+            lc.iter().map(|(w, c)| (*c, wires[*w].clone())).collect()
+        };
+        for [a, b, c] in self.r1cs.constraints.iter() {
+            let mut layouter = layouter.namespace(|| "constraint");
+            let a = chip.linear_combination(layouter.namespace(|| "A"), &terms(a))?;
+            let b = chip.linear_combination(layouter.namespace(|| "B"), &terms(b))?;
+            let c = chip.linear_combination(layouter.namespace(|| "C"), &terms(c))?;
+            let ab = chip.mul(layouter.namespace(|| "A * B"), a, b)?;
+            chip.assert_equal(layouter.namespace(|| "A * B = C"), ab, c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    // Writes a circom binary file with the given sections.
+    fn write_file(magic: &[u8], sections: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut out = magic.to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+        for (kind, content) in sections.iter() {
+            out.extend_from_slice(&kind.to_le_bytes());
+            out.extend_from_slice(&(content.len() as u64).to_le_bytes());
+            out.extend_from_slice(content);
+        }
+        out
+    }
+
+    fn field_header(out: &mut Vec<u8>) {
+        out.extend_from_slice(&32u32.to_le_bytes());
+        out.extend_from_slice(&modulus_bytes::<Fp>());
+    }
+
+    // out = (x^2 + 5) * x and 2 * x = 6, over the wires [1, out, x, y] where
+    // out is public and y = x^2.
+    fn r1cs_file() -> Vec<u8> {
+        let mut header = vec![];
+        field_header(&mut header);
+        for n in [4u32, 1, 0, 1].iter() {
+            header.extend_from_slice(&n.to_le_bytes());
+        }
+        header.extend_from_slice(&4u64.to_le_bytes());
+        header.extend_from_slice(&3u32.to_le_bytes());
+
+        let lc = |out: &mut Vec<u8>, terms: &[(u32, u64)]| {
+            out.extend_from_slice(&(terms.len() as u32).to_le_bytes());
+            for (wire, coeff) in terms.iter() {
+                out.extend_from_slice(&wire.to_le_bytes());
+                out.extend_from_slice(&Fp::from(*coeff).to_bytes());
+            }
+        };
+        let mut constraints = vec![];
+        // x * x = y
+        lc(&mut constraints, &[(2, 1)]);
+        lc(&mut constraints, &[(2, 1)]);
+        lc(&mut constraints, &[(3, 1)]);
+        // (y + 5) * x = out
+        lc(&mut constraints, &[(3, 1), (0, 5)]);
+        lc(&mut constraints, &[(2, 1)]);
+        lc(&mut constraints, &[(1, 1)]);
+        // 2x * 1 = 6
+        lc(&mut constraints, &[(2, 2)]);
+        lc(&mut constraints, &[(0, 1)]);
+        lc(&mut constraints, &[(0, 6)]);
+
+        let labels = (0..4u64).flat_map(|l| l.to_le_bytes().to_vec()).collect();
+        write_file(b"r1cs", &[(1, header), (2, constraints), (3, labels)])
+    }
+
+    fn wtns_file(values: &[u64]) -> Vec<u8> {
+        let mut header = vec![];
+        field_header(&mut header);
+        header.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        let mut body = vec![];
+        for v in values.iter() {
+            body.extend_from_slice(&Fp::from(*v).to_bytes());
+        }
+        write_file(b"wtns", &[(1, header), (2, body)])
+    }
+
+    #[test]
+    fn modulus() {
+        let p = modulus_bytes::<Fp>();
+        // p is not a canonical encoding but p - 1 is
+        assert!(bool::from(Fp::from_bytes(&p).is_none()));
+        assert_eq!(
+            Fp::from_bytes(&(-Fp::one()).to_bytes()).unwrap(),
+            -Fp::one()
+        );
+    }
+
+    #[test]
+    fn import() {
+        let r1cs = R1cs::<Fp>::read(&r1cs_file()[..]).unwrap();
+        let witness = read_witness::<Fp, _>(&wtns_file(&[1, 42, 3, 9])[..]).unwrap();
+        let circuit = R1csCircuit::new(r1cs.clone(), Some(witness)).unwrap();
+        let public_inputs = circuit.public_inputs().unwrap();
+        assert_eq!(public_inputs, vec![Fp::from(42)]);
+        let k = 7;
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(43)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // y is not x^2
+        let witness = read_witness::<Fp, _>(&wtns_file(&[1, 42, 3, 10])[..]).unwrap();
+        let circuit = R1csCircuit::new(r1cs, Some(witness)).unwrap();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(42)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let mut file = r1cs_file();
+        file[0] = b'x';
+        assert!(R1cs::<Fp>::read(&file[..]).is_err());
+        let file = r1cs_file();
+        assert!(R1cs::<Fp>::read(&file[..file.len() - 1]).is_err());
+        // more public outputs than wires, then more constraints than bytes
+        let mut file = r1cs_file();
+        file[64] = 4;
+        assert!(R1cs::<Fp>::read(&file[..]).is_err());
+        let mut file = r1cs_file();
+        file[87] = 0xff;
+        assert!(R1cs::<Fp>::read(&file[..]).is_err());
+        // wrong number of wires in the witness
+        let r1cs = R1cs::<Fp>::read(&r1cs_file()[..]).unwrap();
+        let witness = read_witness::<Fp, _>(&wtns_file(&[1, 42, 3])[..]).unwrap();
+        assert!(R1csCircuit::new(r1cs, Some(witness)).is_err());
+    }
+}
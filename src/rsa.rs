@@ -0,0 +1,293 @@
+// RSA signature verification with PKCS#1 v1.5 padding for SHA-256 digests,
+// as used by DKIM or passports. A signature s on a message under the public
+// key (n, e) is valid when s < n and s^e mod n is the encoded message
+// 0x00 0x01 0xff .. 0xff 0x00 DigestInfo H, with H the SHA-256 digest of the
+// message. The crate has no SHA-256 chip, so `verify_rsa_digest` takes the
+// digest as bytes and hashing the message is left to the caller.
+// The modulus fills its limbs, whose width must be a multiple of 8 bits.
+use super::{
+    bigint::{BigInt, BigIntChip, BigIntInstructions},
+    bytes::Byte,
+    pow2, Bit, FieldChip, FieldConfig, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+// The DER encoding of the SHA-256 AlgorithmIdentifier, prefixed to the digest.
+const SHA256_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+
+const DIGEST_LEN: usize = 32;
+
+// The minimal length of the 0xff padding string.
+const MIN_PADDING: usize = 8;
+
+pub(crate) trait RsaInstructions<F: FieldExt> {
+    /// Returns 1 if `sig` is a valid signature on the message of SHA-256
+    /// digest `digest`, given by its big-endian bytes, under the public key
+    /// (`n`, `e`) and 0 otherwise. `sig` must be less than `n`.
+    fn verify_rsa_digest(
+        &self,
+        layouter: impl Layouter<F>,
+        n: &BigInt<F>,
+        e: u64,
+        sig: &BigInt<F>,
+        digest: &[Byte<F>],
+    ) -> Result<Bit<F>, Error>;
+}
+
+pub(crate) struct RsaChip<F: FieldExt> {
+    field: FieldChip<F>,
+    bigint: BigIntChip<F>,
+    limb_bits: usize,
+}
+
+impl<F: FieldExt> RsaChip<F> {
+    // Fails if the limbs are not made of whole bytes.
+    pub(crate) fn construct(config: FieldConfig, limb_bits: usize) -> Result<Self, Error> {
+        if limb_bits % 8 != 0 {
+            return Err(Error::SynthesisError);
+        }
+        Ok(Self {
+            field: FieldChip::construct(config.clone()),
+            bigint: BigIntChip::construct(config, limb_bits)?,
+            limb_bits,
+        })
+    }
+
+    // Returns the `limbs` little-endian limbs of the message encoding
+    // `digest`.
+    fn assign_encoding(
+        &self,
+        mut layouter: impl Layouter<F>,
+        digest: &[Byte<F>],
+        limbs: usize,
+    ) -> Result<Vec<Number<F>>, Error> {
+        let bytes_per_limb = self.limb_bits / 8;
+        let k = limbs * bytes_per_limb;
+        let padding = k
+            .checked_sub(3 + SHA256_DIGEST_INFO.len() + DIGEST_LEN)
+            .filter(|padding| *padding >= MIN_PADDING)
+            .ok_or(Error::SynthesisError)?;
+        if digest.len() != DIGEST_LEN {
+            return Err(Error::SynthesisError);
+        }
+        // the constant bytes of the encoding, by big-endian position
+        let constant = |p: usize| match p {
+            1 => 1,
+            p if (2..2 + padding).contains(&p) => 0xff,
+            p if p >= 3 + padding => SHA256_DIGEST_INFO[p - 3 - padding],
+            _ => 0,
+        };
+
+        (0..limbs)
+            .map(|j| {
+                let mut terms = vec![];
+                let mut c = 0u64;
+                for i in 0..bytes_per_limb {
+                    // the little-endian byte j * bytes_per_limb + i
+                    let p = k - 1 - (j * bytes_per_limb + i);
+                    if p >= k - DIGEST_LEN {
+                        terms.push((pow2(8 * i), digest[p + DIGEST_LEN - k].num()));
+                    } else {
+                        c |= (constant(p) as u64) << (8 * i);
+                    }
+                }
+                if terms.is_empty() {
+                    self.field
+                        .load_constant(layouter.namespace(|| "constant limb"), F::from(c))
+                } else {
+                    let limb = self
+                        .field
+                        .linear_combination(layouter.namespace(|| "digest limb"), &terms)?;
+                    self.field
+                        .add_const(layouter.namespace(|| "padding"), limb, F::from(c))
+                }
+            })
+            .collect()
+    }
+}
+
+impl<F: FieldExt> RsaInstructions<F> for RsaChip<F> {
+    fn verify_rsa_digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: &BigInt<F>,
+        e: u64,
+        sig: &BigInt<F>,
+        digest: &[Byte<F>],
+    ) -> Result<Bit<F>, Error> {
+        let encoding =
+            self.assign_encoding(layouter.namespace(|| "encoding"), digest, n.limbs().len())?;
+        self.bigint
+            .assert_lt(layouter.namespace(|| "sig < n"), sig, n)?;
+        let m = self
+            .bigint
+            .mod_exp(layouter.namespace(|| "sig^e mod n"), sig, e, n)?;
+
+        let mut valid = self
+            .field
+            .load_constant(layouter.namespace(|| "one"), F::one())?;
+        for (a, b) in m.limbs().iter().zip(encoding.into_iter()) {
+            let same = self
+                .field
+                .is_equal(layouter.namespace(|| "limb"), a.clone(), b)?;
+            valid = self
+                .field
+                .mul(layouter.namespace(|| "valid"), valid, same.num())?;
+        }
+        Ok(Bit(valid))
+    }
+}
+
+// A sample key for the tests and the test vectors: a 512-bit modulus on 8
+// limbs of 64 bits with public exponent 3, and the signature of the SHA-256
+// digest of "abc" under it.
+pub(crate) const SAMPLE_LIMB_BITS: usize = 64;
+pub(crate) const SAMPLE_LIMBS: usize = 8;
+pub(crate) const SAMPLE_E: u64 = 3;
+pub(crate) const SAMPLE_N: [u64; SAMPLE_LIMBS] = [
+    0x074a_253e_cc45_a0fd,
+    0x856d_379f_907c_a603,
+    0xaaa5_578e_49e9_4be4,
+    0x9797_2289_91ca_bf2e,
+    0x9e87_ad2e_e7cd_a5d0,
+    0x6916_e853_cfc1_5837,
+    0xf97d_7e15_acba_c18a,
+    0x9b06_1b22_069f_8084,
+];
+pub(crate) const SAMPLE_SIG: [u64; SAMPLE_LIMBS] = [
+    0xcc9b_9a31_df13_3c58,
+    0x3de4_3d8f_f0ed_02be,
+    0x6403_9df8_e109_e02a,
+    0xa2c3_3e23_e0a9_9320,
+    0xced4_e968_f333_1418,
+    0x8ed9_8f43_ddb5_f686,
+    0x9d1b_9142_814d_9d3d,
+    0x0b60_a633_84af_9772,
+];
+pub(crate) const SAMPLE_DIGEST: [u8; DIGEST_LEN] = [
+    0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+    0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+];
+
+// The `limbs` limbs of `limb_bits` bits of the encoding of `digest`,
+// computed natively.
+pub(crate) fn encoding_native(digest: &[u8], limbs: usize, limb_bits: usize) -> Vec<u64> {
+    let k = limbs * limb_bits / 8;
+    let mut em = vec![0, 1];
+    em.resize(k - 1 - SHA256_DIGEST_INFO.len() - DIGEST_LEN, 0xff);
+    em.push(0);
+    em.extend_from_slice(&SHA256_DIGEST_INFO);
+    em.extend_from_slice(digest);
+    em.reverse();
+    em.chunks(limb_bits / 8)
+        .map(|limb| {
+            limb.iter()
+                .rev()
+                .fold(0, |acc, byte| (acc << 8) | *byte as u64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::mod_exp_native;
+    use crate::bytes::ByteInstructions;
+    use crate::harness::{check, TestableGadget};
+    use crate::integer::lower_64;
+    use halo2::{circuit::Chip, pasta::Fp};
+
+    fn sample(n: &[u64], sig: &[u64], digest: &[u8]) -> Vec<Fp> {
+        n.iter()
+            .chain(sig.iter())
+            .map(|limb| Fp::from(*limb))
+            .chain(digest.iter().map(|byte| Fp::from(*byte as u64)))
+            .collect()
+    }
+
+    // [n, sig, digest] -> [validity]
+    #[derive(Clone, Debug)]
+    struct Verify;
+
+    impl TestableGadget<Fp> for Verify {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            let mut other = SAMPLE_DIGEST;
+            other[0] ^= 1;
+            let mut forged = SAMPLE_SIG;
+            forged[0] += 1;
+            vec![
+                sample(&SAMPLE_N, &SAMPLE_SIG, &SAMPLE_DIGEST),
+                sample(&SAMPLE_N, &SAMPLE_SIG, &other),
+                sample(&SAMPLE_N, &forged, &SAMPLE_DIGEST),
+            ]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            // the signature plus the modulus, of the same value modulo n
+            let mut wrapped = SAMPLE_SIG.to_vec();
+            let mut carry = 0;
+            for (s, n) in wrapped.iter_mut().zip(SAMPLE_N.iter()) {
+                let (sum, c1) = s.overflowing_add(*n);
+                let (sum, c2) = sum.overflowing_add(carry);
+                *s = sum;
+                carry = (c1 || c2) as u64;
+            }
+            assert_eq!(carry, 0);
+            vec![sample(&SAMPLE_N, &wrapped, &SAMPLE_DIGEST)]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let limbs: Vec<u64> = inputs[..2 * SAMPLE_LIMBS].iter().map(lower_64).collect();
+            let digest: Vec<u8> = inputs[2 * SAMPLE_LIMBS..]
+                .iter()
+                .map(|byte| lower_64(byte) as u8)
+                .collect();
+            let m = mod_exp_native(
+                &limbs[SAMPLE_LIMBS..],
+                SAMPLE_E,
+                &limbs[..SAMPLE_LIMBS],
+                SAMPLE_LIMB_BITS,
+            );
+            vec![Fp::from(
+                m == Some(encoding_native(&digest, SAMPLE_LIMBS, SAMPLE_LIMB_BITS)),
+            )]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let rsa = RsaChip::construct(chip.config().clone(), SAMPLE_LIMB_BITS)?;
+            let values: Vec<Option<u64>> = inputs[..2 * SAMPLE_LIMBS]
+                .iter()
+                .map(|a| a.value.map(|v| lower_64(&v)))
+                .collect();
+            let n = rsa
+                .bigint
+                .load(layouter.namespace(|| "n"), &values[..SAMPLE_LIMBS])?;
+            let sig = rsa
+                .bigint
+                .load(layouter.namespace(|| "sig"), &values[SAMPLE_LIMBS..])?;
+            for (limb, input) in n.limbs().iter().chain(sig.limbs()).zip(inputs.iter()) {
+                chip.assert_equal(layouter.namespace(|| "input"), limb.clone(), input.clone())?;
+            }
+            let digest = inputs[2 * SAMPLE_LIMBS..]
+                .iter()
+                .map(|a| chip.byte(layouter.namespace(|| "digest"), a.clone()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let valid = rsa.verify_rsa_digest(layouter, &n, SAMPLE_E, &sig, &digest)?;
+            Ok(vec![valid.num()])
+        }
+    }
+
+    #[test]
+    fn verify() {
+        // three reductions of 512-bit numbers on 64-bit limbs
+        check(&Verify, 16);
+    }
+}
@@ -0,0 +1,255 @@
+// Schnorr signatures over the curve of ecc.rs with the base point G of the
+// smallest x coordinate. A signature (R, s) on the message hash m under the
+// public key pk is valid when s * G = R + e * pk, the challenge e being the
+// low 128 bits of the transcript squeezed after absorbing R, pk and m.
+// Scalars live in the scalar field of the curve rather than the circuit field,
+// so s is given by its bits and constrained to be less than the order q of the
+// curve: s + q would otherwise be another valid signature. The challenge is
+// decomposed on F::CAPACITY bits so that its bits are unique, and truncated to
+// 128 bits for half the cost of a full scalar multiplication.
+use super::{
+    ecc::{
+        add_complete_native, find_point, order_bytes, scalar_mul_native, Affine, EccInstructions,
+        Point,
+    },
+    from_le_bits, to_le_bits,
+    transcript::{challenges_native, TranscriptInstructions},
+    Bit, BitOrder, FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+// The bit length of the challenge.
+pub(crate) const CHALLENGE_BITS: usize = 128;
+
+#[derive(Clone)]
+pub(crate) struct Signature<F: FieldExt> {
+    pub(crate) r: Point<F>,
+    // little-endian bits of s, at most 256
+    pub(crate) s: Vec<Bit<F>>,
+}
+
+// The bits of the challenge for (R, pk, m), computed natively.
+fn challenge_native<F: FieldExt>(r: Affine<F>, pk: Affine<F>, m: F) -> Vec<bool> {
+    let e = challenges_native(&[r.0, r.1, pk.0, pk.1, m], 1)[0];
+    to_le_bits(&e, CHALLENGE_BITS)
+}
+
+// The 255 little-endian bits of q, the order of the curve.
+pub(crate) fn order_bits<F: FieldExt>() -> Vec<bool> {
+    let q = order_bytes::<F>();
+    (0..255).map(|i| (q[i / 8] >> (i % 8)) & 1 == 1).collect()
+}
+
+// (pk, R, 255 bits of s) for the signature of `m` under the secret key `x`
+// with the nonce `k`, computed natively. s = k + e * x has at most 193 bits,
+// so it is the same in F as modulo q.
+pub(crate) fn sign_native<F: FieldExt>(x: u64, k: u64, m: F) -> (Affine<F>, Affine<F>, Vec<bool>) {
+    let (_, g) = find_point::<F>(1);
+    let pk = scalar_mul_native(g, &to_le_bits(&F::from(x), 64));
+    let r = scalar_mul_native(g, &to_le_bits(&F::from(k), 64));
+    let e = from_le_bits::<F>(&challenge_native(r, pk, m));
+    let s = F::from(k) + e * F::from(x);
+    (pk, r, to_le_bits(&s, 255))
+}
+
+// Whether (R, s) is a valid signature on `m` under `pk`, computed natively.
+pub(crate) fn verify_native<F: FieldExt>(pk: Affine<F>, m: F, r: Affine<F>, s: &[bool]) -> bool {
+    let (_, g) = find_point::<F>(1);
+    let e = challenge_native(r, pk, m);
+    let rhs = add_complete_native(r, scalar_mul_native(pk, &e));
+    // s < q, comparing from the most significant bit
+    let canonical = s
+        .iter()
+        .zip(order_bits::<F>())
+        .rev()
+        .find(|(s, q)| **s != *q)
+        .map_or(false, |(s, _)| !*s);
+    scalar_mul_native(g, s) == rhs && canonical
+}
+
+pub(crate) trait SchnorrInstructions<F: FieldExt> {
+    /// Returns 1 if `sig` is a valid signature on `msg_hash` under `pk` and
+    /// 0 otherwise, a signature whose s is not reduced modulo the order of
+    /// the curve being invalid. `pk` and the nonce point of `sig` must be on
+    /// the curve.
+    fn verify_schnorr(
+        &self,
+        layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        msg_hash: Number<F>,
+        sig: &Signature<F>,
+    ) -> Result<Bit<F>, Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Returns the low CHALLENGE_BITS bits of the challenge for (R, pk, m).
+    fn assign_challenge(
+        &self,
+        mut layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        msg_hash: Number<F>,
+        r: &Point<F>,
+    ) -> Result<Vec<Bit<F>>, Error> {
+        let mut transcript = self.init_transcript(layouter.namespace(|| "init"))?;
+        for a in [
+            r.x.clone(),
+            r.y.clone(),
+            pk.x.clone(),
+            pk.y.clone(),
+            msg_hash,
+        ]
+        .iter()
+        {
+            self.absorb(layouter.namespace(|| "absorb"), &mut transcript, a.clone())?;
+        }
+        let e = self.squeeze(layouter.namespace(|| "e"), &mut transcript)?;
+        let mut bits = self.unpack(
+            layouter.namespace(|| "bits of e"),
+            e,
+            F::CAPACITY as usize,
+            BitOrder::LittleEndian,
+        )?;
+        bits.truncate(CHALLENGE_BITS);
+        Ok(bits)
+    }
+
+    // Returns 1 if the little-endian bits `s` are less than the order of the
+    // curve, comparing their bytes with the bytes of the order.
+    fn assign_canonical(
+        &self,
+        mut layouter: impl Layouter<F>,
+        s: &[Bit<F>],
+    ) -> Result<Bit<F>, Error> {
+        let order = order_bytes::<F>();
+        if s.len() > 8 * order.len() {
+            return Err(Error::SynthesisError);
+        }
+        let zero = self.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        let mut s_bytes = s
+            .chunks(8)
+            .map(|bits| {
+                self.pack_bits(
+                    layouter.namespace(|| "byte of s"),
+                    bits,
+                    BitOrder::LittleEndian,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        s_bytes.resize(order.len(), zero);
+        let q_bytes = order
+            .iter()
+            .map(|b| self.load_constant(layouter.namespace(|| "byte of q"), F::from(*b as u64)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        // big-endian for the lexicographic order
+        s_bytes.reverse();
+        let q_bytes: Vec<_> = q_bytes.into_iter().rev().collect();
+        self.lex_lt(layouter, &s_bytes, &q_bytes)
+    }
+}
+
+impl<F: FieldExt> SchnorrInstructions<F> for FieldChip<F> {
+    fn verify_schnorr(
+        &self,
+        mut layouter: impl Layouter<F>,
+        pk: &Point<F>,
+        msg_hash: Number<F>,
+        sig: &Signature<F>,
+    ) -> Result<Bit<F>, Error> {
+        let canonical = self.assign_canonical(layouter.namespace(|| "s < q"), &sig.s)?;
+        let e = self.assign_challenge(layouter.namespace(|| "challenge"), pk, msg_hash, &sig.r)?;
+        let (_, g) = find_point(1);
+        let g = self.constant_point(layouter.namespace(|| "G"), g)?;
+        let lhs = self.scalar_mul(layouter.namespace(|| "s * G"), &g, &sig.s)?;
+        let epk = self.scalar_mul(layouter.namespace(|| "e * pk"), pk, &e)?;
+        let rhs = self.add_complete(layouter.namespace(|| "R + e * pk"), &sig.r, &epk)?;
+
+        let same_x = self.is_equal(layouter.namespace(|| "x"), lhs.x, rhs.x)?;
+        let same_y = self.is_equal(layouter.namespace(|| "y"), lhs.y, rhs.y)?;
+        let same = self.mul(
+            layouter.namespace(|| "same point"),
+            same_x.num(),
+            same_y.num(),
+        )?;
+        Ok(Bit(self.mul(
+            layouter.namespace(|| "valid"),
+            same,
+            canonical.num(),
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use halo2::{arithmetic::Field, pasta::Fp};
+
+    // [pk, m, R, 255 bits of s] of the signature of `m` under the secret key
+    // `x` with the nonce `k`.
+    fn sign(x: u64, k: u64, m: Fp) -> Vec<Fp> {
+        let (pk, r, s) = sign_native(x, k, m);
+        let mut inputs = vec![pk.0, pk.1, m, r.0, r.1];
+        inputs.extend(s.into_iter().map(Fp::from));
+        inputs
+    }
+
+    // [pk, m, R, bits of s] -> [validity]
+    #[derive(Clone, Debug)]
+    struct Verify;
+
+    impl TestableGadget<Fp> for Verify {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            let valid = sign(7, 11, Fp::from(42));
+            // the same signature on another message
+            let mut forged = valid.clone();
+            forged[2] = Fp::from(43);
+            // s + q, which passes the curve equation
+            let mut malleated = valid.clone();
+            let mut carry = false;
+            for (bit, q) in malleated[5..].iter_mut().zip(order_bits::<Fp>()) {
+                let sum = (*bit == Fp::one()) as u8 + q as u8 + carry as u8;
+                *bit = Fp::from((sum & 1) as u64);
+                carry = sum > 1;
+            }
+            assert!(!carry);
+            vec![valid, forged, malleated]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            let s: Vec<bool> = inputs[5..].iter().map(|b| *b == Fp::one()).collect();
+            let valid = verify_native(
+                (inputs[0], inputs[1]),
+                inputs[2],
+                (inputs[3], inputs[4]),
+                &s,
+            );
+            vec![Fp::from(valid)]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let point = |i: usize| Point {
+                x: inputs[i].clone(),
+                y: inputs[i + 1].clone(),
+            };
+            let s = inputs[5..]
+                .iter()
+                .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let sig = Signature { r: point(3), s };
+            let valid = chip.verify_schnorr(layouter, &point(0), inputs[2].clone(), &sig)?;
+            Ok(vec![valid.num()])
+        }
+    }
+
+    #[test]
+    fn verify() {
+        // two scalar multiplications, of 255 and 128 bits, take about 2^15
+        // rows
+        check(&Verify, 16);
+    }
+}
@@ -0,0 +1,238 @@
+// Membership in a small public set, cheaper than a Merkle proof. The set is
+// loaded once in the set table, along with the gaps between its sorted
+// elements: membership is a lookup of the element, and non-membership a
+// lookup of the gap [lo, hi] holding the value, with lo <= a <= hi.
+// The elements are integers of a given bit length so that they can be sorted.
+use super::{integer::lower_64, FieldChip, Number, NumericInstructions};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::Error,
+};
+
+// The sorted elements of a loaded set, all fitting in `bits` bits.
+#[derive(Clone, Debug)]
+pub(crate) struct SetTable {
+    elements: Vec<u64>,
+    bits: usize,
+}
+
+impl SetTable {
+    // The maximal ranges of `bits`-bit integers outside of the set.
+    fn gaps(&self) -> Vec<(u64, u64)> {
+        let mut gaps = vec![];
+        let mut lo = 0;
+        for s in self.elements.iter() {
+            if *s > lo {
+                gaps.push((lo, s - 1));
+            }
+            lo = s + 1;
+        }
+        let max = (1 << self.bits) - 1;
+        if lo <= max {
+            gaps.push((lo, max));
+        }
+        gaps
+    }
+}
+
+pub(crate) trait SetInstructions<F: FieldExt> {
+    /// Loads `set` in the set table. Its elements must fit in `bits` bits,
+    /// `bits` being less than 64. The table can only be loaded once.
+    fn load_set_table(
+        &self,
+        layouter: impl Layouter<F>,
+        set: &[F],
+        bits: usize,
+    ) -> Result<SetTable, Error>;
+
+    /// Constrains `a` to be an element of the loaded set.
+    fn assert_member(&self, layouter: impl Layouter<F>, a: Number<F>) -> Result<(), Error>;
+
+    /// Constrains `a` not to be an element of `set`, the loaded set. `a`
+    /// must already be known to fit in the bit length of the set.
+    fn assert_not_member(
+        &self,
+        layouter: impl Layouter<F>,
+        set: &SetTable,
+        a: Number<F>,
+    ) -> Result<(), Error>;
+}
+
+impl<F: FieldExt> SetInstructions<F> for FieldChip<F> {
+    fn load_set_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        set: &[F],
+        bits: usize,
+    ) -> Result<SetTable, Error> {
+        if bits >= 64 {
+            return Err(Error::SynthesisError);
+        }
+        let mut elements = set
+            .iter()
+            .map(|s| {
+                let int = lower_64(s);
+                if F::from(int) == *s && int >> bits == 0 {
+                    Ok(int)
+                } else {
+                    Err(Error::SynthesisError)
+                }
+            })
+            .collect::<Result<Vec<u64>, Error>>()?;
+        elements.sort_unstable();
+        elements.dedup();
+        let table = SetTable { elements, bits };
+
+        // the first row is the (0, 0, 0) looked up by the rows with the set
+        // selectors off: the unassigned rows of a table repeat its first row
+        let config = self.config();
+        let mut rows = vec![(0, 0, 0)];
+        rows.extend(table.elements.iter().map(|s| (1, *s, 0)));
+        rows.extend(table.gaps().iter().map(|(lo, hi)| (2, *lo, *hi)));
+        layouter.assign_table(
+            || "set table",
+            |mut t| {
+                for (row, (tag, lo, hi)) in rows.iter().enumerate() {
+                    t.assign_cell(|| "tag", config.set_table[0], row, || Ok(F::from(*tag)))?;
+                    t.assign_cell(|| "lo", config.set_table[1], row, || Ok(F::from(*lo)))?;
+                    t.assign_cell(|| "hi", config.set_table[2], row, || Ok(F::from(*hi)))?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(table)
+    }
+
+    fn assert_member(&self, mut layouter: impl Layouter<F>, a: Number<F>) -> Result<(), Error> {
+        let config = self.config();
+        layouter.assign_region(
+            || "member",
+            |mut region| {
+                config.smember.enable(&mut region, 0)?;
+                let cell = region.assign_advice(
+                    || "a",
+                    config.advices[0],
+                    0,
+                    || a.value.ok_or(Error::SynthesisError),
+                )?;
+                region.constrain_equal(a.cell, cell)
+            },
+        )
+    }
+
+    fn assert_not_member(
+        &self,
+        mut layouter: impl Layouter<F>,
+        set: &SetTable,
+        a: Number<F>,
+    ) -> Result<(), Error> {
+        // the gap holding a, None while the witness is unknown
+        let gap = match a.value {
+            Some(value) => {
+                let int = lower_64(&value);
+                let gap = set
+                    .gaps()
+                    .into_iter()
+                    .find(|(lo, hi)| F::from(int) == value && *lo <= int && int <= *hi)
+                    .ok_or(Error::SynthesisError)?;
+                Some(gap)
+            }
+            None => None,
+        };
+
+        let config = self.config();
+        let mut bounds = None;
+        layouter.assign_region(
+            || "gap",
+            |mut region| {
+                config.sgap.enable(&mut region, 0)?;
+                let lo = gap.map(|(lo, _)| F::from(lo));
+                let hi = gap.map(|(_, hi)| F::from(hi));
+                let lo_cell = region.assign_advice(
+                    || "lo",
+                    config.advices[0],
+                    0,
+                    || lo.ok_or(Error::SynthesisError),
+                )?;
+                let hi_cell = region.assign_advice(
+                    || "hi",
+                    config.advices[1],
+                    0,
+                    || hi.ok_or(Error::SynthesisError),
+                )?;
+                bounds = Some((
+                    Number {
+                        cell: lo_cell,
+                        value: lo,
+                    },
+                    Number {
+                        cell: hi_cell,
+                        value: hi,
+                    },
+                ));
+                Ok(())
+            },
+        )?;
+        let (lo, hi) = bounds.unwrap();
+
+        // neither a < lo nor hi < a
+        let zero = self.load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        let below = self.lt(layouter.namespace(|| "a < lo"), a.clone(), lo, set.bits)?;
+        self.assert_equal(layouter.namespace(|| "a >= lo"), below.num(), zero.clone())?;
+        let above = self.lt(layouter.namespace(|| "hi < a"), hi, a, set.bits)?;
+        self.assert_equal(layouter.namespace(|| "a <= hi"), above.num(), zero)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{check, TestableGadget};
+    use halo2::pasta::Fp;
+
+    // [a, b] -> [a * b], the product being constrained to be in the set
+    #[derive(Clone, Debug)]
+    struct MemberProduct;
+
+    const SET: [u64; 3] = [6, 12, 42];
+
+    impl TestableGadget<Fp> for MemberProduct {
+        fn samples(&self) -> Vec<Vec<Fp>> {
+            vec![
+                vec![Fp::from(2), Fp::from(3)],
+                vec![Fp::from(6), Fp::from(7)],
+            ]
+        }
+
+        fn invalid_samples(&self) -> Vec<Vec<Fp>> {
+            vec![vec![Fp::from(5), Fp::from(5)]]
+        }
+
+        fn reference(&self, inputs: &[Fp]) -> Vec<Fp> {
+            vec![inputs[0] * inputs[1]]
+        }
+
+        fn synthesize(
+            &self,
+            chip: &FieldChip<Fp>,
+            mut layouter: impl Layouter<Fp>,
+            inputs: &[Number<Fp>],
+        ) -> Result<Vec<Number<Fp>>, Error> {
+            let set: Vec<Fp> = SET.iter().map(|s| Fp::from(*s)).collect();
+            chip.load_set_table(layouter.namespace(|| "set"), &set, 8)?;
+            let product = chip.mul(
+                layouter.namespace(|| "a * b"),
+                inputs[0].clone(),
+                inputs[1].clone(),
+            )?;
+            chip.assert_member(layouter.namespace(|| "member"), product.clone())?;
+            Ok(vec![product])
+        }
+    }
+
+    #[test]
+    fn member_with_other_gates() {
+        check(&MemberProduct, 11);
+    }
+}
@@ -0,0 +1,102 @@
+// An in-circuit Fiat–Shamir transcript, for verifiers laid out in a circuit.
+// The transcript is a duplex sponge over the MiMC Feistel permutation of
+// mimc.rs, the crate having no Poseidon chip: a Number is absorbed by adding
+// it to the left half of the state and permuting, and a challenge is squeezed
+// by reading the left half and permuting, so that every challenge depends on
+// everything absorbed before it and two challenges in a row differ.
+use super::{
+    mimc::{feistel_native, MimcInstructions},
+    FieldChip, Number, NumericInstructions,
+};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+// The state of a transcript, starting at (0, 0).
+#[derive(Clone)]
+pub(crate) struct Transcript<F: FieldExt> {
+    l: Number<F>,
+    r: Number<F>,
+}
+
+pub(crate) trait TranscriptInstructions<F: FieldExt> {
+    /// Returns an empty transcript.
+    fn init_transcript(&self, layouter: impl Layouter<F>) -> Result<Transcript<F>, Error>;
+
+    /// Absorbs `a` in `transcript`.
+    fn absorb(
+        &self,
+        layouter: impl Layouter<F>,
+        transcript: &mut Transcript<F>,
+        a: Number<F>,
+    ) -> Result<(), Error>;
+
+    /// Returns a challenge depending on everything absorbed so far.
+    fn squeeze(
+        &self,
+        layouter: impl Layouter<F>,
+        transcript: &mut Transcript<F>,
+    ) -> Result<Number<F>, Error>;
+}
+
+impl<F: FieldExt> FieldChip<F> {
+    // Permutes the state of `transcript` with `l` in its left half.
+    fn assign_transcript_permutation(
+        &self,
+        layouter: impl Layouter<F>,
+        transcript: &mut Transcript<F>,
+        l: Number<F>,
+    ) -> Result<(), Error> {
+        let (l, r) = self.feistel(layouter, l, transcript.r.clone())?;
+        transcript.l = l;
+        transcript.r = r;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> TranscriptInstructions<F> for FieldChip<F> {
+    fn init_transcript(&self, mut layouter: impl Layouter<F>) -> Result<Transcript<F>, Error> {
+        Ok(Transcript {
+            l: self.load_constant(layouter.namespace(|| "l"), F::zero())?,
+            r: self.load_constant(layouter.namespace(|| "r"), F::zero())?,
+        })
+    }
+
+    fn absorb(
+        &self,
+        mut layouter: impl Layouter<F>,
+        transcript: &mut Transcript<F>,
+        a: Number<F>,
+    ) -> Result<(), Error> {
+        let l = self.add(layouter.namespace(|| "l + a"), transcript.l.clone(), a)?;
+        self.assign_transcript_permutation(layouter.namespace(|| "permute"), transcript, l)
+    }
+
+    fn squeeze(
+        &self,
+        mut layouter: impl Layouter<F>,
+        transcript: &mut Transcript<F>,
+    ) -> Result<Number<F>, Error> {
+        let challenge = transcript.l.clone();
+        self.assign_transcript_permutation(
+            layouter.namespace(|| "permute"),
+            transcript,
+            challenge.clone(),
+        )?;
+        Ok(challenge)
+    }
+}
+
+// The `squeezes` challenges following the absorption of `inputs`, computed
+// natively.
+pub(crate) fn challenges_native<F: FieldExt>(inputs: &[F], squeezes: usize) -> Vec<F> {
+    let mut state = (F::zero(), F::zero());
+    for input in inputs.iter() {
+        state = feistel_native(state.0 + *input, state.1);
+    }
+    (0..squeezes)
+        .map(|_| {
+            let challenge = state.0;
+            state = feistel_native(state.0, state.1);
+            challenge
+        })
+        .collect()
+}
@@ -0,0 +1,1390 @@
+// Test vectors for every FieldChip instruction, so other implementations can
+// be checked against this crate. Each vector gives the instruction with its
+// parameters, the inputs and the expected outputs as computed natively; the
+// tests check them against the circuit with the TestableGadget harness, the
+// instructions needing 2^16 rows or more only on demand:
+//   cargo test large_gadgets -- --ignored
+// Field elements are serialized as big-endian hex strings.
+// To write the vectors as JSON:
+//   TEST_VECTORS=vectors.json cargo test export_vectors -- --ignored
+use super::{
+    barycentric_weights,
+    bigint::{mod_exp_native, BigIntChip, BigIntInstructions},
+    bytes::ByteInstructions,
+    dynamic::DynamicLookupInstructions,
+    ecc::{
+        add_complete_native, double_native, find_point, scalar_mul_native, Affine, EccInstructions,
+        Point,
+    },
+    elgamal::{encrypt_native, ElGamalInstructions},
+    from_i64,
+    harness::TestableGadget,
+    integer::{lower_64, U64Instructions},
+    keccak::{keccak256_native, KeccakInstructions},
+    merkle::{root_from_path_native, root_native, MerkleInstructions, MerklePath},
+    merkle_damgard::{hash_native, MerkleDamgard},
+    mimc::{compress_native, feistel_hash_native, permute_native, MimcInstructions},
+    note::{note_native, NoteInstructions},
+    pedersen::{commit_native, CommitmentInstructions},
+    root_of_unity,
+    rsa::{
+        encoding_native, RsaChip, RsaInstructions, SAMPLE_DIGEST, SAMPLE_E, SAMPLE_LIMBS,
+        SAMPLE_LIMB_BITS, SAMPLE_N, SAMPLE_SIG,
+    },
+    schnorr::{order_bits, sign_native, verify_native, SchnorrInstructions, Signature},
+    set::SetInstructions,
+    to_le_bits,
+    transcript::{challenges_native, TranscriptInstructions},
+    word32::Word32Instructions,
+    BitOrder, FieldChip, GenericCoeffs, Number, NumericInstructions,
+};
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::Error,
+};
+
+// An instruction and its parameters, along with how its inputs are laid out.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "gadget", rename_all = "snake_case")
+)]
+pub(crate) enum Gadget {
+    // [a, b] -> [a + b]
+    Add,
+    // [a, b] -> [a * b]
+    Mul,
+    // [a, b] -> [a ^ b]
+    Xor,
+    // [a] -> []
+    RangeCheck { num_bits: usize },
+    // [a, b] -> [sum, overflow]
+    CheckedAdd { n_bits: usize },
+    // [a, b] -> [product, overflow]
+    CheckedMul { n_bits: usize },
+    // [x] -> [quotient, remainder]
+    DivPow2 { k: usize, n_bits: usize },
+    // [index] -> n booleans
+    OneHot { n: usize },
+    // [vals.., index] -> [vals[index]]
+    SelectFrom,
+    // [a.., b..] with a and b of the same length -> [a < b]
+    LexLt,
+    // values -> NTT of the values
+    Ntt,
+    // [ys.., z] -> [p(z)]
+    Interpolate { xs: Vec<u64> },
+    // [a] -> [1 / a]
+    Invert,
+    // [a, b] -> [a / b]
+    Div,
+    // [a, b, c] -> [a * b + c]
+    MulAdd,
+    // [a] -> [-a]
+    Neg,
+    // [a] -> [a^2]
+    Square,
+    // xs -> [sum_i coeffs[i] * xs[i]]
+    LinearCombination { coeffs: Vec<u64> },
+    // [a.., b..] with a and b of the same length -> [sum_i a_i * b_i]
+    InnerProduct,
+    // [a] -> [a + k]
+    AddConst { k: u64 },
+    // [a] -> [a * k]
+    MulConst { k: u64 },
+    // [a] -> [a == 0]
+    IsZero,
+    // [a, b] -> [a == b]
+    IsEqual,
+    // [a] -> [1 / a or 0]
+    InvertOrZero,
+    // [a.., b..] with a and b of the same length -> [a_i + b_i]
+    AddMany,
+    // [a.., b..] with a and b of the same length -> [a_i * b_i]
+    MulMany,
+    // [a.., b..] with a and b of the same length -> [a_i - b_i]
+    SubMany,
+    // values -> [sum of the values]
+    Accumulate,
+    // [a, b] -> [out] with q_l * a + q_r * b + q_m * a * b + q_o * out + q_c = 0
+    Generic { q: [i64; 5] },
+    // [a, b] -> []
+    AssertNotEqual,
+    // [a, x, b] -> [a * x + b]
+    Affine,
+    // [a, b] -> [a & b]
+    And,
+    // [a, b] -> [a | b]
+    Or,
+    // [a, b] -> [!(a & b)] on 4 bits
+    Nand,
+    // [a, b] -> [!(a ^ b)] on 4 bits
+    Xnor,
+    // [a] -> [2^bitlength - 1 - a]
+    Not { bitlength: usize },
+    // [a] -> little-endian bits of a
+    DecomposeBits { num_bits: usize },
+    // [a] -> []
+    RangeCheckRunningSum { num_bits: usize },
+    // [a] -> [a << n], [a >> n] and a rotated left or right by n, on words of
+    // word_size bits
+    Shl { n: usize, word_size: usize },
+    Shr { n: usize, word_size: usize },
+    Rotl { n: usize, word_size: usize },
+    Rotr { n: usize, word_size: usize },
+    // [a, b] -> [a ^ b]
+    XorWide { total_bits: usize },
+    // [a, b] -> [a & b]
+    AndWide { total_bits: usize },
+    // [a, b] -> [a + b mod 2^32, carry], on 32-bit words
+    AddMod32,
+    // [a, b] -> [a ^ b], on 32-bit words
+    Xor32,
+    // [a] -> [a rotated right by n], on 32-bit words
+    Rotr32 { n: usize },
+    // [a, b] -> [a op b mod 2^64, carry, borrow or overflow], on 64-bit
+    // integers
+    U64Add,
+    U64Sub,
+    U64Mul,
+    // [a, b] -> [a - b], which must not underflow
+    U64CheckedSub,
+    // [a] -> little-endian bytes of a
+    ToBytes { n: usize },
+    // little-endian bytes -> [number]
+    FromBytes,
+    // [a] -> [number of set bits of a]
+    Popcount { bits: usize },
+    // [a] -> [XOR of the bits of a]
+    Parity { bits: usize },
+    // [a] -> [a], which must be 0 or 1
+    AssertBool,
+    // bits -> [number]
+    PackBits { order: BitOrder },
+    // [a] -> bits of a
+    Unpack { n: usize, order: BitOrder },
+    // [rows of (a, b, c)..., a, b, c] -> [], (a, b, c) being one of the rows
+    DynamicLookup { rows: usize },
+    // [a] -> [top bit of a]
+    Msb { bits: usize },
+    // [a, b] -> [a < b]
+    Lt { bits: usize },
+    // [bit, a, b] -> [a if bit is set, b otherwise]
+    Select,
+    // [a, b] -> [min(a, b)]
+    Min { bits: usize },
+    // [a, b] -> [max(a, b)]
+    Max { bits: usize },
+    // [a] -> [], lo <= a <= hi
+    AssertInRange { lo: u64, hi: u64 },
+    // [bit, a, b] -> [b, a] if bit is set, [a, b] otherwise
+    CondSwap,
+    // values -> [], values being sorted
+    AssertSorted { bits: usize },
+    // [a, b] -> [|a - b|]
+    AbsDiff { bits: usize },
+    // bits -> [at least k bits are set]
+    AtLeastKOfN { k: u64 },
+    // bytes -> 32 bytes of the Keccak-256 digest
+    Keccak256,
+    // [x, k] -> [MiMC encryption of x under k]
+    MimcPermute,
+    // inputs -> [MiMC Feistel hash of the inputs]
+    MimcFeistelHash,
+    // [value, blinding] -> [x, y] of the Pedersen commitment
+    PedersenCommit,
+    // inputs -> [Merkle–Damgård hash of the inputs over MiMC]
+    MimcMerkleDamgard,
+    // inputs -> the challenges squeezed once the inputs are absorbed
+    Transcript { squeezes: usize },
+    // leaves -> [root of the tree over MiMC]
+    MerkleRoot,
+    // [old_root, new_root, leaf_old, leaf_new, index bits, siblings] -> [],
+    // with as many index bits as siblings
+    MerkleUpdate,
+    // [a] -> [], a being in the set
+    AssertMember { set: Vec<u64>, bits: usize },
+    // [a] -> [], a not being in the set
+    AssertNotMember { set: Vec<u64>, bits: usize },
+    // [value, rho, r, nk] -> [note commitment, nullifier]
+    Note,
+    // [x_p, y_p, x_q, y_q] -> [p + q], the identity being (0, 0)
+    EccAdd,
+    // [x, y, bits of k, little-endian] -> [k * (x, y)]
+    EccScalarMul { bits: usize },
+    // [x_i, y_i, bits of k_i, little-endian]* -> [sum of k_i * (x_i, y_i)]
+    EccMsm { bits: usize },
+    // [pk, m, R, 255 bits of s] -> [validity of the Schnorr signature]
+    Schnorr,
+    // [a.., m..] on n limbs of `bits` bits each -> [a^e mod m]
+    BigIntModExp { n: usize, bits: usize, e: u64 },
+    // [n.., sig.., 32 bytes of the digest] -> [validity of the RSA signature],
+    // on the 64-bit limbs of the sample key of rsa.rs, of exponent 3
+    RsaVerify,
+    // [pk, commitment, m, r, blinding] -> [c1, c2], the ElGamal encryption of
+    // the committed value
+    ElGamalEncryptCommitted,
+}
+
+impl Gadget {
+    // Every instruction, with the parameters of its test vectors.
+    pub(crate) fn all() -> Vec<Gadget> {
+        vec![
+            Gadget::Add,
+            Gadget::Mul,
+            Gadget::Xor,
+            Gadget::RangeCheck { num_bits: 8 },
+            Gadget::CheckedAdd { n_bits: 8 },
+            Gadget::CheckedMul { n_bits: 8 },
+            Gadget::DivPow2 { k: 3, n_bits: 8 },
+            Gadget::OneHot { n: 4 },
+            Gadget::SelectFrom,
+            Gadget::LexLt,
+            Gadget::Ntt,
+            Gadget::Interpolate { xs: vec![1, 2, 3] },
+            Gadget::Invert,
+            Gadget::Div,
+            Gadget::MulAdd,
+            Gadget::Neg,
+            Gadget::Square,
+            Gadget::LinearCombination {
+                coeffs: vec![1, 2, 3],
+            },
+            Gadget::InnerProduct,
+            Gadget::AddConst { k: 7 },
+            Gadget::MulConst { k: 7 },
+            Gadget::IsZero,
+            Gadget::IsEqual,
+            Gadget::InvertOrZero,
+            Gadget::AddMany,
+            Gadget::MulMany,
+            Gadget::SubMany,
+            Gadget::Accumulate,
+            // a - b
+            Gadget::Generic {
+                q: [1, -1, 0, -1, 0],
+            },
+            // 2 * a * b + 3
+            Gadget::Generic {
+                q: [0, 0, 2, -1, 3],
+            },
+            Gadget::AssertNotEqual,
+            Gadget::Affine,
+            Gadget::And,
+            Gadget::Or,
+            Gadget::Nand,
+            Gadget::Xnor,
+            Gadget::Not { bitlength: 3 },
+            Gadget::DecomposeBits { num_bits: 4 },
+            Gadget::RangeCheckRunningSum { num_bits: 10 },
+            Gadget::Shl { n: 3, word_size: 8 },
+            Gadget::Shr { n: 3, word_size: 8 },
+            Gadget::Rotl { n: 3, word_size: 8 },
+            Gadget::Rotr { n: 3, word_size: 8 },
+            Gadget::XorWide { total_bits: 12 },
+            Gadget::AndWide { total_bits: 12 },
+            Gadget::AddMod32,
+            Gadget::Xor32,
+            Gadget::Rotr32 { n: 7 },
+            Gadget::U64Add,
+            Gadget::U64Sub,
+            Gadget::U64Mul,
+            Gadget::U64CheckedSub,
+            Gadget::ToBytes { n: 3 },
+            Gadget::FromBytes,
+            Gadget::Popcount { bits: 5 },
+            Gadget::Parity { bits: 5 },
+            Gadget::AssertBool,
+            Gadget::PackBits {
+                order: BitOrder::LittleEndian,
+            },
+            Gadget::PackBits {
+                order: BitOrder::BigEndian,
+            },
+            Gadget::Unpack {
+                n: 5,
+                order: BitOrder::LittleEndian,
+            },
+            Gadget::Unpack {
+                n: 5,
+                order: BitOrder::BigEndian,
+            },
+            Gadget::DynamicLookup { rows: 3 },
+            Gadget::Msb { bits: 5 },
+            Gadget::Lt { bits: 8 },
+            Gadget::Select,
+            Gadget::Min { bits: 8 },
+            Gadget::Max { bits: 8 },
+            Gadget::AssertInRange { lo: 18, hi: 130 },
+            Gadget::CondSwap,
+            Gadget::AssertSorted { bits: 8 },
+            Gadget::AbsDiff { bits: 8 },
+            Gadget::AtLeastKOfN { k: 0 },
+            Gadget::AtLeastKOfN { k: 2 },
+            Gadget::AtLeastKOfN { k: 5 },
+            Gadget::Keccak256,
+            Gadget::MimcPermute,
+            Gadget::MimcFeistelHash,
+            Gadget::PedersenCommit,
+            Gadget::MimcMerkleDamgard,
+            Gadget::Transcript { squeezes: 2 },
+            Gadget::MerkleRoot,
+            Gadget::MerkleUpdate,
+            Gadget::AssertMember {
+                set: vec![200, 3, 5, 6],
+                bits: 8,
+            },
+            Gadget::AssertNotMember {
+                set: vec![200, 3, 5, 6],
+                bits: 8,
+            },
+            Gadget::Note,
+            Gadget::EccAdd,
+            Gadget::EccScalarMul { bits: 8 },
+            Gadget::EccMsm { bits: 7 },
+            Gadget::Schnorr,
+            Gadget::BigIntModExp {
+                n: 3,
+                bits: 8,
+                e: 5,
+            },
+            Gadget::RsaVerify,
+            Gadget::ElGamalEncryptCommitted,
+        ]
+    }
+
+    // The log2 of the number of rows the samples of the instruction need.
+    pub(crate) fn k(&self) -> u32 {
+        match self {
+            Gadget::EccMsm { .. } | Gadget::MerkleUpdate => 12,
+            Gadget::BigIntModExp { .. } => 13,
+            Gadget::PedersenCommit | Gadget::Schnorr | Gadget::RsaVerify => 16,
+            Gadget::ElGamalEncryptCommitted => 17,
+            Gadget::Keccak256 => 19,
+            _ => 11,
+        }
+    }
+}
+
+fn field<F: FieldExt>(values: &[u64]) -> Vec<F> {
+    values.iter().map(|v| F::from(*v)).collect()
+}
+
+fn flatten<F: FieldExt>(points: &[Affine<F>]) -> Vec<F> {
+    points.iter().flat_map(|p| vec![p.0, p.1]).collect()
+}
+
+// G, 2G and the identity.
+fn points<F: FieldExt>() -> (Affine<F>, Affine<F>, Affine<F>) {
+    let (_, g) = find_point(1);
+    (g, double_native(g), (F::zero(), F::zero()))
+}
+
+// [x, y, bits of k] for the point p and the scalar k on `bits` bits.
+fn scalar_mul_sample<F: FieldExt>(p: Affine<F>, k: u64, bits: usize) -> Vec<F> {
+    let mut inputs = flatten(&[p]);
+    inputs.extend((0..bits).map(|i| F::from((k >> i) & 1)));
+    inputs
+}
+
+fn merkle_sample<F: FieldExt>(
+    leaf_old: u64,
+    leaf_new: u64,
+    index: u64,
+    siblings: &[u64],
+) -> Vec<F> {
+    let siblings: Vec<F> = field(siblings);
+    let mut inputs = vec![
+        root_from_path_native(F::from(leaf_old), index, &siblings),
+        root_from_path_native(F::from(leaf_new), index, &siblings),
+        F::from(leaf_old),
+        F::from(leaf_new),
+    ];
+    inputs.extend((0..siblings.len()).map(|i| F::from((index >> i) & 1)));
+    inputs.extend(siblings);
+    inputs
+}
+
+// [pk, m, R, bits of s] of the signature of `m` under the secret key `x`
+// with the nonce `k`.
+fn schnorr_sample<F: FieldExt>(x: u64, k: u64, m: u64) -> Vec<F> {
+    let (pk, r, s) = sign_native(x, k, F::from(m));
+    let mut inputs = vec![pk.0, pk.1, F::from(m), r.0, r.1];
+    inputs.extend(s.into_iter().map(F::from));
+    inputs
+}
+
+fn rsa_sample(sig: &[u64], digest: &[u8]) -> Vec<u64> {
+    SAMPLE_N
+        .iter()
+        .chain(sig.iter())
+        .copied()
+        .chain(digest.iter().map(|byte| *byte as u64))
+        .collect()
+}
+
+// [pk, commitment, m, r, blinding] for the secret key `x`.
+fn elgamal_sample<F: FieldExt>(x: u64, m: u64, r: u64, blinding: u64) -> Vec<F> {
+    let (g, _, _) = points::<F>();
+    let pk = scalar_mul_native(g, &to_le_bits(&F::from(x), 64));
+    let (m, r, blinding) = (F::from(m), F::from(r), F::from(blinding));
+    let commitment = commit_native(m, blinding);
+    vec![pk.0, pk.1, commitment.0, commitment.1, m, r, blinding]
+}
+
+fn coeffs<F: FieldExt>(q: &[i64; 5]) -> GenericCoeffs<F> {
+    GenericCoeffs {
+        q_l: from_i64(q[0]),
+        q_r: from_i64(q[1]),
+        q_m: from_i64(q[2]),
+        q_o: from_i64(q[3]),
+        q_c: from_i64(q[4]),
+    }
+}
+
+impl<F: FieldExt> TestableGadget<F> for Gadget {
+    fn samples(&self) -> Vec<Vec<F>> {
+        let samples: Vec<Vec<u64>> = match self {
+            Gadget::Add => vec![vec![2, 3]],
+            Gadget::Mul => vec![vec![6, 7]],
+            Gadget::Xor => vec![vec![5, 9]],
+            Gadget::RangeCheck { .. } => vec![vec![255]],
+            Gadget::CheckedAdd { .. } => vec![vec![1, 2], vec![200, 100]],
+            Gadget::CheckedMul { .. } => vec![vec![3, 5], vec![16, 16]],
+            Gadget::DivPow2 { .. } => vec![vec![203]],
+            Gadget::OneHot { .. } => vec![vec![2]],
+            Gadget::SelectFrom => vec![vec![10, 20, 30, 1]],
+            Gadget::LexLt => vec![vec![1, 2, 3, 1, 3, 0], vec![1, 2, 3, 1, 2, 3]],
+            Gadget::Ntt => vec![vec![1, 2, 3, 4]],
+            Gadget::Interpolate { .. } => vec![vec![1, 4, 9, 5]],
+            Gadget::Invert => vec![vec![3], vec![1]],
+            Gadget::Div => vec![vec![6, 3], vec![1, 2]],
+            Gadget::MulAdd => vec![vec![3, 4, 5]],
+            Gadget::Neg => vec![vec![7], vec![0]],
+            Gadget::Square => vec![vec![9]],
+            Gadget::LinearCombination { .. } => vec![vec![4, 5, 6], vec![0, 0, 0]],
+            Gadget::InnerProduct => vec![vec![1, 2, 3, 4, 5, 6]],
+            Gadget::AddConst { .. } | Gadget::MulConst { .. } => vec![vec![5]],
+            Gadget::IsZero => vec![vec![0], vec![5]],
+            Gadget::IsEqual => vec![vec![3, 3], vec![3, 4]],
+            Gadget::InvertOrZero => vec![vec![3], vec![0]],
+            Gadget::AddMany | Gadget::MulMany | Gadget::SubMany => vec![vec![5, 7, 9, 1, 2, 3]],
+            Gadget::Accumulate => vec![vec![1, 2, 3, 4], vec![7]],
+            Gadget::Generic { .. } => vec![vec![9, 4]],
+            Gadget::AssertNotEqual => vec![vec![3, 4]],
+            Gadget::Affine => vec![vec![2, 10, 1]],
+            Gadget::And => vec![vec![12, 10]],
+            Gadget::Or | Gadget::Nand | Gadget::Xnor => vec![vec![12, 10]],
+            Gadget::Not { .. } => vec![vec![5]],
+            Gadget::DecomposeBits { .. } => vec![vec![11], vec![0]],
+            Gadget::RangeCheckRunningSum { .. } => vec![vec![1023], vec![0]],
+            Gadget::Shl { .. } | Gadget::Shr { .. } | Gadget::Rotl { .. } | Gadget::Rotr { .. } => {
+                vec![vec![0b1011_0110], vec![255]]
+            }
+            Gadget::XorWide { .. } | Gadget::AndWide { .. } => {
+                vec![vec![0xabc, 0x5a3], vec![0xfff, 0]]
+            }
+            Gadget::AddMod32 | Gadget::Xor32 => {
+                vec![vec![0x6a09_e667, 0xbb67_ae85], vec![0xffff_ffff, 1]]
+            }
+            Gadget::Rotr32 { .. } => vec![vec![0x6a09_e667], vec![1]],
+            Gadget::U64Add | Gadget::U64Sub | Gadget::U64Mul => {
+                vec![vec![u64::MAX, 2], vec![2, u64::MAX], vec![1 << 40, 1 << 20]]
+            }
+            Gadget::U64CheckedSub => vec![vec![7, 5]],
+            Gadget::ToBytes { .. } => vec![vec![0x12_3456], vec![0xff_ffff]],
+            Gadget::FromBytes => vec![vec![0x56, 0x34, 0x12], vec![0, 0, 255]],
+            Gadget::AssertBool => vec![vec![0], vec![1]],
+            Gadget::PackBits { .. } => vec![vec![1, 1, 0, 1], vec![0, 0, 0, 0]],
+            Gadget::Unpack { .. } => vec![vec![0b10110], vec![0b11111]],
+            Gadget::Msb { .. } => vec![vec![0], vec![0b01111], vec![0b10000], vec![0b11111]],
+            Gadget::Lt { .. }
+            | Gadget::Min { .. }
+            | Gadget::Max { .. }
+            | Gadget::AbsDiff { .. } => {
+                vec![vec![3, 5], vec![5, 3], vec![7, 7], vec![0, 255]]
+            }
+            Gadget::Select | Gadget::CondSwap => vec![vec![1, 3, 5], vec![0, 3, 5]],
+            Gadget::AssertInRange { .. } => vec![vec![18], vec![40], vec![130]],
+            Gadget::AssertSorted { .. } => vec![vec![1, 3, 3, 200, 255], vec![7]],
+            Gadget::MimcPermute => vec![vec![0, 0], vec![1, 2], vec![123_456, 789]],
+            Gadget::MimcFeistelHash => vec![vec![1], vec![1, 2], vec![0, 0]],
+            Gadget::MimcMerkleDamgard => vec![vec![0], vec![1, 2, 3]],
+            Gadget::Transcript { .. } => vec![vec![7], vec![1, 2]],
+            Gadget::MerkleRoot => vec![vec![1, 2, 3], vec![5, 6], vec![9]],
+            Gadget::AssertMember { .. } => vec![vec![3], vec![6], vec![200]],
+            Gadget::AssertNotMember { .. } => vec![vec![0], vec![4], vec![7], vec![255]],
+            Gadget::Note => vec![vec![10, 1, 2, 3], vec![0, 5, 5, 7]],
+            Gadget::AtLeastKOfN { .. } => {
+                vec![vec![0, 0, 0], vec![1, 0, 0], vec![1, 0, 1], vec![1, 1, 1]]
+            }
+            Gadget::DynamicLookup { .. } => vec![
+                vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 4, 5, 6],
+                vec![1, 2, 3, 4, 5, 6, 1, 2, 3, 1, 2, 3],
+            ],
+            Gadget::Popcount { .. } | Gadget::Parity { .. } => {
+                vec![vec![0], vec![0b10110], vec![0b11111]]
+            }
+            // a single sample as every run lays out a whole permutation
+            Gadget::Keccak256 => vec![b"abc".iter().map(|b| *b as u64).collect()],
+            Gadget::PedersenCommit => vec![vec![1, 2], vec![42, 0x1234_5678_9abc]],
+            Gadget::MerkleUpdate => {
+                return vec![
+                    merkle_sample(5, 6, 2, &[3, 4]),
+                    merkle_sample(0, 9, 1, &[7, 8]),
+                ]
+            }
+            Gadget::EccAdd => {
+                let (g, g2, o) = points::<F>();
+                return vec![
+                    flatten(&[g, g2]),
+                    flatten(&[g, g]),
+                    flatten(&[g, (g.0, -g.1)]),
+                    flatten(&[o, g2]),
+                    flatten(&[g2, o]),
+                    flatten(&[o, o]),
+                ];
+            }
+            Gadget::EccScalarMul { bits } => {
+                let (g, _, o) = points::<F>();
+                return [(g, 0), (g, 1), (g, 6), (g, 255), (o, 3)]
+                    .iter()
+                    .map(|(p, k)| scalar_mul_sample(*p, *k, *bits))
+                    .collect();
+            }
+            Gadget::EccMsm { bits } => {
+                let (g, g2, o) = points::<F>();
+                return vec![
+                    [(g, 3), (g2, 5), (o, 7)],
+                    [(g, 0), (g2, 0), (g, 0)],
+                    // 127 G - 127 G + G
+                    [(g, 127), ((g.0, -g.1), 127), (g, 1)],
+                ]
+                .iter()
+                .map(|terms| {
+                    terms
+                        .iter()
+                        .flat_map(|(p, k)| scalar_mul_sample(*p, *k, *bits))
+                        .collect()
+                })
+                .collect();
+            }
+            Gadget::Schnorr => {
+                let valid = schnorr_sample::<F>(7, 11, 42);
+                // the same signature on another message
+                let mut forged = valid.clone();
+                forged[2] = F::from(43);
+                // s + q, which passes the curve equation
+                let mut malleated = valid.clone();
+                let mut carry = false;
+                for (bit, q) in malleated[5..].iter_mut().zip(order_bits::<F>()) {
+                    let sum = (*bit == F::one()) as u8 + q as u8 + carry as u8;
+                    *bit = F::from((sum & 1) as u64);
+                    carry = sum > 1;
+                }
+                return vec![valid, forged, malleated];
+            }
+            Gadget::BigIntModExp { n, bits, .. } => {
+                let (n, bits) = (*n, *bits);
+                let split = |a: u64| (0..n).map(move |j| (a >> (j * bits)) & ((1 << bits) - 1));
+                [
+                    (0x12_3456, 0xab_cdef),
+                    (0xff_ffff, 0xff_ffff),
+                    (7, 1),
+                    (0, 0x10_0001),
+                ]
+                .iter()
+                .map(|(a, m)| split(*a).chain(split(*m)).collect())
+                .collect()
+            }
+            Gadget::RsaVerify => {
+                let mut other = SAMPLE_DIGEST;
+                other[0] ^= 1;
+                let mut forged = SAMPLE_SIG;
+                forged[0] += 1;
+                vec![
+                    rsa_sample(&SAMPLE_SIG, &SAMPLE_DIGEST),
+                    rsa_sample(&SAMPLE_SIG, &other),
+                    rsa_sample(&forged, &SAMPLE_DIGEST),
+                ]
+            }
+            Gadget::ElGamalEncryptCommitted => return vec![elgamal_sample(7, 42, 0x1234_5678, 99)],
+        };
+        samples.iter().map(|s| field(s)).collect()
+    }
+
+    fn invalid_samples(&self) -> Vec<Vec<F>> {
+        let samples: Vec<Vec<u64>> = match self {
+            Gadget::RangeCheck { .. } => vec![vec![256]],
+            Gadget::DivPow2 { .. } => vec![vec![256]],
+            Gadget::OneHot { .. } => vec![vec![4]],
+            Gadget::SelectFrom => vec![vec![10, 20, 30, 3]],
+            Gadget::Invert => vec![vec![0]],
+            Gadget::Div => vec![vec![1, 0]],
+            Gadget::AssertNotEqual => vec![vec![3, 3]],
+            Gadget::Not { .. } => vec![vec![9]],
+            Gadget::DecomposeBits { .. } => vec![vec![16]],
+            Gadget::RangeCheckRunningSum { .. } => vec![vec![1024]],
+            Gadget::Shl { .. } | Gadget::Shr { .. } | Gadget::Rotl { .. } | Gadget::Rotr { .. } => {
+                vec![vec![256]]
+            }
+            Gadget::XorWide { .. } | Gadget::AndWide { .. } => vec![vec![0x1000, 1]],
+            Gadget::AddMod32 | Gadget::Xor32 => vec![vec![1 << 32, 1]],
+            Gadget::Rotr32 { .. } => vec![vec![1 << 32]],
+            Gadget::U64CheckedSub => vec![vec![5, 7]],
+            Gadget::ToBytes { .. } => vec![vec![1 << 24]],
+            Gadget::FromBytes => vec![vec![256, 0, 0]],
+            Gadget::Popcount { .. } | Gadget::Parity { .. } => vec![vec![1 << 5]],
+            Gadget::AssertBool => vec![vec![2]],
+            Gadget::PackBits { .. } => vec![vec![1, 2, 0, 1]],
+            Gadget::Unpack { .. } => vec![vec![1 << 5]],
+            Gadget::Msb { .. } => vec![vec![1 << 5]],
+            Gadget::Select | Gadget::CondSwap => vec![vec![2, 3, 5]],
+            Gadget::AssertInRange { .. } => vec![vec![17], vec![131], vec![0], vec![1 << 20]],
+            Gadget::AssertSorted { .. } => vec![vec![1, 3, 2, 200], vec![5, 4]],
+            Gadget::AtLeastKOfN { .. } => vec![vec![1, 2, 1]],
+            Gadget::AssertMember { .. } => vec![vec![0], vec![4], vec![201]],
+            Gadget::AssertNotMember { .. } => vec![vec![3], vec![5], vec![200]],
+            Gadget::DynamicLookup { .. } => vec![
+                vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 4, 5, 7],
+                vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 1, 5, 9],
+            ],
+            // not a byte
+            Gadget::Keccak256 => vec![vec![0x61, 0x162]],
+            // p - 1 does not fit in F::CAPACITY bits
+            Gadget::PedersenCommit => return vec![vec![-F::one(), F::from(2)]],
+            Gadget::MerkleUpdate => {
+                // a new root for another leaf, then for another position
+                let mut wrong_leaf = merkle_sample::<F>(5, 6, 2, &[3, 4]);
+                wrong_leaf[1] = merkle_sample::<F>(5, 7, 2, &[3, 4])[1];
+                let mut wrong_index = merkle_sample::<F>(5, 6, 2, &[3, 4]);
+                wrong_index[1] = merkle_sample::<F>(5, 6, 3, &[3, 4])[1];
+                return vec![wrong_leaf, wrong_index];
+            }
+            Gadget::BigIntModExp { n, bits, .. } => {
+                // a zero modulus, then a limb out of range
+                let mut zero = vec![0; 2 * n];
+                zero[0] = 5;
+                let mut wide = vec![0; 2 * n];
+                wide[0] = 1 << bits;
+                wide[*n] = 3;
+                vec![zero, wide]
+            }
+            Gadget::RsaVerify => {
+                // the signature plus the modulus, of the same value modulo n
+                let mut wrapped = SAMPLE_SIG.to_vec();
+                let mut carry = 0;
+                for (s, n) in wrapped.iter_mut().zip(SAMPLE_N.iter()) {
+                    let (sum, c1) = s.overflowing_add(*n);
+                    let (sum, c2) = sum.overflowing_add(carry);
+                    *s = sum;
+                    carry = (c1 || c2) as u64;
+                }
+                vec![rsa_sample(&wrapped, &SAMPLE_DIGEST)]
+            }
+            Gadget::ElGamalEncryptCommitted => {
+                // a commitment to another value
+                let mut other = elgamal_sample::<F>(7, 42, 0x1234_5678, 99);
+                let forged = elgamal_sample::<F>(7, 43, 0x1234_5678, 99);
+                other[2] = forged[2];
+                other[3] = forged[3];
+                return vec![other];
+            }
+            _ => vec![],
+        };
+        samples.iter().map(|s| field(s)).collect()
+    }
+
+    fn reference(&self, inputs: &[F]) -> Vec<F> {
+        let f = |v: u64| F::from(v);
+        // the samples fit in 32 bits except for the NTT, interpolation, curve and
+        // big integer ones
+        let ints: Vec<u64> = inputs.iter().map(|v| v.get_lower_32() as u64).collect();
+        match self {
+            Gadget::Add => vec![inputs[0] + inputs[1]],
+            Gadget::Mul => vec![inputs[0] * inputs[1]],
+            Gadget::Xor | Gadget::XorWide { .. } | Gadget::Xor32 => vec![f(ints[0] ^ ints[1])],
+            Gadget::AndWide { .. } => vec![f(ints[0] & ints[1])],
+            Gadget::AddMod32 => {
+                let sum = ints[0] + ints[1];
+                vec![f(sum & 0xffff_ffff), f(sum >> 32)]
+            }
+            Gadget::Rotr32 { n } => vec![f((ints[0] as u32).rotate_right(*n as u32) as u64)],
+            Gadget::U64Add | Gadget::U64Sub | Gadget::U64Mul | Gadget::U64CheckedSub => {
+                let (a, b) = (lower_64(&inputs[0]), lower_64(&inputs[1]));
+                let (r, flag) = match self {
+                    Gadget::U64Add => a.overflowing_add(b),
+                    Gadget::U64Mul => a.overflowing_mul(b),
+                    _ => a.overflowing_sub(b),
+                };
+                match self {
+                    Gadget::U64CheckedSub => vec![f(r)],
+                    _ => vec![f(r), F::from(flag)],
+                }
+            }
+            Gadget::ToBytes { n } => (0..*n).map(|i| f((ints[0] >> (8 * i)) & 0xff)).collect(),
+            Gadget::FromBytes => vec![ints
+                .iter()
+                .rev()
+                .fold(F::zero(), |acc, byte| acc * f(256) + f(*byte))],
+            Gadget::Popcount { .. } => vec![f(ints[0].count_ones() as u64)],
+            Gadget::Parity { .. } => vec![f((ints[0].count_ones() % 2) as u64)],
+            Gadget::AssertBool => vec![inputs[0]],
+            Gadget::PackBits { order } => {
+                let mut bits = ints.clone();
+                if *order == BitOrder::BigEndian {
+                    bits.reverse();
+                }
+                vec![f(bits.iter().rev().fold(0, |acc, bit| (acc << 1) | bit))]
+            }
+            Gadget::Msb { bits } => vec![f(ints[0] >> (bits - 1))],
+            Gadget::Lt { .. } => vec![F::from(ints[0] < ints[1])],
+            Gadget::Select => vec![if ints[0] == 1 { inputs[1] } else { inputs[2] }],
+            Gadget::CondSwap => match ints[0] {
+                1 => vec![inputs[2], inputs[1]],
+                _ => vec![inputs[1], inputs[2]],
+            },
+            Gadget::Min { .. } => vec![f(ints[0].min(ints[1]))],
+            Gadget::Max { .. } => vec![f(ints[0].max(ints[1]))],
+            Gadget::AbsDiff { .. } => vec![f(ints[0].max(ints[1]) - ints[0].min(ints[1]))],
+            Gadget::AtLeastKOfN { k } => vec![F::from(ints.iter().sum::<u64>() >= *k)],
+            Gadget::Keccak256 => {
+                let bytes: Vec<u8> = ints.iter().map(|b| *b as u8).collect();
+                keccak256_native(&bytes)
+                    .iter()
+                    .map(|b| f(*b as u64))
+                    .collect()
+            }
+            Gadget::MimcPermute => vec![permute_native(inputs[0], inputs[1])],
+            Gadget::MimcFeistelHash => vec![feistel_hash_native(inputs)],
+            Gadget::PedersenCommit => flatten(&[commit_native(inputs[0], inputs[1])]),
+            Gadget::MimcMerkleDamgard => hash_native(inputs, 1, vec![F::zero()], |h, m| {
+                vec![compress_native(h[0], m[0])]
+            }),
+            Gadget::Transcript { squeezes } => challenges_native(inputs, *squeezes),
+            Gadget::MerkleRoot => vec![root_native(inputs)],
+            Gadget::Note => {
+                let (cm, nf) = note_native(inputs[0], inputs[1], inputs[2], inputs[3]);
+                vec![cm, nf]
+            }
+            Gadget::EccAdd => flatten(&[add_complete_native(
+                (inputs[0], inputs[1]),
+                (inputs[2], inputs[3]),
+            )]),
+            Gadget::EccScalarMul { bits } | Gadget::EccMsm { bits } => {
+                let sum = inputs
+                    .chunks(2 + bits)
+                    .fold((F::zero(), F::zero()), |acc, term| {
+                        let bits: Vec<bool> = term[2..].iter().map(|b| *b == F::one()).collect();
+                        add_complete_native(acc, scalar_mul_native((term[0], term[1]), &bits))
+                    });
+                flatten(&[sum])
+            }
+            Gadget::Schnorr => {
+                let s: Vec<bool> = inputs[5..].iter().map(|b| *b == F::one()).collect();
+                let pk = (inputs[0], inputs[1]);
+                let r = (inputs[3], inputs[4]);
+                vec![F::from(verify_native(pk, inputs[2], r, &s))]
+            }
+            Gadget::BigIntModExp { n, bits, e } => {
+                let ints: Vec<u64> = inputs.iter().map(lower_64).collect();
+                mod_exp_native(&ints[..*n], *e, &ints[*n..], *bits)
+                    .unwrap_or_else(|| vec![0; *n])
+                    .into_iter()
+                    .map(f)
+                    .collect()
+            }
+            Gadget::RsaVerify => {
+                let ints: Vec<u64> = inputs.iter().map(lower_64).collect();
+                let (n, sig) = (&ints[..SAMPLE_LIMBS], &ints[SAMPLE_LIMBS..2 * SAMPLE_LIMBS]);
+                let digest: Vec<u8> = ints[2 * SAMPLE_LIMBS..].iter().map(|b| *b as u8).collect();
+                let m = mod_exp_native(sig, SAMPLE_E, n, SAMPLE_LIMB_BITS);
+                let em = encoding_native(&digest, SAMPLE_LIMBS, SAMPLE_LIMB_BITS);
+                vec![F::from(m == Some(em))]
+            }
+            Gadget::ElGamalEncryptCommitted => {
+                let (c1, c2) = encrypt_native((inputs[0], inputs[1]), inputs[4], inputs[5]);
+                flatten(&[c1, c2])
+            }
+            Gadget::Unpack { n, order } => {
+                let mut bits: Vec<F> = (0..*n).map(|i| f((ints[0] >> i) & 1)).collect();
+                if *order == BitOrder::BigEndian {
+                    bits.reverse();
+                }
+                bits
+            }
+            Gadget::RangeCheck { .. }
+            | Gadget::RangeCheckRunningSum { .. }
+            | Gadget::DynamicLookup { .. }
+            | Gadget::AssertInRange { .. }
+            | Gadget::AssertSorted { .. }
+            | Gadget::AssertMember { .. }
+            | Gadget::AssertNotMember { .. }
+            | Gadget::MerkleUpdate => vec![],
+            Gadget::CheckedAdd { n_bits } | Gadget::CheckedMul { n_bits } => {
+                let r = match self {
+                    Gadget::CheckedAdd { .. } => ints[0] + ints[1],
+                    _ => ints[0] * ints[1],
+                };
+                vec![f(r % (1 << n_bits)), f((r >> n_bits != 0) as u64)]
+            }
+            Gadget::DivPow2 { k, .. } => vec![f(ints[0] >> k), f(ints[0] % (1 << k))],
+            Gadget::OneHot { n } => (0..*n as u64).map(|i| f((i == ints[0]) as u64)).collect(),
+            Gadget::SelectFrom => {
+                let (index, vals) = inputs.split_last().unwrap();
+                let index = index.get_lower_32() as usize;
+                vec![vals.get(index).copied().unwrap_or_else(F::zero)]
+            }
+            Gadget::LexLt => {
+                let (a, b) = ints.split_at(ints.len() / 2);
+                vec![f((a < b) as u64)]
+            }
+            Gadget::Ntt => {
+                let omega: F = root_of_unity(inputs.len().trailing_zeros()).unwrap();
+                (0..inputs.len())
+                    .map(|k| {
+                        let w = omega.pow_vartime(&[k as u64]);
+                        inputs.iter().rev().fold(F::zero(), |acc, x| acc * w + *x)
+                    })
+                    .collect()
+            }
+            Gadget::Interpolate { xs } => {
+                let (z, ys) = inputs.split_last().unwrap();
+                let xs: Vec<F> = field(xs);
+                let weights = barycentric_weights(&xs).unwrap();
+                let value = ys.iter().enumerate().fold(F::zero(), |acc, (i, y)| {
+                    let others = xs
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .fold(F::one(), |acc, (_, xj)| acc * (*z - *xj));
+                    acc + *y * weights[i] * others
+                });
+                vec![value]
+            }
+            Gadget::Invert => vec![inputs[0].invert().unwrap_or(F::zero())],
+            Gadget::Div => vec![inputs[0] * inputs[1].invert().unwrap_or(F::zero())],
+            Gadget::MulAdd => vec![inputs[0] * inputs[1] + inputs[2]],
+            Gadget::Neg => vec![-inputs[0]],
+            Gadget::Square => vec![inputs[0].square()],
+            Gadget::LinearCombination { coeffs } => vec![field::<F>(coeffs)
+                .iter()
+                .zip(inputs.iter())
+                .fold(F::zero(), |acc, (c, x)| acc + *c * *x)],
+            Gadget::InnerProduct => {
+                let (a, b) = inputs.split_at(inputs.len() / 2);
+                vec![a
+                    .iter()
+                    .zip(b.iter())
+                    .fold(F::zero(), |acc, (a, b)| acc + *a * *b)]
+            }
+            Gadget::AddConst { k } => vec![inputs[0] + f(*k)],
+            Gadget::MulConst { k } => vec![inputs[0] * f(*k)],
+            Gadget::IsZero => vec![F::from(inputs[0] == F::zero())],
+            Gadget::IsEqual => vec![F::from(inputs[0] == inputs[1])],
+            Gadget::InvertOrZero => vec![inputs[0].invert().unwrap_or(F::zero())],
+            Gadget::AddMany | Gadget::MulMany | Gadget::SubMany => {
+                let (a, b) = inputs.split_at(inputs.len() / 2);
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| match self {
+                        Gadget::AddMany => *a + *b,
+                        Gadget::MulMany => *a * *b,
+                        _ => *a - *b,
+                    })
+                    .collect()
+            }
+            Gadget::Accumulate => vec![inputs.iter().fold(F::zero(), |acc, v| acc + *v)],
+            Gadget::Generic { q } => {
+                let q = coeffs(q);
+                let (a, b) = (inputs[0], inputs[1]);
+                let sum = q.q_l * a + q.q_r * b + q.q_m * a * b + q.q_c;
+                vec![-sum * q.q_o.invert().unwrap()]
+            }
+            Gadget::AssertNotEqual => vec![],
+            Gadget::Affine => vec![inputs[0] * inputs[1] + inputs[2]],
+            Gadget::And => vec![f(ints[0] & ints[1])],
+            Gadget::Or => vec![f(ints[0] | ints[1])],
+            Gadget::Nand => vec![f(!(ints[0] & ints[1]) & 0xf)],
+            Gadget::Xnor => vec![f(!(ints[0] ^ ints[1]) & 0xf)],
+            Gadget::Not { bitlength } => vec![f((1 << bitlength) - 1) - inputs[0]],
+            Gadget::DecomposeBits { num_bits } => {
+                (0..*num_bits).map(|i| f((ints[0] >> i) & 1)).collect()
+            }
+            Gadget::Shl { n, word_size }
+            | Gadget::Shr { n, word_size }
+            | Gadget::Rotl { n, word_size }
+            | Gadget::Rotr { n, word_size } => {
+                let (a, w, mask) = (ints[0], *word_size, (1 << word_size) - 1);
+                let r = match self {
+                    Gadget::Shl { .. } => a << n,
+                    Gadget::Shr { .. } => a >> n,
+                    Gadget::Rotl { .. } => (a << (n % w)) | (a >> (w - n % w)),
+                    _ => (a >> (n % w)) | (a << (w - n % w)),
+                };
+                vec![f(r & mask)]
+            }
+        }
+    }
+
+    fn synthesize(
+        &self,
+        chip: &FieldChip<F>,
+        mut layouter: impl Layouter<F>,
+        inputs: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error> {
+        let input = |i: usize| inputs.get(i).cloned().ok_or(Error::SynthesisError);
+        let point = |i: usize| -> Result<Point<F>, Error> {
+            Ok(Point {
+                x: input(i)?,
+                y: input(i + 1)?,
+            })
+        };
+        Ok(match self {
+            Gadget::Add => vec![chip.add(layouter, input(0)?, input(1)?)?],
+            Gadget::Mul => vec![chip.mul(layouter, input(0)?, input(1)?)?],
+            Gadget::Xor => vec![chip.xor(layouter, input(0)?, input(1)?)?],
+            Gadget::RangeCheck { num_bits } => {
+                chip.range_check(layouter, input(0)?, *num_bits)?;
+                vec![]
+            }
+            Gadget::CheckedAdd { n_bits } => {
+                let (sum, overflow) = chip.checked_add(layouter, input(0)?, input(1)?, *n_bits)?;
+                vec![sum, overflow]
+            }
+            Gadget::CheckedMul { n_bits } => {
+                let (prod, overflow) = chip.checked_mul(layouter, input(0)?, input(1)?, *n_bits)?;
+                vec![prod, overflow]
+            }
+            Gadget::DivPow2 { k, n_bits } => {
+                let (q, r) = chip.div_pow2(layouter, input(0)?, *k, *n_bits)?;
+                vec![q, r]
+            }
+            Gadget::OneHot { n } => chip.one_hot(layouter, input(0)?, *n)?,
+            Gadget::SelectFrom => {
+                let (index, vals) = inputs.split_last().ok_or(Error::SynthesisError)?;
+                vec![chip.select_from(layouter, vals, index.clone())?]
+            }
+            Gadget::LexLt => {
+                let (a, b) = inputs.split_at(inputs.len() / 2);
+                vec![chip.lex_lt(layouter, a, b)?.num()]
+            }
+            Gadget::Ntt => chip.ntt(layouter, inputs)?,
+            Gadget::Interpolate { xs } => {
+                let (z, ys) = inputs.split_last().ok_or(Error::SynthesisError)?;
+                vec![chip.interpolate(layouter, &field(xs), ys, z.clone())?]
+            }
+            Gadget::Invert => vec![chip.invert(layouter, input(0)?)?],
+            Gadget::Div => vec![chip.div(layouter, input(0)?, input(1)?)?],
+            Gadget::MulAdd => vec![chip.mul_add(layouter, input(0)?, input(1)?, input(2)?)?],
+            Gadget::Neg => vec![chip.neg(layouter, input(0)?)?],
+            Gadget::Square => vec![chip.square(layouter, input(0)?)?],
+            Gadget::LinearCombination { coeffs } => {
+                let terms: Vec<_> = field(coeffs)
+                    .into_iter()
+                    .zip(inputs.iter().cloned())
+                    .collect();
+                vec![chip.linear_combination(layouter, &terms)?]
+            }
+            Gadget::InnerProduct => {
+                let (a, b) = inputs.split_at(inputs.len() / 2);
+                vec![chip.inner_product(layouter, a, b)?]
+            }
+            Gadget::AddConst { k } => vec![chip.add_const(layouter, input(0)?, F::from(*k))?],
+            Gadget::MulConst { k } => vec![chip.mul_const(layouter, input(0)?, F::from(*k))?],
+            Gadget::IsZero => vec![chip.is_zero(layouter, input(0)?)?.num()],
+            Gadget::IsEqual => vec![chip.is_equal(layouter, input(0)?, input(1)?)?.num()],
+            Gadget::InvertOrZero => vec![chip.invert_or_zero(layouter, input(0)?)?],
+            Gadget::AddMany | Gadget::MulMany | Gadget::SubMany => {
+                let (a, b) = inputs.split_at(inputs.len() / 2);
+                match self {
+                    Gadget::AddMany => chip.add_many(layouter, a, b)?,
+                    Gadget::MulMany => chip.mul_many(layouter, a, b)?,
+                    _ => chip.sub_many(layouter, a, b)?,
+                }
+            }
+            Gadget::Accumulate => vec![chip.accumulate(layouter, inputs)?],
+            Gadget::Generic { q } => {
+                vec![chip.generic_op(layouter, input(0)?, input(1)?, coeffs(q))?]
+            }
+            Gadget::AssertNotEqual => {
+                chip.assert_not_equal(layouter, input(0)?, input(1)?)?;
+                vec![]
+            }
+            Gadget::Affine => vec![chip.affine(layouter, input(0)?, input(1)?, input(2)?)?],
+            Gadget::And => vec![chip.and(layouter, input(0)?, input(1)?)?],
+            Gadget::Or => vec![chip.or(layouter, input(0)?, input(1)?)?],
+            Gadget::Nand => vec![chip.nand(layouter, input(0)?, input(1)?)?],
+            Gadget::Xnor => vec![chip.xnor(layouter, input(0)?, input(1)?)?],
+            Gadget::Not { bitlength } => vec![chip.not(layouter, input(0)?, *bitlength)?],
+            Gadget::DecomposeBits { num_bits } => {
+                chip.decompose_bits(layouter, input(0)?, *num_bits)?
+            }
+            Gadget::RangeCheckRunningSum { num_bits } => {
+                chip.range_check_running_sum(layouter, input(0)?, *num_bits)?;
+                vec![]
+            }
+            Gadget::Shl { n, word_size } => vec![chip.shl(layouter, input(0)?, *n, *word_size)?],
+            Gadget::Shr { n, word_size } => vec![chip.shr(layouter, input(0)?, *n, *word_size)?],
+            Gadget::Rotl { n, word_size } => {
+                vec![chip.rotl(layouter, input(0)?, *n, *word_size)?]
+            }
+            Gadget::Rotr { n, word_size } => {
+                vec![chip.rotr(layouter, input(0)?, *n, *word_size)?]
+            }
+            Gadget::AndWide { total_bits } => {
+                vec![chip.and_wide(layouter, input(0)?, input(1)?, *total_bits)?]
+            }
+            Gadget::XorWide { total_bits } => {
+                vec![chip.xor_wide(layouter, input(0)?, input(1)?, *total_bits)?]
+            }
+            Gadget::AddMod32 | Gadget::Xor32 => {
+                let a = chip.word(layouter.namespace(|| "a"), input(0)?)?;
+                let b = chip.word(layouter.namespace(|| "b"), input(1)?)?;
+                match self {
+                    Gadget::AddMod32 => {
+                        let (sum, carry) = chip.add_mod32_carry(layouter, a, b)?;
+                        vec![sum.num(), carry]
+                    }
+                    _ => vec![chip.xor32(layouter, a, b)?.num()],
+                }
+            }
+            Gadget::Rotr32 { n } => {
+                let a = chip.word(layouter.namespace(|| "a"), input(0)?)?;
+                vec![chip.rotr32(layouter, a, *n)?.num()]
+            }
+            Gadget::U64Add | Gadget::U64Sub | Gadget::U64Mul | Gadget::U64CheckedSub => {
+                let a = chip.u64(layouter.namespace(|| "a"), input(0)?)?;
+                let b = chip.u64(layouter.namespace(|| "b"), input(1)?)?;
+                let (r, flag) = match self {
+                    Gadget::U64Add => chip.overflowing_add64(layouter, a, b)?,
+                    Gadget::U64Sub => chip.overflowing_sub64(layouter, a, b)?,
+                    Gadget::U64Mul => chip.overflowing_mul64(layouter, a, b)?,
+                    _ => return Ok(vec![chip.checked_sub64(layouter, a, b)?.num()]),
+                };
+                vec![r.num(), flag]
+            }
+            Gadget::ToBytes { n } => chip
+                .to_bytes(layouter, input(0)?, *n)?
+                .iter()
+                .map(|byte| byte.num())
+                .collect(),
+            Gadget::FromBytes => {
+                let bytes = inputs
+                    .iter()
+                    .map(|a| chip.byte(layouter.namespace(|| "byte"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                vec![chip.from_bytes(layouter, &bytes)?]
+            }
+            Gadget::Popcount { bits } => vec![chip.popcount(layouter, input(0)?, *bits)?],
+            Gadget::Parity { bits } => vec![chip.parity(layouter, input(0)?, *bits)?],
+            Gadget::AssertBool => vec![chip.assert_bool(layouter, input(0)?)?.num()],
+            Gadget::PackBits { order } => {
+                let bits = inputs
+                    .iter()
+                    .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                vec![chip.pack_bits(layouter, &bits, *order)?]
+            }
+            Gadget::Unpack { n, order } => chip
+                .unpack(layouter, input(0)?, *n, *order)?
+                .iter()
+                .map(|bit| bit.num())
+                .collect(),
+            Gadget::Msb { bits } => vec![chip.msb(layouter, input(0)?, *bits)?.num()],
+            Gadget::Lt { bits } => vec![chip.lt(layouter, input(0)?, input(1)?, *bits)?.num()],
+            Gadget::Select => {
+                let bit = chip.assert_bool(layouter.namespace(|| "bit"), input(0)?)?;
+                vec![chip.select(layouter, bit, input(1)?, input(2)?)?]
+            }
+            Gadget::CondSwap => {
+                let bit = chip.assert_bool(layouter.namespace(|| "bit"), input(0)?)?;
+                let (first, second) = chip.cond_swap(layouter, bit, input(1)?, input(2)?)?;
+                vec![first, second]
+            }
+            Gadget::Min { bits } => vec![chip.min(layouter, input(0)?, input(1)?, *bits)?],
+            Gadget::Max { bits } => vec![chip.max(layouter, input(0)?, input(1)?, *bits)?],
+            Gadget::AbsDiff { bits } => {
+                vec![chip.abs_diff(layouter, input(0)?, input(1)?, *bits)?]
+            }
+            Gadget::AssertInRange { lo, hi } => {
+                chip.assert_in_range(layouter, input(0)?, *lo, *hi)?;
+                vec![]
+            }
+            Gadget::AssertSorted { bits } => {
+                chip.assert_sorted(layouter, inputs, *bits)?;
+                vec![]
+            }
+            Gadget::AtLeastKOfN { k } => {
+                let bits = inputs
+                    .iter()
+                    .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                vec![chip.at_least_k_of_n(layouter, &bits, *k)?.num()]
+            }
+            Gadget::Keccak256 => {
+                let bytes = inputs
+                    .iter()
+                    .map(|a| chip.byte(layouter.namespace(|| "byte"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                chip.keccak256(layouter, &bytes)?
+                    .iter()
+                    .map(|byte| byte.num())
+                    .collect()
+            }
+            Gadget::MimcPermute => vec![chip.permute(layouter, input(0)?, input(1)?)?],
+            Gadget::MimcFeistelHash => vec![chip.feistel_hash(layouter, inputs)?],
+            Gadget::PedersenCommit => {
+                let commitment =
+                    chip.commit(layouter.namespace(|| "commit"), input(0)?, input(1)?)?;
+                chip.open(
+                    layouter.namespace(|| "open"),
+                    &commitment,
+                    input(0)?,
+                    input(1)?,
+                )?;
+                vec![commitment.x, commitment.y]
+            }
+            Gadget::MimcMerkleDamgard => {
+                MerkleDamgard::construct(chip, chip).hash(layouter, inputs)?
+            }
+            Gadget::Transcript { squeezes } => {
+                let mut transcript = chip.init_transcript(layouter.namespace(|| "init"))?;
+                for a in inputs.iter() {
+                    chip.absorb(layouter.namespace(|| "absorb"), &mut transcript, a.clone())?;
+                }
+                (0..*squeezes)
+                    .map(|_| chip.squeeze(layouter.namespace(|| "squeeze"), &mut transcript))
+                    .collect::<Result<Vec<_>, Error>>()?
+            }
+            Gadget::MerkleRoot => vec![chip.compute_root(layouter, inputs)?],
+            Gadget::MerkleUpdate => {
+                let depth = inputs.len().saturating_sub(4) / 2;
+                let index = inputs[4..4 + depth]
+                    .iter()
+                    .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let path = MerklePath {
+                    index,
+                    siblings: inputs[4 + depth..].to_vec(),
+                };
+                chip.verify_update(layouter, input(0)?, input(1)?, input(2)?, input(3)?, &path)?;
+                vec![]
+            }
+            Gadget::AssertMember { set, bits } => {
+                chip.load_set_table(layouter.namespace(|| "set"), &field(set), *bits)?;
+                chip.assert_member(layouter, input(0)?)?;
+                vec![]
+            }
+            Gadget::AssertNotMember { set, bits } => {
+                let set = chip.load_set_table(layouter.namespace(|| "set"), &field(set), *bits)?;
+                chip.assert_not_member(layouter, &set, input(0)?)?;
+                vec![]
+            }
+            // the chip exposes the outputs itself
+            Gadget::Note => {
+                chip.commit_and_nullify(layouter, input(0)?, input(1)?, input(2)?, input(3)?, 0)?;
+                vec![]
+            }
+            Gadget::EccAdd => {
+                let sum = chip.add_complete(layouter, &point(0)?, &point(2)?)?;
+                vec![sum.x, sum.y]
+            }
+            Gadget::EccScalarMul { bits } | Gadget::EccMsm { bits } => {
+                let terms = inputs
+                    .chunks(2 + bits)
+                    .map(|term| {
+                        let bits = term[2..]
+                            .iter()
+                            .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        let p = Point {
+                            x: term[0].clone(),
+                            y: term[1].clone(),
+                        };
+                        Ok((bits, p))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let sum = match (self, terms.as_slice()) {
+                    (Gadget::EccScalarMul { .. }, [(bits, p)]) => {
+                        chip.scalar_mul(layouter, p, bits)?
+                    }
+                    (Gadget::EccMsm { .. }, _) => chip.msm(layouter, &terms)?,
+                    _ => return Err(Error::SynthesisError),
+                };
+                vec![sum.x, sum.y]
+            }
+            Gadget::Schnorr => {
+                let s = inputs[5..]
+                    .iter()
+                    .map(|a| chip.assert_bool(layouter.namespace(|| "bit"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let sig = Signature { r: point(3)?, s };
+                vec![chip
+                    .verify_schnorr(layouter, &point(0)?, input(2)?, &sig)?
+                    .num()]
+            }
+            Gadget::BigIntModExp { n, bits, e } => {
+                let bigint = BigIntChip::construct(chip.config().clone(), *bits)?;
+                let values: Vec<Option<u64>> = inputs
+                    .iter()
+                    .map(|a| a.value.map(|v| lower_64(&v)))
+                    .collect();
+                let a = bigint.load(layouter.namespace(|| "a"), &values[..*n])?;
+                let m = bigint.load(layouter.namespace(|| "m"), &values[*n..])?;
+                for (limb, number) in a.limbs().iter().chain(m.limbs()).zip(inputs.iter()) {
+                    chip.assert_equal(
+                        layouter.namespace(|| "input"),
+                        limb.clone(),
+                        number.clone(),
+                    )?;
+                }
+                bigint.mod_exp(layouter, &a, *e, &m)?.limbs().to_vec()
+            }
+            Gadget::RsaVerify => {
+                let rsa = RsaChip::construct(chip.config().clone(), SAMPLE_LIMB_BITS)?;
+                let bigint = BigIntChip::construct(chip.config().clone(), SAMPLE_LIMB_BITS)?;
+                let values: Vec<Option<u64>> = inputs[..2 * SAMPLE_LIMBS]
+                    .iter()
+                    .map(|a| a.value.map(|v| lower_64(&v)))
+                    .collect();
+                let n = bigint.load(layouter.namespace(|| "n"), &values[..SAMPLE_LIMBS])?;
+                let sig = bigint.load(layouter.namespace(|| "sig"), &values[SAMPLE_LIMBS..])?;
+                for (limb, number) in n.limbs().iter().chain(sig.limbs()).zip(inputs.iter()) {
+                    chip.assert_equal(
+                        layouter.namespace(|| "input"),
+                        limb.clone(),
+                        number.clone(),
+                    )?;
+                }
+                let digest = inputs[2 * SAMPLE_LIMBS..]
+                    .iter()
+                    .map(|a| chip.byte(layouter.namespace(|| "digest"), a.clone()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                vec![rsa
+                    .verify_rsa_digest(layouter, &n, SAMPLE_E, &sig, &digest)?
+                    .num()]
+            }
+            Gadget::ElGamalEncryptCommitted => {
+                let ct = chip.encrypt_committed(
+                    layouter,
+                    &point(0)?,
+                    &point(2)?,
+                    input(4)?,
+                    input(5)?,
+                    input(6)?,
+                )?;
+                vec![ct.c1.x, ct.c1.y, ct.c2.x, ct.c2.y]
+            }
+            Gadget::DynamicLookup { rows } => {
+                let values: Vec<Option<F>> = inputs[..3 * rows].iter().map(|a| a.value).collect();
+                let table = chip.load_dynamic_table(
+                    layouter.namespace(|| "table"),
+                    &values
+                        .chunks(3)
+                        .map(|row| [row[0], row[1], row[2]])
+                        .collect::<Vec<_>>(),
+                )?;
+                let query = [input(3 * rows)?, input(3 * rows + 1)?, input(3 * rows + 2)?];
+                chip.lookup_dynamic(layouter, &table, query)?;
+                vec![]
+            }
+        })
+    }
+}
+
+pub(crate) struct TestVector<F: FieldExt> {
+    pub(crate) gadget: Gadget,
+    pub(crate) inputs: Vec<F>,
+    pub(crate) outputs: Vec<F>,
+}
+
+// Returns the test vectors of all the instructions.
+pub(crate) fn generate<F: FieldExt>() -> Vec<TestVector<F>> {
+    let mut vectors = vec![];
+    for gadget in Gadget::all() {
+        for inputs in TestableGadget::<F>::samples(&gadget) {
+            vectors.push(TestVector {
+                outputs: gadget.reference(&inputs),
+                gadget: gadget.clone(),
+                inputs,
+            });
+        }
+    }
+    vectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::check;
+    use halo2::pasta::Fp;
+
+    #[test]
+    fn gadgets() {
+        for gadget in Gadget::all().iter().filter(|g| g.k() <= 13) {
+            check::<Fp, _>(gadget, gadget.k());
+        }
+    }
+
+    // The instructions over 2^16 rows or more take minutes to check, and the
+    // tests of their modules already check them.
+    #[test]
+    #[ignore]
+    fn large_gadgets() {
+        for gadget in Gadget::all().iter().filter(|g| g.k() > 13) {
+            check::<Fp, _>(gadget, gadget.k());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_hex<F: FieldExt>(f: &F) -> String {
+        let hex: String = f
+            .to_bytes()
+            .iter()
+            .rev()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        format!("0x{}", hex)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_json<F: FieldExt>(vector: &TestVector<F>) -> serde_json::Value {
+        let mut json = serde_json::to_value(&vector.gadget).unwrap();
+        json["inputs"] = vector.inputs.iter().map(to_hex).collect();
+        json["outputs"] = vector.outputs.iter().map(to_hex).collect();
+        json
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let vector = &generate::<Fp>()[5];
+        assert_eq!(
+            to_json(vector),
+            serde_json::json!({
+                "gadget": "checked_add",
+                "n_bits": 8,
+                "inputs": [
+                    "0x00000000000000000000000000000000000000000000000000000000000000c8",
+                    "0x0000000000000000000000000000000000000000000000000000000000000064",
+                ],
+                "outputs": [
+                    "0x000000000000000000000000000000000000000000000000000000000000002c",
+                    "0x0000000000000000000000000000000000000000000000000000000000000001",
+                ],
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    #[ignore]
+    fn export_vectors() {
+        let path = std::env::var("TEST_VECTORS").unwrap_or_else(|_| "vectors.json".to_string());
+        let vectors: Vec<_> = generate::<Fp>().iter().map(to_json).collect();
+        std::fs::write(path, serde_json::to_string_pretty(&vectors).unwrap()).unwrap();
+    }
+}
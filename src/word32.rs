@@ -0,0 +1,111 @@
+// 32-bit words over FieldChip, the layer SHA-256 style gadgets are built on.
+// A Word32 is a Number known to fit in 32 bits: it is only obtained by range
+// checking a Number, or as the output of an operation on words, so the
+// operations never need to check their operands again.
+// * `add_mod32` is the checked addition, dropping the carry,
+// * `xor32` is the wide XOR over the bitwise table,
+// * `rotr32` is the rotation of the bits of the word.
+use super::{FieldChip, Number, NumericInstructions};
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+
+#[derive(Clone)]
+pub(crate) struct Word32<F: FieldExt>(Number<F>);
+
+impl<F: FieldExt> Word32<F> {
+    pub(crate) fn num(&self) -> Number<F> {
+        self.0.clone()
+    }
+}
+
+pub(crate) trait Word32Instructions<F: FieldExt> {
+    /// Loads a private word, range checked to 32 bits.
+    fn load_word(&self, layouter: impl Layouter<F>, value: Option<u32>)
+        -> Result<Word32<F>, Error>;
+
+    /// Constrains `a` to fit in 32 bits and returns it as a word.
+    fn word(&self, layouter: impl Layouter<F>, a: Number<F>) -> Result<Word32<F>, Error>;
+
+    /// Returns `(a + b mod 2^32, carry)`.
+    fn add_mod32_carry(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        b: Word32<F>,
+    ) -> Result<(Word32<F>, Number<F>), Error>;
+
+    /// Returns `a + b mod 2^32`.
+    fn add_mod32(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        b: Word32<F>,
+    ) -> Result<Word32<F>, Error> {
+        Ok(self.add_mod32_carry(layouter, a, b)?.0)
+    }
+
+    /// Returns `a ^ b`.
+    fn xor32(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        b: Word32<F>,
+    ) -> Result<Word32<F>, Error>;
+
+    /// Returns `a` rotated right by `n` bits.
+    fn rotr32(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        n: usize,
+    ) -> Result<Word32<F>, Error>;
+}
+
+impl<F: FieldExt> Word32Instructions<F> for FieldChip<F> {
+    fn load_word(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<u32>,
+    ) -> Result<Word32<F>, Error> {
+        let a = self.load_private(
+            layouter.namespace(|| "word"),
+            value.map(|v| F::from(v as u64)),
+        )?;
+        self.word(layouter.namespace(|| "range"), a)
+    }
+
+    fn word(&self, layouter: impl Layouter<F>, a: Number<F>) -> Result<Word32<F>, Error> {
+        self.range_check_running_sum(layouter, a.clone(), 32)?;
+        Ok(Word32(a))
+    }
+
+    fn add_mod32_carry(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        b: Word32<F>,
+    ) -> Result<(Word32<F>, Number<F>), Error> {
+        // the sum is decomposed on 32 bits, so it is a word
+        let (sum, carry) = self.checked_add(layouter, a.0, b.0, 32)?;
+        Ok((Word32(sum), carry))
+    }
+
+    fn xor32(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        b: Word32<F>,
+    ) -> Result<Word32<F>, Error> {
+        // recomposed from chunks of the table size, so it is a word
+        Ok(Word32(self.xor_wide(layouter, a.0, b.0, 32)?))
+    }
+
+    fn rotr32(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Word32<F>,
+        n: usize,
+    ) -> Result<Word32<F>, Error> {
+        // recomposed from 32 bits, so it is a word
+        Ok(Word32(self.rotr(layouter, a.0, n, 32)?))
+    }
+}